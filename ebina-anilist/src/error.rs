@@ -0,0 +1,66 @@
+use std::fmt::Display;
+
+use chrono::{DateTime, Utc};
+use cynic::GraphQlError;
+use reqwest::StatusCode;
+
+/// Errors returned by the AniList API functions.
+#[derive(Debug)]
+pub enum AniListError {
+	/// The request never got a response, or the body could not be read.
+	Transport(reqwest::Error),
+	/// AniList responded with a non-success status and no usable GraphQL body.
+	Http {
+		status: StatusCode,
+		body: String,
+	},
+	/// AniList is rate limiting us, `reset` is when requests are allowed again if it was sent.
+	RateLimited {
+		reset: Option<DateTime<Utc>>,
+	},
+	/// The query was executed but AniList returned GraphQL errors.
+	GraphQl(Vec<GraphQlError>),
+	/// The response could not be decoded into the query type.
+	Decode(serde_json::Error),
+	/// The response contained no data.
+	NoData,
+}
+
+impl Display for AniListError {
+	fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+		match self {
+			AniListError::Transport(e) => write!(f, "error sending request to AniList: {}", e),
+			AniListError::Http { status, body } => write!(f, "AniList returned {}: {}", status, body),
+			AniListError::RateLimited { reset: Some(reset) } => write!(f, "rate limited by AniList until {}", reset),
+			AniListError::RateLimited { reset: None } => f.write_str("rate limited by AniList"),
+			AniListError::GraphQl(errors) => {
+				let messages = errors.iter().map(|e| e.message.as_str()).collect::<Vec<_>>();
+				write!(f, "AniList returned errors: {}", messages.join(", "))
+			}
+			AniListError::Decode(e) => write!(f, "could not decode AniList response: {}", e),
+			AniListError::NoData => f.write_str("AniList returned no data"),
+		}
+	}
+}
+
+impl std::error::Error for AniListError {
+	fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+		match self {
+			AniListError::Transport(e) => Some(e),
+			AniListError::Decode(e) => Some(e),
+			_ => None,
+		}
+	}
+}
+
+impl From<reqwest::Error> for AniListError {
+	fn from(e: reqwest::Error) -> Self {
+		AniListError::Transport(e)
+	}
+}
+
+impl From<serde_json::Error> for AniListError {
+	fn from(e: serde_json::Error) -> Self {
+		AniListError::Decode(e)
+	}
+}
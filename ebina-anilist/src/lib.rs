@@ -1,33 +1,92 @@
 //use graphql_client::{GraphQLQuery, Response};
-use cynic::{QueryBuilder, http::ReqwestExt};
-use queries::queries::MediaType;
-use std::{error::Error, ops::{Sub, Add}};
+use cynic::{QueryBuilder, MutationBuilder, GraphQlResponse, Operation};
+use futures::stream::{Stream, TryStreamExt};
+use queries::queries::{MediaType, MediaSeason, MediaFormat, MediaSort, MediaListStatus, Media, Media4, MediaList, AiringSchedule, Character};
+use reqwest::{StatusCode, header::HeaderMap};
+use serde::de::DeserializeOwned;
+use std::ops::{Sub, Add};
 
 const GQL_URL: &str = "https://graphql.anilist.co/";
 
+pub mod error;
+pub mod pagination;
 pub mod queries;
+pub mod transport;
 
-pub async fn search<S>(title: S) -> Result<queries::queries::MediaSearch, Box<dyn Error>> where
+pub use error::AniListError;
+pub use pagination::{paginate, Paged};
+use transport::{HttpTransport, RawResponse, Transport};
+
+/// Sends the operation to AniList and decodes the response, turning rate limits,
+/// HTTP failures and GraphQL errors into an [`AniListError`].
+async fn run<ResponseData>(operation: Operation<'_, ResponseData>) -> Result<ResponseData, AniListError> where
+ResponseData: DeserializeOwned {
+	run_with(&HttpTransport::default(), None, operation).await
+}
+
+/// Like [`run`], but sends the operation through `transport`, authenticated as the owner of `token` if given.
+pub async fn run_with<ResponseData>(transport: &dyn Transport, token: Option<&str>, operation: Operation<'_, ResponseData>) -> Result<ResponseData, AniListError> where
+ResponseData: DeserializeOwned {
+	let body = serde_json::to_string(&operation)?;
+	let response = transport.send(body, token).await?;
+	decode_response(response)
+}
+
+/// Decodes a raw response into the query type.
+fn decode_response<ResponseData>(response: RawResponse) -> Result<ResponseData, AniListError> where
+ResponseData: DeserializeOwned {
+	let RawResponse { status, headers, body } = response;
+	if status == StatusCode::TOO_MANY_REQUESTS {
+		return Err(AniListError::RateLimited {
+			reset: rate_limit_reset(&headers),
+		});
+	}
+	let response = match serde_json::from_str::<GraphQlResponse<ResponseData>>(&body) {
+		Ok(v) => v,
+		// AniList sends GraphQL errors with non-success statuses, only fall back to the status if there are none
+		Err(_) if !status.is_success() => return Err(AniListError::Http { status, body }),
+		Err(why) => return Err(why.into()),
+	};
+	if let Some(errors) = response.errors {
+		// Single item queries like `User` answer a missing item with a 404 and a "Not Found." error
+		if status == StatusCode::NOT_FOUND {
+			return Err(AniListError::NoData);
+		}
+		if !errors.is_empty() {
+			return Err(AniListError::GraphQl(errors));
+		}
+	}
+	response.data.ok_or(AniListError::NoData)
+}
+
+/// Reads when the rate limit resets from the response headers.
+fn rate_limit_reset(headers: &HeaderMap) -> Option<chrono::DateTime<chrono::Utc>> {
+	let header = |name: &str| headers.get(name)?.to_str().ok()?.parse::<i64>().ok();
+	if let Some(reset) = header("x-ratelimit-reset") {
+		return Some(chrono::DateTime::<chrono::Utc>::from_utc(chrono::NaiveDateTime::from_timestamp(reset, 0), chrono::Utc));
+	}
+	header("retry-after").map(|secs| chrono::Utc::now().add(chrono::Duration::seconds(secs)))
+}
+
+pub async fn search<S>(title: S) -> Result<queries::queries::MediaSearch, AniListError> where
 S: Into<String> {
 	use queries::queries::{MediaSearch, MediaSearchArguments};
 	let arguments = MediaSearchArguments {
+		page: Some(1),
 		title: Some(title.into())
 	};
 	let operation = MediaSearch::build(arguments);
 	/*for (key, val) in &operation.variables {
 		println!("key: {} val: {:?}", key, val.name);
 	}*/
-	let client = reqwest::Client::new();
-	let response = client.post(GQL_URL)
-	.run_graphql(operation)
-	.await?;
-	Ok(response.data.unwrap())
+	run(operation).await
 }
 
-pub async fn search_specific<S>(title: S, media_type: Option<MediaType>) -> Result<queries::queries::MediaSpecific, Box<dyn Error>> where
+pub async fn search_specific<S>(title: S, media_type: Option<MediaType>) -> Result<queries::queries::MediaSpecific, AniListError> where
 S: Into<String> {
 	use queries::queries::{MediaSpecific, MediaSpecificArguments};
 	let arguments = MediaSpecificArguments {
+		page: Some(1),
 		title: Some(title.into()),
     	r#type: media_type,
 	};
@@ -35,34 +94,250 @@ S: Into<String> {
 	/*for (key, val) in &operation.variables {
 		println!("key: {} val: {:?}", key, val.name);
 	}*/
-	let client = reqwest::Client::new();
-	let response = client.post(GQL_URL)
-	.run_graphql(operation)
-	.await?;
-	Ok(response.data.unwrap())
+	run(operation).await
+}
+
+pub async fn search_character<S>(name: S) -> Result<queries::queries::CharacterSearch, AniListError> where
+S: Into<String> {
+	use queries::queries::{CharacterSearch, CharacterSearchArguments};
+	let arguments = CharacterSearchArguments {
+		page: Some(1),
+		name: Some(name.into()),
+	};
+	let operation = CharacterSearch::build(arguments);
+	run(operation).await
+}
+
+pub async fn search_staff<S>(name: S) -> Result<queries::queries::StaffSearch, AniListError> where
+S: Into<String> {
+	use queries::queries::{StaffSearch, StaffSearchArguments};
+	let arguments = StaffSearchArguments {
+		page: Some(1),
+		name: Some(name.into()),
+	};
+	let operation = StaffSearch::build(arguments);
+	run(operation).await
+}
+
+pub async fn search_studio<S>(name: S) -> Result<queries::queries::StudioSearch, AniListError> where
+S: Into<String> {
+	use queries::queries::{StudioSearch, StudioSearchArguments};
+	let arguments = StudioSearchArguments {
+		page: Some(1),
+		name: Some(name.into()),
+	};
+	let operation = StudioSearch::build(arguments);
+	run(operation).await
+}
+
+pub async fn get_media(id: i32) -> Result<queries::queries::MediaById, AniListError> {
+	use queries::queries::{MediaById, MediaByIdArguments};
+	let arguments = MediaByIdArguments {
+		id: Some(id),
+	};
+	let operation = MediaById::build(arguments);
+	run(operation).await
+}
+
+/// Gets the user an OAuth access token belongs to.
+pub async fn get_viewer(token: &str) -> Result<queries::queries::CurrentUser, AniListError> {
+	use queries::queries::CurrentUser;
+	let operation = CurrentUser::build(());
+	run_with(&HttpTransport::default(), Some(token), operation).await
+}
+
+/// Creates or updates a list entry of the user the access token belongs to, fields left as `None` are unchanged.
+pub async fn save_media_list_entry(token: &str, entry: queries::queries::SaveMediaListEntryArguments) -> Result<queries::queries::SaveMediaListEntry, AniListError> {
+	use queries::queries::SaveMediaListEntry;
+	let operation = SaveMediaListEntry::build(entry);
+	run_with(&HttpTransport::default(), Some(token), operation).await
+}
+
+pub async fn get_user<S>(name: S) -> Result<queries::queries::UserProfile, AniListError> where
+S: Into<String> {
+	use queries::queries::{UserProfile, UserProfileArguments};
+	let arguments = UserProfileArguments {
+		name: Some(name.into()),
+	};
+	let operation = UserProfile::build(arguments);
+	run(operation).await
+}
+
+/// Returns the season and season year the date falls in.
+pub fn current_season<Tz: chrono::TimeZone>(date: chrono::DateTime<Tz>) -> (MediaSeason, i32) {
+	use chrono::Datelike;
+	let season = match date.month() {
+		1..=3 => MediaSeason::Winter,
+		4..=6 => MediaSeason::Spring,
+		7..=9 => MediaSeason::Summer,
+		_ => MediaSeason::Fall,
+	};
+	(season, date.year())
+}
+
+/// Gets one page of anime airing in a season.
+pub async fn get_season(season: MediaSeason, year: i32, formats: Vec<MediaFormat>, sort: MediaSort, page: i32) -> Result<queries::queries::SeasonChart, AniListError> {
+	use queries::queries::{SeasonChart, SeasonArguments};
+	let arguments = SeasonArguments {
+		page: Some(page),
+		season: Some(season),
+		season_year: Some(year),
+		formats: if formats.is_empty() { None } else { Some(formats.into_iter().map(Some).collect()) },
+		sort: Some(vec![Some(sort)]),
+	};
+	let operation = SeasonChart::build(arguments);
+	run(operation).await
+}
+
+/// Gets every anime airing in a season.
+pub async fn get_full_season(season: MediaSeason, year: i32, formats: Vec<MediaFormat>, sort: MediaSort) -> Result<Vec<Media4>, AniListError> {
+	season_stream(season, year, formats, sort).try_collect().await
+}
+
+/// Streams every anime airing in a season, fetching the pages as they're needed.
+pub fn season_stream(season: MediaSeason, year: i32, formats: Vec<MediaFormat>, sort: MediaSort) -> impl Stream<Item = Result<Media4, AniListError>> {
+	paginate(move |page| {
+		let formats = formats.clone();
+		async move {
+			let chart = get_season(season, year, formats, sort, page).await?;
+			Ok(chart.page.map(|p| Paged::new(p.media, p.page_info)).unwrap_or_else(Paged::empty))
+		}
+	})
+}
+
+/// Streams every media matching the search, of one type if `media_type` is given.
+pub fn search_stream(title: String, media_type: Option<MediaType>) -> impl Stream<Item = Result<Media, AniListError>> {
+	use queries::queries::{MediaSpecific, MediaSpecificArguments};
+	paginate(move |page| {
+		let operation = MediaSpecific::build(MediaSpecificArguments {
+			page: Some(page),
+			title: Some(title.clone()),
+			r#type: media_type,
+		});
+		async move {
+			let results = run(operation).await?;
+			Ok(results.page.map(|p| Paged::new(p.media, p.page_info)).unwrap_or_else(Paged::empty))
+		}
+	})
+}
+
+/// Streams every character matching the search.
+pub fn character_stream(name: String) -> impl Stream<Item = Result<Character, AniListError>> {
+	use queries::queries::{CharacterSearch, CharacterSearchArguments};
+	paginate(move |page| {
+		let operation = CharacterSearch::build(CharacterSearchArguments {
+			page: Some(page),
+			name: Some(name.clone()),
+		});
+		async move {
+			let results = run(operation).await?;
+			Ok(results.page.map(|p| Paged::new(p.characters, p.page_info)).unwrap_or_else(Paged::empty))
+		}
+	})
+}
+
+/// Streams the list entries of a user, optionally only of one media type or status.
+pub fn user_list_stream(user_name: String, media_type: Option<MediaType>, status: Option<MediaListStatus>) -> impl Stream<Item = Result<MediaList, AniListError>> {
+	use queries::queries::{UserList, UserListArguments};
+	paginate(move |page| {
+		let operation = UserList::build(UserListArguments {
+			page: Some(page),
+			user_name: Some(user_name.clone()),
+			r#type: media_type,
+			status,
+		});
+		async move {
+			let list = run(operation).await?;
+			Ok(list.page.map(|p| Paged::new(p.media_list, p.page_info)).unwrap_or_else(Paged::empty))
+		}
+	})
 }
 
-pub async fn get_schedule(date_utc: chrono::DateTime<chrono::Utc>) -> Result<queries::queries::Schedule, Box<dyn Error>> {
+/// Gets a page of media matching the filters, an `is_adult` of `Some(false)` leaves out adult media.
+pub async fn discover(media_type: Option<MediaType>, sort: MediaSort, genres: Vec<String>, tags: Vec<String>, is_adult: Option<bool>, page: i32, per_page: i32) -> Result<queries::queries::Discover, AniListError> {
+	use queries::queries::{Discover, DiscoverArguments};
+	let arguments = DiscoverArguments {
+		page: Some(page),
+		per_page: Some(per_page),
+		r#type: media_type,
+		sort: Some(vec![Some(sort)]),
+		genres: if genres.is_empty() { None } else { Some(genres.into_iter().map(Some).collect()) },
+		tags: if tags.is_empty() { None } else { Some(tags.into_iter().map(Some).collect()) },
+		is_adult,
+	};
+	let operation = Discover::build(arguments);
+	run(operation).await
+}
+
+/// Picks a random media matching the filters, `None` if nothing matches.
+pub async fn get_random_media(media_type: Option<MediaType>, genres: Vec<String>, tags: Vec<String>, is_adult: Option<bool>) -> Result<Option<Media4>, AniListError> {
+	use rand::Rng;
+	let first = discover(media_type, MediaSort::PopularityDesc, genres.clone(), tags.clone(), is_adult, 1, 1).await?;
+	// AniList doesn't page past the first 5000 results
+	let total = first.page.and_then(|p| p.page_info).and_then(|p| p.total).unwrap_or(0).min(5000);
+	if total <= 0 {
+		return Ok(None);
+	}
+	let page = rand::thread_rng().gen_range(1..=total);
+	let random = discover(media_type, MediaSort::PopularityDesc, genres, tags, is_adult, page, 1).await?;
+	Ok(random.page.and_then(|p| p.media).unwrap_or_default().into_iter().flatten().next())
+}
+
+/// Gets every genre and tag AniList knows about.
+pub async fn get_collections() -> Result<queries::queries::Collections, AniListError> {
+	use queries::queries::Collections;
+	let operation = Collections::build(());
+	run(operation).await
+}
+
+/// Gets one page of the episodes airing between two unix timestamps.
+pub async fn get_schedule_page(airing_at_greater: i32, airing_at_lesser: i32, page: i32) -> Result<queries::queries::Schedule, AniListError> {
 	use queries::queries::{Schedule, ScheduleArguments};
+	let arguments = ScheduleArguments {
+		page: Some(page),
+		airing_at_greater: Some(airing_at_greater),
+		airing_at_lesser: Some(airing_at_lesser),
+	};
+	let operation = Schedule::build(arguments);
+	run(operation).await
+}
+
+/// Gets every episode airing between `from` and `to`.
+pub async fn get_schedule_range(from: chrono::DateTime<chrono::Utc>, to: chrono::DateTime<chrono::Utc>) -> Result<Vec<AiringSchedule>, AniListError> {
+	schedule_stream(from, to).try_collect().await
+}
+
+/// Streams every episode airing between `from` and `to`, fetching the pages as they're needed.
+pub fn schedule_stream(from: chrono::DateTime<chrono::Utc>, to: chrono::DateTime<chrono::Utc>) -> impl Stream<Item = Result<AiringSchedule, AniListError>> {
+	paginate(move |page| async move {
+		let schedule = get_schedule_page(from.timestamp() as i32, to.timestamp() as i32, page).await?;
+		Ok(schedule.page.map(|p| Paged::new(p.airing_schedules, p.page_info)).unwrap_or_else(Paged::empty))
+	})
+}
+
+/// Gets every episode airing on the UTC day of `date_utc`.
+pub async fn get_schedule(date_utc: chrono::DateTime<chrono::Utc>) -> Result<Vec<AiringSchedule>, AniListError> {
 	// Todays NaiveDatein UTC clamping to the start of the day
 	let date_utc_clamped = date_utc.date().and_hms(0, 0, 0);
 	// Todays dateTime in UTC clamping to the end of the day
 	let date_utc_clamped_end = date_utc_clamped.add(chrono::Duration::days(1)).sub(chrono::Duration::seconds(1));
-	let arguments = ScheduleArguments {
-		airing_at_greater: Some(date_utc_clamped.timestamp() as i32),
-		airing_at_lesser: Some(date_utc_clamped_end.timestamp() as i32),
-	};
-	let operation = Schedule::build(arguments);
-	let client = reqwest::Client::new();
-	let response = client.post(GQL_URL)
-		.run_graphql(operation)
-		.await?;
-	Ok(response.data.unwrap())
+	get_schedule_range(date_utc_clamped, date_utc_clamped_end).await
 }
 
 #[cfg(test)]
 mod tests {
+	use super::transport::FixtureTransport;
+	use futures::stream::StreamExt;
+
+	/// A recorded AniList response from the `fixtures` directory.
+	macro_rules! fixture {
+		($name:literal) => {
+			include_str!(concat!("../fixtures/", $name))
+		};
+	}
+
 	#[tokio::test]
+	#[ignore = "talks to the live AniList API"]
 	async fn search_manga() {
 		use super::*;
 		let manga_res = search("To love Ru").await.unwrap();
@@ -70,4 +345,507 @@ mod tests {
 		assert_eq!(manga[0].as_ref().unwrap().title.as_ref().unwrap().romaji.as_ref().unwrap(), "To LOVE-Ru");
 		println!("{:?}", manga[0].as_ref().unwrap());
 	}
+
+	#[test]
+	fn current_season_boundaries() {
+		use super::*;
+		use chrono::TimeZone;
+		assert_eq!(current_season(chrono::Utc.ymd(2022, 1, 1).and_hms(0, 0, 0)), (MediaSeason::Winter, 2022));
+		assert_eq!(current_season(chrono::Utc.ymd(2022, 6, 30).and_hms(0, 0, 0)), (MediaSeason::Spring, 2022));
+		assert_eq!(current_season(chrono::Utc.ymd(2022, 7, 1).and_hms(0, 0, 0)), (MediaSeason::Summer, 2022));
+		assert_eq!(current_season(chrono::Utc.ymd(2022, 12, 31).and_hms(0, 0, 0)), (MediaSeason::Fall, 2022));
+	}
+
+	/// Answers a single request on a local port with the given response and hands back the raw request.
+	async fn mock_server(status: &'static str, headers: &'static str, body: &'static str) -> (String, tokio::task::JoinHandle<String>) {
+		use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+		let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+		let url = format!("http://{}/", listener.local_addr().unwrap());
+		let handle = tokio::spawn(async move {
+			let (mut socket, _) = listener.accept().await.unwrap();
+			let mut request = Vec::new();
+			let mut buf = [0u8; 4096];
+			loop {
+				let n = socket.read(&mut buf).await.unwrap();
+				if n == 0 {
+					break;
+				}
+				request.extend_from_slice(&buf[..n]);
+				// Stop once the headers and the whole body are in
+				if let Some(end) = request.windows(4).position(|w| w == b"\r\n\r\n") {
+					let head = String::from_utf8_lossy(&request[..end]).to_lowercase();
+					let length = head.lines()
+						.find_map(|l| l.strip_prefix("content-length:"))
+						.and_then(|v| v.trim().parse::<usize>().ok())
+						.unwrap_or(0);
+					if request.len() >= end + 4 + length {
+						break;
+					}
+				}
+			}
+			let response = format!(
+				"HTTP/1.1 {}\r\ncontent-type: application/json\r\ncontent-length: {}\r\nconnection: close\r\n{}\r\n{}",
+				status, body.len(), headers, body
+			);
+			socket.write_all(response.as_bytes()).await.unwrap();
+			String::from_utf8_lossy(&request).to_string()
+		});
+		(url, handle)
+	}
+
+	#[tokio::test]
+	async fn save_media_list_entry_mutation() {
+		use super::*;
+		use queries::queries::{MediaListStatus, SaveMediaListEntry, SaveMediaListEntryArguments};
+
+		let (url, request) = mock_server("200 OK", "", fixture!("save_media_list_entry.json")).await;
+		let operation = SaveMediaListEntry::build(SaveMediaListEntryArguments {
+			media_id: Some(140960),
+			status: Some(MediaListStatus::Current),
+			progress: Some(10),
+			score_raw: None,
+		});
+		let saved = run_with(&HttpTransport::new(url), Some("secret"), operation).await.unwrap();
+		let entry = saved.save_media_list_entry.unwrap();
+		assert_eq!(entry.media_id, 140960);
+		assert_eq!(entry.progress, Some(10));
+		assert_eq!(entry.score, Some(8.5));
+		assert_eq!(entry.status, Some(MediaListStatus::Current));
+
+		let request = request.await.unwrap();
+		assert!(request.to_lowercase().contains("authorization: bearer secret"));
+		assert!(request.to_lowercase().contains("content-type: application/json"));
+		assert!(request.contains("mutation"));
+		assert!(request.contains("SaveMediaListEntry"));
+	}
+
+	#[tokio::test]
+	async fn search_sends_variables() {
+		use super::*;
+		use queries::queries::{MediaSearch, MediaSearchArguments};
+
+		let transport = FixtureTransport::new().respond(200, fixture!("media_search.json"));
+		let operation = MediaSearch::build(MediaSearchArguments {
+			page: Some(1),
+			title: Some("Spy x Family".to_string()),
+		});
+		run_with(&transport, None, operation).await.unwrap();
+
+		let requests = transport.requests();
+		assert_eq!(requests.len(), 1);
+		let (body, token) = &requests[0];
+		assert!(token.is_none());
+		let body: serde_json::Value = serde_json::from_str(body).unwrap();
+		assert!(body["query"].as_str().unwrap().starts_with("query Query("));
+		assert_eq!(body["variables"]["_0"], 1);
+		assert_eq!(body["variables"]["_1"], "Spy x Family");
+	}
+
+	#[tokio::test]
+	async fn decode_media_search() {
+		use super::*;
+		use queries::queries::{MediaSearch, MediaSearchArguments, MediaFormat, MediaSeason, MediaRelation};
+
+		let transport = FixtureTransport::new().respond(200, fixture!("media_search.json"));
+		let operation = MediaSearch::build(MediaSearchArguments { page: Some(1), title: None });
+		let search = run_with(&transport, None, operation).await.unwrap();
+		let page = search.page.unwrap();
+		assert!(!page.page_info.unwrap().has_next_page.unwrap());
+		let media = page.media.unwrap();
+		assert_eq!(media.len(), 2);
+
+		let spy = media[0].as_ref().unwrap();
+		assert_eq!(spy.id, 140960);
+		assert!(matches!(spy.type_, Some(MediaType::Anime)));
+		assert_eq!(spy.title.as_ref().unwrap().english.as_deref(), Some("SPY x FAMILY"));
+		assert_eq!(spy.format, Some(MediaFormat::Tv));
+		assert_eq!(spy.season, Some(MediaSeason::Spring));
+		assert_eq!(spy.genres.as_ref().unwrap().len(), 3);
+		assert_eq!(spy.next_airing_episode.as_ref().unwrap().episode, 11);
+		assert_eq!(spy.studios.as_ref().unwrap().nodes.as_ref().unwrap()[0].as_ref().unwrap().name, "Wit Studio");
+		let relation = spy.relations.as_ref().unwrap().edges.as_ref().unwrap()[0].as_ref().unwrap();
+		assert_eq!(relation.relation_type, Some(MediaRelation::Source));
+		assert_eq!(relation.node.as_ref().unwrap().id, 108556);
+		assert_eq!(spy.trailer.as_ref().unwrap().url().as_deref(), Some("https://www.youtube.com/watch?v=ofXigq9aIpo"));
+		let director = spy.staff.as_ref().unwrap().edges.as_ref().unwrap()[0].as_ref().unwrap();
+		assert_eq!(director.role.as_deref(), Some("Director"));
+		assert_eq!(director.node.as_ref().unwrap().name.as_ref().unwrap().full.as_deref(), Some("Kazuhiro Furuhashi"));
+	}
+
+	#[tokio::test]
+	async fn decode_nullable_fields() {
+		use super::*;
+		use queries::queries::{MediaSearch, MediaSearchArguments};
+
+		let transport = FixtureTransport::new().respond(200, fixture!("media_search.json"));
+		let operation = MediaSearch::build(MediaSearchArguments { page: Some(1), title: None });
+		let media = run_with(&transport, None, operation).await.unwrap().page.unwrap().media.unwrap();
+
+		// The full entry still has a few holes
+		let spy = media[0].as_ref().unwrap();
+		assert!(spy.volumes.is_none());
+		assert!(spy.chapters.is_none());
+		assert_eq!(spy.end_date.as_ref().unwrap().to_string(), "");
+		let recommendations = spy.recommendations.as_ref().unwrap().nodes.as_ref().unwrap();
+		assert!(recommendations[1].as_ref().unwrap().media_recommendation.is_none());
+
+		// A brand new entry has next to nothing set
+		let sparse = media[1].as_ref().unwrap();
+		let title = sparse.title.as_ref().unwrap();
+		assert!(title.english.is_none());
+		assert!(title.native.is_none());
+		assert!(sparse.episodes.is_none());
+		assert!(sparse.status.is_none());
+		assert!(sparse.format.is_none());
+		assert!(sparse.cover_image.is_none());
+		assert!(sparse.description.is_none());
+		assert!(sparse.next_airing_episode.is_none());
+		assert!(sparse.trailer.is_none());
+		assert!(sparse.genres.as_ref().unwrap().is_empty());
+		assert!(sparse.staff.as_ref().unwrap().edges.as_ref().unwrap().is_empty());
+	}
+
+	#[tokio::test]
+	async fn decode_media_by_id() {
+		use super::*;
+		use queries::queries::{MediaById, MediaByIdArguments};
+
+		let transport = FixtureTransport::new().respond(200, fixture!("media_by_id.json"));
+		let operation = MediaById::build(MediaByIdArguments { id: Some(140960) });
+		let media = run_with(&transport, None, operation).await.unwrap().media.unwrap();
+		assert_eq!(media.id, 140960);
+		assert_eq!(media.external_links.unwrap().len(), 2);
+	}
+
+	#[tokio::test]
+	async fn decode_season_pages() {
+		use super::*;
+		use queries::queries::{SeasonChart, SeasonArguments, MediaRankType};
+
+		let transport = FixtureTransport::new()
+			.respond(200, fixture!("season_page_1.json"))
+			.respond(200, fixture!("season_page_2.json"));
+		let arguments = |page| SeasonArguments {
+			page: Some(page),
+			season: Some(MediaSeason::Spring),
+			season_year: Some(2022),
+			formats: None,
+			sort: Some(vec![Some(MediaSort::PopularityDesc)]),
+		};
+
+		let first = run_with(&transport, None, SeasonChart::build(arguments(1))).await.unwrap().page.unwrap();
+		let info = first.page_info.unwrap();
+		assert_eq!(info.total, Some(3));
+		assert_eq!(info.current_page, Some(1));
+		assert_eq!(info.has_next_page, Some(true));
+		let media = first.media.unwrap();
+		assert_eq!(media.len(), 2);
+		let kaguya = media[0].as_ref().unwrap();
+		let ranking = kaguya.rankings.as_ref().unwrap().iter().flatten().find(|r| r.all_time == Some(true)).unwrap();
+		assert_eq!(ranking.type_, MediaRankType::Rated);
+		assert_eq!(ranking.rank, 12);
+
+		let second = run_with(&transport, None, SeasonChart::build(arguments(2))).await.unwrap().page.unwrap();
+		assert_eq!(second.page_info.unwrap().has_next_page, Some(false));
+		let last = second.media.unwrap().into_iter().flatten().next().unwrap();
+		assert!(last.rankings.is_none());
+		assert!(last.cover_image.unwrap().color.is_none());
+		let start = last.start_date.unwrap();
+		assert!(start.day.is_none());
+		assert_eq!(start.month, Some(7));
+
+		// Both pages were asked for, in order
+		let requests = transport.requests();
+		assert_eq!(requests.len(), 2);
+		let page = |body: &str| serde_json::from_str::<serde_json::Value>(body).unwrap()["variables"]["_0"].clone();
+		assert_eq!(page(&requests[0].0), 1);
+		assert_eq!(page(&requests[1].0), 2);
+	}
+
+	#[tokio::test]
+	async fn decode_schedule() {
+		use super::*;
+		use queries::queries::{Schedule, ScheduleArguments};
+
+		let transport = FixtureTransport::new().respond(200, fixture!("schedule.json"));
+		let operation = Schedule::build(ScheduleArguments {
+			page: Some(1),
+			airing_at_greater: Some(1655510400),
+			airing_at_lesser: Some(1655596799),
+		});
+		let airings = run_with(&transport, None, operation).await.unwrap().page.unwrap().airing_schedules.unwrap();
+		let first = airings[0].as_ref().unwrap();
+		assert_eq!(first.episode, 11);
+		assert_eq!(first.airing_at, 1655565000);
+		assert_eq!(first.media.as_ref().unwrap().id, 140960);
+		// Media hidden from the schedule, like adult entries for logged out users, come back as null
+		assert!(airings[1].as_ref().unwrap().media.is_none());
+	}
+
+	#[tokio::test]
+	async fn decode_discover() {
+		use super::*;
+		use queries::queries::{Discover, DiscoverArguments};
+
+		let transport = FixtureTransport::new().respond(200, fixture!("discover.json"));
+		let operation = Discover::build(DiscoverArguments {
+			page: Some(1),
+			per_page: Some(2),
+			r#type: Some(MediaType::Anime),
+			sort: Some(vec![Some(MediaSort::TrendingDesc)]),
+			genres: None,
+			tags: None,
+			is_adult: Some(false),
+		});
+		let page = run_with(&transport, None, operation).await.unwrap().page.unwrap();
+		assert_eq!(page.page_info.unwrap().total, Some(5000));
+		let media = page.media.unwrap();
+		assert!(media[1].as_ref().unwrap().average_score.is_none());
+	}
+
+	#[tokio::test]
+	async fn decode_character_staff_and_studio() {
+		use super::*;
+		use queries::queries::{CharacterSearch, CharacterSearchArguments, StaffSearch, StaffSearchArguments, StudioSearch, StudioSearchArguments, CharacterRole};
+
+		let transport = FixtureTransport::new().respond(200, fixture!("character_search.json"));
+		let operation = CharacterSearch::build(CharacterSearchArguments { page: Some(1), name: Some("Anya".to_string()) });
+		let characters = run_with(&transport, None, operation).await.unwrap().page.unwrap().characters.unwrap();
+		let anya = characters[0].as_ref().unwrap();
+		assert_eq!(anya.name.as_ref().unwrap().full.as_deref(), Some("Anya Forger"));
+		let edges = anya.media.as_ref().unwrap().edges.as_ref().unwrap();
+		let anime = edges[0].as_ref().unwrap();
+		assert!(matches!(anime.character_role, Some(CharacterRole::Main)));
+		assert_eq!(anime.voice_actors.as_ref().unwrap().len(), 1);
+		assert!(edges[1].as_ref().unwrap().voice_actors.as_ref().unwrap().is_empty());
+
+		let transport = FixtureTransport::new().respond(200, fixture!("staff_search.json"));
+		let operation = StaffSearch::build(StaffSearchArguments { page: Some(1), name: Some("Tatsuya Endou".to_string()) });
+		let staff = run_with(&transport, None, operation).await.unwrap().page.unwrap().staff.unwrap();
+		let endou = staff[0].as_ref().unwrap();
+		assert!(endou.description.is_none());
+		assert_eq!(endou.years_active.as_ref().unwrap(), &vec![Some(2000)]);
+		assert_eq!(endou.staff_media.as_ref().unwrap().edges.as_ref().unwrap().len(), 2);
+
+		let transport = FixtureTransport::new().respond(200, fixture!("studio_search.json"));
+		let operation = StudioSearch::build(StudioSearchArguments { page: Some(1), name: Some("Wit".to_string()) });
+		let studios = run_with(&transport, None, operation).await.unwrap().page.unwrap().studios.unwrap();
+		let wit = studios[0].as_ref().unwrap();
+		assert!(wit.is_animation_studio);
+		assert_eq!(wit.media.as_ref().unwrap().nodes.as_ref().unwrap().len(), 2);
+	}
+
+	#[tokio::test]
+	async fn decode_user_profile() {
+		use super::*;
+		use queries::queries::{UserProfile, UserProfileArguments};
+
+		let transport = FixtureTransport::new().respond(200, fixture!("user_profile.json"));
+		let operation = UserProfile::build(UserProfileArguments { name: Some("Tracreed".to_string()) });
+		let user = run_with(&transport, None, operation).await.unwrap().user.unwrap();
+		assert_eq!(user.name, "Tracreed");
+		assert!(user.banner_image.is_none());
+		let statistics = user.statistics.unwrap();
+		let anime = statistics.anime.unwrap();
+		assert_eq!(anime.count, 412);
+		assert!((anime.mean_score - 74.3).abs() < f64::EPSILON);
+		assert_eq!(anime.genres.unwrap()[0].as_ref().unwrap().genre.as_deref(), Some("Comedy"));
+		let favourites = user.favourites.unwrap();
+		assert!(favourites.manga.unwrap().nodes.unwrap().is_empty());
+	}
+
+	#[tokio::test]
+	async fn decode_collections_and_viewer() {
+		use super::*;
+		use queries::queries::{Collections, CurrentUser};
+
+		let transport = FixtureTransport::new().respond(200, fixture!("collections.json"));
+		let collections = run_with(&transport, None, Collections::build(())).await.unwrap();
+		assert!(collections.genre_collection.unwrap().contains(&Some("Slice of Life".to_string())));
+		let tags = collections.media_tag_collection.unwrap();
+		assert_eq!(tags.iter().flatten().filter(|t| t.is_adult == Some(true)).count(), 1);
+
+		let transport = FixtureTransport::new().respond(200, fixture!("viewer.json"));
+		let viewer = run_with(&transport, Some("secret"), CurrentUser::build(())).await.unwrap().viewer.unwrap();
+		assert_eq!(viewer.id, 5290);
+		assert_eq!(transport.requests()[0].1.as_deref(), Some("secret"));
+	}
+
+	/// Fetches a season chart page through `transport`, the way [`season_stream`] does over HTTP.
+	async fn season_page(transport: &FixtureTransport, page: i32) -> Result<Paged<Media4>, AniListError> {
+		use super::*;
+		use queries::queries::{SeasonChart, SeasonArguments};
+
+		let operation = SeasonChart::build(SeasonArguments {
+			page: Some(page),
+			season: Some(MediaSeason::Spring),
+			season_year: Some(2022),
+			formats: None,
+			sort: Some(vec![Some(MediaSort::PopularityDesc)]),
+		});
+		let chart = run_with(transport, None, operation).await?;
+		Ok(chart.page.map(|p| Paged::new(p.media, p.page_info)).unwrap_or_else(Paged::empty))
+	}
+
+	#[tokio::test]
+	async fn paginate_follows_pages() {
+		use super::*;
+
+		let transport = FixtureTransport::new()
+			.respond(200, fixture!("season_page_1.json"))
+			.respond(200, fixture!("season_page_2.json"));
+		let media = paginate(|page| season_page(&transport, page)).try_collect::<Vec<_>>().await.unwrap();
+		assert_eq!(media.iter().map(|m| m.id).collect::<Vec<_>>(), vec![125367, 140960, 153800]);
+		// The second page says it's the last one, so there's no third request
+		assert_eq!(transport.requests().len(), 2);
+	}
+
+	#[tokio::test]
+	async fn paginate_fetches_lazily() {
+		use super::*;
+
+		let transport = FixtureTransport::new()
+			.respond(200, fixture!("season_page_1.json"))
+			.respond(200, fixture!("season_page_2.json"));
+		let mut media = Box::pin(paginate(|page| season_page(&transport, page)));
+		assert_eq!(media.try_next().await.unwrap().unwrap().id, 125367);
+		assert_eq!(media.try_next().await.unwrap().unwrap().id, 140960);
+		assert_eq!(transport.requests().len(), 1);
+		assert_eq!(media.try_next().await.unwrap().unwrap().id, 153800);
+		assert_eq!(transport.requests().len(), 2);
+		assert!(media.try_next().await.unwrap().is_none());
+	}
+
+	#[tokio::test]
+	async fn paginate_retries_rate_limited_pages() {
+		use super::*;
+
+		let transport = FixtureTransport::new()
+			.respond_with_headers(429, &[("retry-after", "1")], "{}")
+			.respond(200, fixture!("season_page_2.json"));
+		let media = paginate(|page| season_page(&transport, page)).try_collect::<Vec<_>>().await.unwrap();
+		assert_eq!(media.len(), 1);
+		let requests = transport.requests();
+		assert_eq!(requests.len(), 2);
+		assert_eq!(requests[0].0, requests[1].0);
+	}
+
+	#[tokio::test]
+	async fn paginate_stops_at_errors() {
+		use super::*;
+
+		let transport = FixtureTransport::new().respond(400, fixture!("validation_error.json"));
+		let results = paginate(|page| season_page(&transport, page)).collect::<Vec<_>>().await;
+		assert_eq!(results.len(), 1);
+		assert!(matches!(results[0], Err(AniListError::GraphQl(_))));
+	}
+
+	#[tokio::test]
+	async fn decode_user_list() {
+		use super::*;
+		use queries::queries::{UserList, UserListArguments};
+
+		let transport = FixtureTransport::new().respond(200, fixture!("user_list.json"));
+		let operation = UserList::build(UserListArguments {
+			page: Some(1),
+			user_name: Some("Tracreed".to_string()),
+			r#type: Some(MediaType::Anime),
+			status: None,
+		});
+		let page = run_with(&transport, None, operation).await.unwrap().page.unwrap();
+		let list = Paged::new(page.media_list, page.page_info);
+		assert!(!list.has_next_page());
+		assert_eq!(list.items.len(), 2);
+		assert_eq!(list.items[0].status, Some(MediaListStatus::Current));
+		assert_eq!(list.items[1].score, Some(0.0));
+	}
+
+	#[tokio::test]
+	async fn missing_user_is_no_data() {
+		use super::*;
+		use queries::queries::{UserProfile, UserProfileArguments};
+
+		let transport = FixtureTransport::new().respond(404, fixture!("user_not_found.json"));
+		let operation = UserProfile::build(UserProfileArguments { name: Some("nobody".to_string()) });
+		match run_with(&transport, None, operation).await {
+			Err(AniListError::NoData) => {}
+			other => panic!("expected no data, got {:?}", other),
+		}
+	}
+
+	#[tokio::test]
+	async fn invalid_token_is_a_graphql_error() {
+		use super::*;
+		use queries::queries::CurrentUser;
+
+		let transport = FixtureTransport::new().respond(400, fixture!("invalid_token.json"));
+		match run_with(&transport, Some("expired"), CurrentUser::build(())).await {
+			Err(AniListError::GraphQl(errors)) => assert_eq!(errors[0].message, "Invalid token"),
+			other => panic!("expected a GraphQL error, got {:?}", other),
+		}
+	}
+
+	#[tokio::test]
+	async fn validation_error_is_a_graphql_error() {
+		use super::*;
+		use queries::queries::Collections;
+
+		let transport = FixtureTransport::new().respond(400, fixture!("validation_error.json"));
+		match run_with(&transport, None, Collections::build(())).await {
+			Err(AniListError::GraphQl(errors)) => assert!(errors[0].message.contains("Expected type MediaSort")),
+			other => panic!("expected a GraphQL error, got {:?}", other),
+		}
+	}
+
+	#[tokio::test]
+	async fn server_error_without_body_is_http() {
+		use super::*;
+		use queries::queries::Collections;
+
+		let transport = FixtureTransport::new().respond(502, "<html><body>502 Bad Gateway</body></html>");
+		match run_with(&transport, None, Collections::build(())).await {
+			Err(AniListError::Http { status, body }) => {
+				assert_eq!(status, StatusCode::BAD_GATEWAY);
+				assert!(body.contains("Bad Gateway"));
+			}
+			other => panic!("expected an HTTP error, got {:?}", other),
+		}
+	}
+
+	#[tokio::test]
+	async fn unexpected_shape_is_a_decode_error() {
+		use super::*;
+		use queries::queries::CurrentUser;
+
+		// `id` can't be null
+		let transport = FixtureTransport::new().respond(200, r#"{"data":{"Viewer":{"id":null,"name":"Tracreed"}}}"#);
+		match run_with(&transport, None, CurrentUser::build(())).await {
+			Err(AniListError::Decode(_)) => {}
+			other => panic!("expected a decode error, got {:?}", other),
+		}
+	}
+
+	#[tokio::test]
+	async fn rate_limited_mutation() {
+		use super::*;
+		use queries::queries::CurrentUser;
+
+		let transport = FixtureTransport::new().respond_with_headers(429, &[("retry-after", "30")], "{}");
+		match run_with(&transport, Some("secret"), CurrentUser::build(())).await {
+			Err(AniListError::RateLimited { reset: Some(_) }) => {}
+			other => panic!("expected to be rate limited, got {:?}", other),
+		}
+	}
+
+	#[test]
+	fn rate_limit_reset_headers() {
+		use super::*;
+		let mut headers = HeaderMap::new();
+		assert!(rate_limit_reset(&headers).is_none());
+		headers.insert("retry-after", "60".parse().unwrap());
+		assert!(rate_limit_reset(&headers).unwrap() > chrono::Utc::now());
+		headers.insert("x-ratelimit-reset", "1654732800".parse().unwrap());
+		assert_eq!(rate_limit_reset(&headers).unwrap().timestamp(), 1654732800);
+	}
 }
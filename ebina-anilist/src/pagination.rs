@@ -0,0 +1,83 @@
+use std::future::Future;
+
+use futures::stream::{self, Stream, TryStreamExt};
+
+use crate::AniListError;
+use crate::queries::queries::PageInfo;
+
+/// How many times a page is retried after being rate limited.
+const RATE_LIMIT_RETRIES: usize = 3;
+
+/// One page of results and where it sits in the whole list.
+#[derive(Debug, Clone)]
+pub struct Paged<T> {
+	pub items: Vec<T>,
+	pub page_info: Option<PageInfo>,
+}
+
+impl<T> Paged<T> {
+	/// Builds a page from the nullable list AniList returns, dropping the null items.
+	pub fn new(items: Option<Vec<Option<T>>>, page_info: Option<PageInfo>) -> Self {
+		Paged {
+			items: items.unwrap_or_default().into_iter().flatten().collect(),
+			page_info,
+		}
+	}
+
+	/// An empty last page, for when AniList returns no page at all.
+	pub fn empty() -> Self {
+		Paged::new(None, None)
+	}
+
+	/// Whether there's another page after this one, an empty page is always the last.
+	pub fn has_next_page(&self) -> bool {
+		!self.items.is_empty() && self.page_info.and_then(|p| p.has_next_page).unwrap_or(false)
+	}
+}
+
+/// Streams the items of every page `fetch` returns, starting at page 1.
+///
+/// Pages are only fetched once the items of the previous page have been taken, spacing
+/// them out is left to the transport's [`RateLimiter`](crate::transport::RateLimiter).
+/// A rate limited page is retried, other errors end the stream.
+/// # Examples
+/// ```rust,no_run
+/// # async fn example() -> Result<(), ebina_anilist::AniListError> {
+/// use ebina_anilist::queries::queries::{MediaSeason, MediaSort};
+/// use futures::stream::TryStreamExt;
+///
+/// let media = ebina_anilist::season_stream(MediaSeason::Spring, 2022, Vec::new(), MediaSort::PopularityDesc)
+///     .try_collect::<Vec<_>>()
+///     .await?;
+/// # Ok(())
+/// # }
+/// ```
+pub fn paginate<T, F, Fut>(fetch: F) -> impl Stream<Item = Result<T, AniListError>> where
+F: FnMut(i32) -> Fut,
+Fut: Future<Output = Result<Paged<T>, AniListError>> {
+	stream::try_unfold((Some(1), fetch), |(page, mut fetch)| async move {
+		let page = match page {
+			Some(v) => v,
+			None => return Ok(None),
+		};
+		let paged = fetch_page(&mut fetch, page).await?;
+		let next = if paged.has_next_page() { Some(page + 1) } else { None };
+		let items = stream::iter(paged.items.into_iter().map(Ok));
+		Ok(Some((items, (next, fetch))))
+	}).try_flatten()
+}
+
+/// Fetches a single page, retrying it if AniList rate limits us anyway.
+///
+/// The transport holds the retry back until the limit resets.
+async fn fetch_page<T, F, Fut>(fetch: &mut F, page: i32) -> Result<Paged<T>, AniListError> where
+F: FnMut(i32) -> Fut,
+Fut: Future<Output = Result<Paged<T>, AniListError>> {
+	let mut retries = 0;
+	loop {
+		match fetch(page).await {
+			Err(AniListError::RateLimited { .. }) if retries < RATE_LIMIT_RETRIES => retries += 1,
+			result => return result,
+		}
+	}
+}
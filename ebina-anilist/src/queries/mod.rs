@@ -9,38 +9,183 @@ pub mod queries {
 	
 	#[derive(cynic::FragmentArguments, Debug, Clone)]
 	pub struct MediaSpecificArguments {
+		pub page: Option<i32>,
 		pub title: Option<String>,
 		pub r#type: Option<MediaType>,
 	}
 
 	#[derive(cynic::FragmentArguments, Debug, Clone)]
     pub struct ScheduleArguments {
+        pub page: Option<i32>,
         pub airing_at_greater: Option<i32>,
         pub airing_at_lesser: Option<i32>,
     }
 	
 	#[derive(cynic::FragmentArguments, Debug, Clone)]
 	pub struct MediaSearchArguments {
+		pub page: Option<i32>,
 		pub title: Option<String>,
 	}
+
+	#[derive(cynic::FragmentArguments, Debug, Clone)]
+	pub struct CharacterSearchArguments {
+		pub page: Option<i32>,
+		pub name: Option<String>,
+	}
+
+	#[derive(cynic::FragmentArguments, Debug, Clone)]
+	pub struct StaffSearchArguments {
+		pub page: Option<i32>,
+		pub name: Option<String>,
+	}
+
+	#[derive(cynic::FragmentArguments, Debug, Clone)]
+	pub struct StudioSearchArguments {
+		pub page: Option<i32>,
+		pub name: Option<String>,
+	}
+
+	#[derive(cynic::FragmentArguments, Debug, Clone)]
+	pub struct MediaByIdArguments {
+		pub id: Option<i32>,
+	}
+
+	#[derive(cynic::FragmentArguments, Debug, Clone)]
+	pub struct SaveMediaListEntryArguments {
+		pub media_id: Option<i32>,
+		pub status: Option<MediaListStatus>,
+		pub progress: Option<i32>,
+		/// The score out of 100, whatever score format the user has picked
+		pub score_raw: Option<i32>,
+	}
+
+	#[derive(cynic::FragmentArguments, Debug, Clone)]
+	pub struct UserProfileArguments {
+		pub name: Option<String>,
+	}
+
+	#[derive(cynic::FragmentArguments, Debug, Clone)]
+	pub struct UserListArguments {
+		pub page: Option<i32>,
+		pub user_name: Option<String>,
+		pub r#type: Option<MediaType>,
+		pub status: Option<MediaListStatus>,
+	}
+
+	#[derive(cynic::FragmentArguments, Debug, Clone)]
+	pub struct SeasonArguments {
+		pub page: Option<i32>,
+		pub season: Option<MediaSeason>,
+		pub season_year: Option<i32>,
+		pub formats: Option<Vec<Option<MediaFormat>>>,
+		pub sort: Option<Vec<Option<MediaSort>>>,
+	}
 	
 	#[derive(cynic::QueryFragment, Debug, Clone)]
 	#[cynic(graphql_type = "Query", argument_struct = "MediaSpecificArguments")]
 	pub struct MediaSpecific {
-		#[arguments(page = 1)]
+		#[arguments(page = args.page)]
 		pub page: Option<Page>,
 	}
 	
 	#[derive(cynic::QueryFragment, Debug, Clone)]
 	#[cynic(graphql_type = "Query", argument_struct = "MediaSearchArguments")]
 	pub struct MediaSearch {
-		#[arguments(page = 1)]
+		#[arguments(page = args.page)]
 		pub page: Option<Page2>,
 	}
 	
+	#[derive(cynic::FragmentArguments, Debug, Clone)]
+	pub struct DiscoverArguments {
+		pub page: Option<i32>,
+		pub per_page: Option<i32>,
+		pub r#type: Option<MediaType>,
+		pub sort: Option<Vec<Option<MediaSort>>>,
+		pub genres: Option<Vec<Option<String>>>,
+		pub tags: Option<Vec<Option<String>>>,
+		pub is_adult: Option<bool>,
+	}
+
+	#[derive(cynic::QueryFragment, Debug, Clone)]
+	#[cynic(graphql_type = "Query", argument_struct = "DiscoverArguments")]
+	pub struct Discover {
+		#[arguments(page = args.page, per_page = args.per_page)]
+		pub page: Option<Page8>,
+	}
+
+	#[derive(cynic::QueryFragment, Debug, Clone)]
+	#[cynic(graphql_type = "Query")]
+	pub struct Collections {
+		pub genre_collection: Option<Vec<Option<String>>>,
+		pub media_tag_collection: Option<Vec<Option<MediaTag2>>>,
+	}
+
+	#[derive(cynic::QueryFragment, Debug, Clone)]
+	#[cynic(graphql_type = "Query")]
+	pub struct CurrentUser {
+		pub viewer: Option<User2>,
+	}
+
+	#[derive(cynic::QueryFragment, Debug, Clone)]
+	#[cynic(graphql_type = "Mutation", argument_struct = "SaveMediaListEntryArguments")]
+	pub struct SaveMediaListEntry {
+		#[arguments(media_id = args.media_id, status = args.status, score_raw = args.score_raw, progress = args.progress)]
+		pub save_media_list_entry: Option<MediaList>,
+	}
+
+	#[derive(cynic::QueryFragment, Debug, Clone)]
+	#[cynic(graphql_type = "Query", argument_struct = "MediaByIdArguments")]
+	pub struct MediaById {
+		#[arguments(id = args.id)]
+		pub media: Option<Media>,
+	}
+
+	#[derive(cynic::QueryFragment, Debug, Clone)]
+	#[cynic(graphql_type = "Query", argument_struct = "CharacterSearchArguments")]
+	pub struct CharacterSearch {
+		#[arguments(page = args.page, per_page = 10)]
+		pub page: Option<Page4>,
+	}
+
+	#[derive(cynic::QueryFragment, Debug, Clone)]
+	#[cynic(graphql_type = "Query", argument_struct = "StaffSearchArguments")]
+	pub struct StaffSearch {
+		#[arguments(page = args.page, per_page = 10)]
+		pub page: Option<Page5>,
+	}
+
+	#[derive(cynic::QueryFragment, Debug, Clone)]
+	#[cynic(graphql_type = "Query", argument_struct = "StudioSearchArguments")]
+	pub struct StudioSearch {
+		#[arguments(page = args.page, per_page = 10)]
+		pub page: Option<Page6>,
+	}
+
+	#[derive(cynic::QueryFragment, Debug, Clone)]
+	#[cynic(graphql_type = "Query", argument_struct = "UserProfileArguments")]
+	pub struct UserProfile {
+		#[arguments(name = &args.name)]
+		pub user: Option<User>,
+	}
+
+	#[derive(cynic::QueryFragment, Debug, Clone)]
+	#[cynic(graphql_type = "Query", argument_struct = "UserListArguments")]
+	pub struct UserList {
+		#[arguments(page = args.page, per_page = 50)]
+		pub page: Option<Page9>,
+	}
+
+	#[derive(cynic::QueryFragment, Debug, Clone)]
+	#[cynic(graphql_type = "Query", argument_struct = "SeasonArguments")]
+	pub struct SeasonChart {
+		#[arguments(page = args.page, per_page = 50)]
+		pub page: Option<Page7>,
+	}
+
 	#[derive(cynic::QueryFragment, Debug)]
     #[cynic(graphql_type = "Query", argument_struct = "ScheduleArguments")]
     pub struct Schedule {
+        #[arguments(page = args.page, per_page = 50)]
         pub page: Option<Page3>,
     }
 	
@@ -72,10 +217,59 @@ pub mod queries {
 	#[derive(cynic::QueryFragment, Debug, Clone)]
     #[cynic(graphql_type = "Page", argument_struct = "ScheduleArguments")]
     pub struct Page3 {
-        #[arguments(airing_at_greater = args.airing_at_greater, airing_at_lesser = args.airing_at_lesser)]
+        pub page_info: Option<PageInfo>,
+        #[arguments(airing_at_greater = args.airing_at_greater, airing_at_lesser = args.airing_at_lesser, sort = Some(vec![Some(AiringSort::Time)]))]
         pub airing_schedules: Option<Vec<Option<AiringSchedule>>>,
     }
 	
+	#[derive(cynic::QueryFragment, Debug, Clone)]
+	#[cynic(graphql_type = "Page", argument_struct = "CharacterSearchArguments")]
+	pub struct Page4 {
+		pub page_info: Option<PageInfo>,
+		#[arguments(search = &args.name)]
+		pub characters: Option<Vec<Option<Character>>>,
+	}
+
+	#[derive(cynic::QueryFragment, Debug, Clone)]
+	#[cynic(graphql_type = "Page", argument_struct = "StaffSearchArguments")]
+	pub struct Page5 {
+		pub page_info: Option<PageInfo>,
+		#[arguments(search = &args.name)]
+		pub staff: Option<Vec<Option<Staff2>>>,
+	}
+
+	#[derive(cynic::QueryFragment, Debug, Clone)]
+	#[cynic(graphql_type = "Page", argument_struct = "StudioSearchArguments")]
+	pub struct Page6 {
+		pub page_info: Option<PageInfo>,
+		#[arguments(search = &args.name)]
+		pub studios: Option<Vec<Option<Studio>>>,
+	}
+
+	#[derive(cynic::QueryFragment, Debug, Clone)]
+	#[cynic(graphql_type = "Page", argument_struct = "SeasonArguments")]
+	pub struct Page7 {
+		pub page_info: Option<PageInfo>,
+		#[arguments(season = args.season, season_year = args.season_year, format_in = &args.formats, sort = &args.sort, r#type = MediaType::Anime)]
+		pub media: Option<Vec<Option<Media4>>>,
+	}
+
+	#[derive(cynic::QueryFragment, Debug, Clone)]
+	#[cynic(graphql_type = "Page", argument_struct = "DiscoverArguments")]
+	pub struct Page8 {
+		pub page_info: Option<PageInfo>,
+		#[arguments(r#type = args.r#type, sort = &args.sort, genre_in = &args.genres, tag_in = &args.tags, is_adult = args.is_adult)]
+		pub media: Option<Vec<Option<Media4>>>,
+	}
+
+	#[derive(cynic::QueryFragment, Debug, Clone)]
+	#[cynic(graphql_type = "Page", argument_struct = "UserListArguments")]
+	pub struct Page9 {
+		pub page_info: Option<PageInfo>,
+		#[arguments(user_name = &args.user_name, r#type = args.r#type, status = args.status)]
+		pub media_list: Option<Vec<Option<MediaList>>>,
+	}
+
 	#[derive(cynic::QueryFragment, Debug, Clone)]
 	pub struct Media {
 		pub id: i32,
@@ -104,6 +298,76 @@ pub mod queries {
 		pub source: Option<MediaSource>,
 		pub start_date: Option<FuzzyDate>,
 		pub end_date: Option<FuzzyDate>,
+		#[arguments(sort = Some(vec![Some(StaffSort::Relevance)]), per_page = 10)]
+		pub staff: Option<StaffConnection>,
+		pub next_airing_episode: Option<AiringSchedule2>,
+		#[arguments(is_main = true)]
+		pub studios: Option<StudioConnection>,
+		pub relations: Option<MediaConnection4>,
+		#[arguments(sort = Some(vec![Some(RecommendationSort::RatingDesc)]), per_page = 5)]
+		pub recommendations: Option<RecommendationConnection>,
+		pub external_links: Option<Vec<Option<MediaExternalLink>>>,
+		pub trailer: Option<MediaTrailer>,
+	}
+
+	#[derive(cynic::QueryFragment, Debug, Clone)]
+	pub struct StudioConnection {
+		pub nodes: Option<Vec<Option<Studio2>>>,
+	}
+
+	#[derive(cynic::QueryFragment, Debug, Clone)]
+	#[cynic(graphql_type = "Studio")]
+	pub struct Studio2 {
+		pub id: i32,
+		pub name: String,
+		pub site_url: Option<String>,
+	}
+
+	#[derive(cynic::QueryFragment, Debug, Clone)]
+	#[cynic(graphql_type = "MediaConnection")]
+	pub struct MediaConnection4 {
+		pub edges: Option<Vec<Option<MediaEdge3>>>,
+	}
+
+	#[derive(cynic::QueryFragment, Debug, Clone)]
+	#[cynic(graphql_type = "MediaEdge")]
+	pub struct MediaEdge3 {
+		#[arguments(version = 2)]
+		pub relation_type: Option<MediaRelation>,
+		pub node: Option<Media3>,
+	}
+
+	#[derive(cynic::QueryFragment, Debug, Clone)]
+	pub struct RecommendationConnection {
+		pub nodes: Option<Vec<Option<Recommendation>>>,
+	}
+
+	#[derive(cynic::QueryFragment, Debug, Clone)]
+	pub struct Recommendation {
+		pub media_recommendation: Option<Media3>,
+	}
+
+	#[derive(cynic::QueryFragment, Debug, Clone)]
+	pub struct MediaExternalLink {
+		pub url: String,
+		pub site: String,
+	}
+
+	#[derive(cynic::QueryFragment, Debug, Clone)]
+	pub struct MediaTrailer {
+		pub id: Option<String>,
+		pub site: Option<String>,
+	}
+
+	impl MediaTrailer {
+		/// Link to the trailer on the site hosting it.
+		pub fn url(&self) -> Option<String> {
+			match (self.id.as_deref(), self.site.as_deref()) {
+				(Some(id), Some("youtube")) => Some(format!("https://www.youtube.com/watch?v={}", id)),
+				(Some(id), Some("dailymotion")) => Some(format!("https://www.dailymotion.com/video/{}", id)),
+				_ => None,
+			}
+		}
 	}
 	
 	#[derive(cynic::QueryFragment, Debug, Clone)]
@@ -142,6 +406,14 @@ pub mod queries {
         pub media: Option<Media2>,
     }
 
+    #[derive(cynic::QueryFragment, Debug, Clone, Copy)]
+    #[cynic(graphql_type = "AiringSchedule")]
+    pub struct AiringSchedule2 {
+        pub episode: i32,
+        pub airing_at: i32,
+        pub time_until_airing: i32,
+    }
+
     #[derive(cynic::QueryFragment, Debug, Clone)]
     #[cynic(graphql_type = "Media")]
     pub struct Media2 {
@@ -154,8 +426,96 @@ pub mod queries {
         pub popularity: Option<i32>,
         pub status: Option<MediaStatus>,
         pub average_score: Option<i32>,
+        pub cover_image: Option<MediaCoverImage>,
+        pub format: Option<MediaFormat>,
+        pub is_adult: Option<bool>,
     }
 	
+	#[derive(cynic::QueryFragment, Debug, Clone)]
+	pub struct Character {
+		pub id: i32,
+		pub name: Option<CharacterName>,
+		pub image: Option<CharacterImage>,
+		#[arguments(as_html = false)]
+		pub description: Option<String>,
+		pub gender: Option<String>,
+		pub age: Option<String>,
+		pub favourites: Option<i32>,
+		pub site_url: Option<String>,
+		#[arguments(sort = Some(vec![Some(MediaSort::PopularityDesc)]), per_page = 10)]
+		pub media: Option<MediaConnection>,
+	}
+
+	#[derive(cynic::QueryFragment, Debug, Clone)]
+	pub struct CharacterName {
+		pub full: Option<String>,
+		pub native: Option<String>,
+		pub alternative: Option<Vec<Option<String>>>,
+		pub alternative_spoiler: Option<Vec<Option<String>>>,
+		pub user_preferred: Option<String>,
+	}
+
+	#[derive(cynic::QueryFragment, Debug, Clone)]
+	pub struct CharacterImage {
+		pub large: Option<String>,
+		pub medium: Option<String>,
+	}
+
+	#[derive(cynic::QueryFragment, Debug, Clone)]
+	pub struct MediaConnection {
+		pub edges: Option<Vec<Option<MediaEdge>>>,
+	}
+
+	#[derive(cynic::QueryFragment, Debug, Clone)]
+	pub struct MediaEdge {
+		pub character_role: Option<CharacterRole>,
+		#[arguments(language = StaffLanguage::Japanese)]
+		pub voice_actors: Option<Vec<Option<Staff>>>,
+		pub node: Option<Media3>,
+	}
+
+	#[derive(cynic::QueryFragment, Debug, Clone)]
+	#[cynic(graphql_type = "Media")]
+	pub struct Media3 {
+		pub id: i32,
+		#[cynic(rename = "type")]
+		pub type_: Option<MediaType>,
+		pub title: Option<MediaTitle>,
+		pub format: Option<MediaFormat>,
+		pub is_adult: Option<bool>,
+	}
+
+	#[derive(cynic::QueryFragment, Debug, Clone)]
+	#[cynic(graphql_type = "Media")]
+	pub struct Media4 {
+		pub id: i32,
+		#[cynic(rename = "type")]
+		pub type_: Option<MediaType>,
+		pub title: Option<MediaTitle>,
+		pub format: Option<MediaFormat>,
+		pub status: Option<MediaStatus>,
+		pub episodes: Option<i32>,
+		pub chapters: Option<i32>,
+		pub average_score: Option<i32>,
+		pub popularity: Option<i32>,
+		pub genres: Option<Vec<Option<String>>>,
+		pub is_adult: Option<bool>,
+		pub season_year: Option<i32>,
+		pub start_date: Option<FuzzyDate>,
+		pub cover_image: Option<MediaCoverImage>,
+		pub rankings: Option<Vec<Option<MediaRank2>>>,
+	}
+
+	#[derive(cynic::QueryFragment, Debug, Clone)]
+	#[cynic(graphql_type = "MediaRank")]
+	pub struct MediaRank2 {
+		pub rank: i32,
+		#[cynic(rename = "type")]
+		pub type_: MediaRankType,
+		pub all_time: Option<bool>,
+		pub context: String,
+	}
+
 	#[derive(cynic::QueryFragment, Debug, Clone)]
 	pub struct MediaCoverImage {
 		pub extra_large: Option<String>,
@@ -178,21 +538,178 @@ pub mod queries {
 	
 	#[derive(cynic::QueryFragment, Debug, Clone)]
 	pub struct Staff {
+		pub id: i32,
 		pub name: Option<StaffName>,
+		pub site_url: Option<String>,
 	}
 	
 	#[derive(cynic::QueryFragment, Debug, Clone)]
 	pub struct StaffName {
 		pub first: Option<String>,
 		pub last: Option<String>,
+		pub full: Option<String>,
 	}
 	
+	#[derive(cynic::QueryFragment, Debug, Clone)]
+	#[cynic(graphql_type = "Staff")]
+	pub struct Staff2 {
+		pub id: i32,
+		pub name: Option<StaffName2>,
+		pub image: Option<StaffImage>,
+		#[arguments(as_html = false)]
+		pub description: Option<String>,
+		pub primary_occupations: Option<Vec<Option<String>>>,
+		pub gender: Option<String>,
+		pub years_active: Option<Vec<Option<i32>>>,
+		pub home_town: Option<String>,
+		pub favourites: Option<i32>,
+		pub site_url: Option<String>,
+		#[arguments(sort = Some(vec![Some(MediaSort::PopularityDesc)]), per_page = 10)]
+		pub staff_media: Option<MediaConnection2>,
+		#[arguments(sort = Some(vec![Some(CharacterSort::FavouritesDesc)]), per_page = 10)]
+		pub characters: Option<CharacterConnection>,
+	}
+
+	#[derive(cynic::QueryFragment, Debug, Clone)]
+	#[cynic(graphql_type = "StaffName")]
+	pub struct StaffName2 {
+		pub full: Option<String>,
+		pub native: Option<String>,
+		pub alternative: Option<Vec<Option<String>>>,
+		pub user_preferred: Option<String>,
+	}
+
+	#[derive(cynic::QueryFragment, Debug, Clone)]
+	pub struct StaffImage {
+		pub large: Option<String>,
+		pub medium: Option<String>,
+	}
+
+	#[derive(cynic::QueryFragment, Debug, Clone)]
+	#[cynic(graphql_type = "MediaConnection")]
+	pub struct MediaConnection2 {
+		pub edges: Option<Vec<Option<MediaEdge2>>>,
+	}
+
+	#[derive(cynic::QueryFragment, Debug, Clone)]
+	#[cynic(graphql_type = "MediaEdge")]
+	pub struct MediaEdge2 {
+		pub staff_role: Option<String>,
+		pub node: Option<Media3>,
+	}
+
+	#[derive(cynic::QueryFragment, Debug, Clone)]
+	pub struct CharacterConnection {
+		pub nodes: Option<Vec<Option<Character2>>>,
+	}
+
+	#[derive(cynic::QueryFragment, Debug, Clone)]
+	#[cynic(graphql_type = "Character")]
+	pub struct Character2 {
+		pub id: i32,
+		pub name: Option<CharacterName>,
+	}
+
+	#[derive(cynic::QueryFragment, Debug, Clone)]
+	pub struct Studio {
+		pub id: i32,
+		pub name: String,
+		pub is_animation_studio: bool,
+		pub favourites: Option<i32>,
+		pub site_url: Option<String>,
+		#[arguments(sort = Some(vec![Some(MediaSort::PopularityDesc)]), is_main = true, per_page = 10)]
+		pub media: Option<MediaConnection3>,
+	}
+
+	#[derive(cynic::QueryFragment, Debug, Clone)]
+	#[cynic(graphql_type = "MediaConnection")]
+	pub struct MediaConnection3 {
+		pub nodes: Option<Vec<Option<Media3>>>,
+	}
+
+	#[derive(cynic::QueryFragment, Debug, Clone)]
+	pub struct MediaList {
+		pub id: i32,
+		pub media_id: i32,
+		pub status: Option<MediaListStatus>,
+		/// In the score format the user has picked
+		pub score: Option<f64>,
+		pub progress: Option<i32>,
+		pub media: Option<Media3>,
+	}
+
+	#[derive(cynic::QueryFragment, Debug, Clone)]
+	#[cynic(graphql_type = "User")]
+	pub struct User2 {
+		pub id: i32,
+		pub name: String,
+	}
+
+	#[derive(cynic::QueryFragment, Debug, Clone)]
+	pub struct User {
+		pub id: i32,
+		pub name: String,
+		pub avatar: Option<UserAvatar>,
+		pub banner_image: Option<String>,
+		pub site_url: Option<String>,
+		pub statistics: Option<UserStatisticTypes>,
+		pub favourites: Option<Favourites>,
+	}
+
+	#[derive(cynic::QueryFragment, Debug, Clone)]
+	pub struct UserAvatar {
+		pub large: Option<String>,
+		pub medium: Option<String>,
+	}
+
+	#[derive(cynic::QueryFragment, Debug, Clone)]
+	pub struct UserStatisticTypes {
+		pub anime: Option<UserStatistics>,
+		pub manga: Option<UserStatistics>,
+	}
+
+	#[derive(cynic::QueryFragment, Debug, Clone)]
+	pub struct UserStatistics {
+		pub count: i32,
+		pub mean_score: f64,
+		pub minutes_watched: i32,
+		pub episodes_watched: i32,
+		pub chapters_read: i32,
+		pub volumes_read: i32,
+		#[arguments(limit = 5, sort = Some(vec![Some(UserStatisticsSort::CountDesc)]))]
+		pub genres: Option<Vec<Option<UserGenreStatistic>>>,
+	}
+
+	#[derive(cynic::QueryFragment, Debug, Clone)]
+	pub struct UserGenreStatistic {
+		pub count: i32,
+		pub genre: Option<String>,
+	}
+
+	#[derive(cynic::QueryFragment, Debug, Clone)]
+	pub struct Favourites {
+		#[arguments(per_page = 5)]
+		pub anime: Option<MediaConnection3>,
+		#[arguments(per_page = 5)]
+		pub manga: Option<MediaConnection3>,
+		#[arguments(per_page = 5)]
+		pub characters: Option<CharacterConnection>,
+	}
+
 	#[derive(cynic::QueryFragment, Debug, Clone)]
 	pub struct MediaTag {
 		pub id: i32,
 		pub name: String,
 		pub rank: Option<i32>,
 	}
+
+	#[derive(cynic::QueryFragment, Debug, Clone)]
+	#[cynic(graphql_type = "MediaTag")]
+	pub struct MediaTag2 {
+		pub name: String,
+		pub category: Option<String>,
+		pub is_adult: Option<bool>,
+	}
 	
 	#[derive(cynic::QueryFragment, Debug, Clone)]
 	pub struct MediaTitle {
@@ -202,7 +719,7 @@ pub mod queries {
 		pub user_preferred: Option<String>,
 	}
 	
-	#[derive(cynic::Enum, Clone, Copy, Debug)]
+	#[derive(cynic::Enum, Clone, Copy, Debug, PartialEq)]
 	pub enum MediaFormat {
 		Tv,
 		TvShort,
@@ -233,19 +750,30 @@ pub mod queries {
 		}
 	}
 	
-	#[derive(cynic::Enum, Clone, Copy, Debug)]
+	#[derive(cynic::Enum, Clone, Copy, Debug, PartialEq)]
 	pub enum MediaRankType {
 		Rated,
 		Popular,
 	}
 	
-	#[derive(cynic::Enum, Clone, Copy, Debug)]
+	#[derive(cynic::Enum, Clone, Copy, Debug, PartialEq)]
 	pub enum MediaSeason {
 		Winter,
 		Spring,
 		Summer,
 		Fall,
 	}
+
+	impl Display for MediaSeason {
+		fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+			match self {
+				MediaSeason::Winter => f.write_str("Winter"),
+				MediaSeason::Spring => f.write_str("Spring"),
+				MediaSeason::Summer => f.write_str("Summer"),
+				MediaSeason::Fall => f.write_str("Fall"),
+			}
+		}
+	}
 	
 	#[derive(cynic::Enum, Clone, Copy, Debug)]
 	pub enum MediaSource {
@@ -287,6 +815,196 @@ pub mod queries {
 		}
 	}
 	
+	#[derive(cynic::Enum, Clone, Copy, Debug)]
+	pub enum MediaSort {
+		Id,
+		IdDesc,
+		TitleRomaji,
+		TitleRomajiDesc,
+		TitleEnglish,
+		TitleEnglishDesc,
+		TitleNative,
+		TitleNativeDesc,
+		Type,
+		TypeDesc,
+		Format,
+		FormatDesc,
+		StartDate,
+		StartDateDesc,
+		EndDate,
+		EndDateDesc,
+		Score,
+		ScoreDesc,
+		Popularity,
+		PopularityDesc,
+		Trending,
+		TrendingDesc,
+		Episodes,
+		EpisodesDesc,
+		Duration,
+		DurationDesc,
+		Status,
+		StatusDesc,
+		Chapters,
+		ChaptersDesc,
+		Volumes,
+		VolumesDesc,
+		UpdatedAt,
+		UpdatedAtDesc,
+		SearchMatch,
+		Favourites,
+		FavouritesDesc,
+	}
+
+	#[derive(cynic::Enum, Clone, Copy, Debug)]
+	pub enum StaffSort {
+		Id,
+		IdDesc,
+		Role,
+		RoleDesc,
+		Language,
+		LanguageDesc,
+		SearchMatch,
+		Favourites,
+		FavouritesDesc,
+		Relevance,
+	}
+
+	#[derive(cynic::Enum, Clone, Copy, Debug)]
+	pub enum CharacterSort {
+		Id,
+		IdDesc,
+		Role,
+		RoleDesc,
+		SearchMatch,
+		Favourites,
+		FavouritesDesc,
+		Relevance,
+	}
+
+	#[derive(cynic::Enum, Clone, Copy, Debug)]
+	pub enum UserStatisticsSort {
+		Id,
+		IdDesc,
+		Count,
+		CountDesc,
+		Progress,
+		ProgressDesc,
+		MeanScore,
+		MeanScoreDesc,
+	}
+
+	#[derive(cynic::Enum, Clone, Copy, Debug, PartialEq)]
+	pub enum MediaListStatus {
+		Current,
+		Planning,
+		Completed,
+		Dropped,
+		Paused,
+		Repeating,
+	}
+
+	impl Display for MediaListStatus {
+		fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+			match self {
+				MediaListStatus::Current => f.write_str("Current"),
+				MediaListStatus::Planning => f.write_str("Planning"),
+				MediaListStatus::Completed => f.write_str("Completed"),
+				MediaListStatus::Dropped => f.write_str("Dropped"),
+				MediaListStatus::Paused => f.write_str("Paused"),
+				MediaListStatus::Repeating => f.write_str("Repeating"),
+			}
+		}
+	}
+
+	#[derive(cynic::Enum, Clone, Copy, Debug)]
+	pub enum RecommendationSort {
+		Id,
+		IdDesc,
+		Rating,
+		RatingDesc,
+	}
+
+	#[derive(cynic::Enum, Clone, Copy, Debug, PartialEq)]
+	pub enum MediaRelation {
+		Adaptation,
+		Prequel,
+		Sequel,
+		Parent,
+		SideStory,
+		Character,
+		Summary,
+		Alternative,
+		SpinOff,
+		Other,
+		Source,
+		Compilation,
+		Contains,
+	}
+
+	impl Display for MediaRelation {
+		fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+			match self {
+				MediaRelation::Adaptation => f.write_str("Adaptation"),
+				MediaRelation::Prequel => f.write_str("Prequel"),
+				MediaRelation::Sequel => f.write_str("Sequel"),
+				MediaRelation::Parent => f.write_str("Parent"),
+				MediaRelation::SideStory => f.write_str("Side Story"),
+				MediaRelation::Character => f.write_str("Character"),
+				MediaRelation::Summary => f.write_str("Summary"),
+				MediaRelation::Alternative => f.write_str("Alternative"),
+				MediaRelation::SpinOff => f.write_str("Spin Off"),
+				MediaRelation::Other => f.write_str("Other"),
+				MediaRelation::Source => f.write_str("Source"),
+				MediaRelation::Compilation => f.write_str("Compilation"),
+				MediaRelation::Contains => f.write_str("Contains"),
+			}
+		}
+	}
+
+	#[derive(cynic::Enum, Clone, Copy, Debug)]
+	pub enum AiringSort {
+		Id,
+		IdDesc,
+		MediaId,
+		MediaIdDesc,
+		Time,
+		TimeDesc,
+		Episode,
+		EpisodeDesc,
+	}
+
+	#[derive(cynic::Enum, Clone, Copy, Debug)]
+	pub enum CharacterRole {
+		Main,
+		Supporting,
+		Background,
+	}
+
+	impl Display for CharacterRole {
+		fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+			match self {
+				CharacterRole::Main => f.write_str("Main"),
+				CharacterRole::Supporting => f.write_str("Supporting"),
+				CharacterRole::Background => f.write_str("Background"),
+			}
+		}
+	}
+
+	#[derive(cynic::Enum, Clone, Copy, Debug)]
+	pub enum StaffLanguage {
+		Japanese,
+		English,
+		Korean,
+		Italian,
+		Spanish,
+		Portuguese,
+		French,
+		German,
+		Hebrew,
+		Hungarian,
+	}
+
 	#[derive(cynic::Enum, Clone, Copy, Debug)]
 	pub enum MediaType {
 		Anime,
@@ -312,16 +1030,150 @@ mod schema {
 #[cfg(test)]
 mod tests {
 	use super::*;
+	use cynic::{QueryBuilder, MutationBuilder};
+	use queries::{MediaType, MediaSeason, MediaSort, MediaListStatus};
 	
 	#[test]
 	fn search_manga_query_gql_output() {
-		use cynic::QueryBuilder;
-		use queries::MediaType;
 		let arguments = queries::MediaSpecificArguments {
+			page: Some(1),
 			title: Some("To love-ru".to_string()),
 			r#type: Some(MediaType::Manga)
 		};
 		let operation = queries::MediaSpecific::build(arguments);
 		insta::assert_snapshot!(operation.query);
 	}
-}
\ No newline at end of file
+
+	#[test]
+	fn media_search_query_gql_output() {
+		let arguments = queries::MediaSearchArguments {
+			page: Some(1),
+			title: Some("Spy x Family".to_string()),
+		};
+		let operation = queries::MediaSearch::build(arguments);
+		insta::assert_snapshot!(operation.query);
+	}
+
+	#[test]
+	fn media_by_id_query_gql_output() {
+		let arguments = queries::MediaByIdArguments {
+			id: Some(140960),
+		};
+		let operation = queries::MediaById::build(arguments);
+		insta::assert_snapshot!(operation.query);
+	}
+
+	#[test]
+	fn character_search_query_gql_output() {
+		let arguments = queries::CharacterSearchArguments {
+			page: Some(1),
+			name: Some("Anya".to_string()),
+		};
+		let operation = queries::CharacterSearch::build(arguments);
+		insta::assert_snapshot!(operation.query);
+	}
+
+	#[test]
+	fn staff_search_query_gql_output() {
+		let arguments = queries::StaffSearchArguments {
+			page: Some(1),
+			name: Some("Tatsuya Endou".to_string()),
+		};
+		let operation = queries::StaffSearch::build(arguments);
+		insta::assert_snapshot!(operation.query);
+	}
+
+	#[test]
+	fn studio_search_query_gql_output() {
+		let arguments = queries::StudioSearchArguments {
+			page: Some(1),
+			name: Some("Wit".to_string()),
+		};
+		let operation = queries::StudioSearch::build(arguments);
+		insta::assert_snapshot!(operation.query);
+	}
+
+	#[test]
+	fn user_profile_query_gql_output() {
+		let arguments = queries::UserProfileArguments {
+			name: Some("Tracreed".to_string()),
+		};
+		let operation = queries::UserProfile::build(arguments);
+		insta::assert_snapshot!(operation.query);
+	}
+
+	#[test]
+	fn user_list_query_gql_output() {
+		let arguments = queries::UserListArguments {
+			page: Some(1),
+			user_name: Some("Tracreed".to_string()),
+			r#type: Some(MediaType::Anime),
+			status: Some(MediaListStatus::Current),
+		};
+		let operation = queries::UserList::build(arguments);
+		insta::assert_snapshot!(operation.query);
+	}
+
+	#[test]
+	fn season_chart_query_gql_output() {
+		let arguments = queries::SeasonArguments {
+			page: Some(1),
+			season: Some(MediaSeason::Spring),
+			season_year: Some(2022),
+			formats: None,
+			sort: Some(vec![Some(MediaSort::PopularityDesc)]),
+		};
+		let operation = queries::SeasonChart::build(arguments);
+		insta::assert_snapshot!(operation.query);
+	}
+
+	#[test]
+	fn schedule_query_gql_output() {
+		let arguments = queries::ScheduleArguments {
+			page: Some(1),
+			airing_at_greater: Some(1655510400),
+			airing_at_lesser: Some(1655596799),
+		};
+		let operation = queries::Schedule::build(arguments);
+		insta::assert_snapshot!(operation.query);
+	}
+
+	#[test]
+	fn discover_query_gql_output() {
+		let arguments = queries::DiscoverArguments {
+			page: Some(1),
+			per_page: Some(10),
+			r#type: Some(MediaType::Anime),
+			sort: Some(vec![Some(MediaSort::TrendingDesc)]),
+			genres: None,
+			tags: None,
+			is_adult: Some(false),
+		};
+		let operation = queries::Discover::build(arguments);
+		insta::assert_snapshot!(operation.query);
+	}
+
+	#[test]
+	fn collections_query_gql_output() {
+		let operation = queries::Collections::build(());
+		insta::assert_snapshot!(operation.query);
+	}
+
+	#[test]
+	fn current_user_query_gql_output() {
+		let operation = queries::CurrentUser::build(());
+		insta::assert_snapshot!(operation.query);
+	}
+
+	#[test]
+	fn save_media_list_entry_mutation_gql_output() {
+		let arguments = queries::SaveMediaListEntryArguments {
+			media_id: Some(140960),
+			status: Some(MediaListStatus::Current),
+			progress: Some(10),
+			score_raw: None,
+		};
+		let operation = queries::SaveMediaListEntry::build(arguments);
+		insta::assert_snapshot!(operation.query);
+	}
+}
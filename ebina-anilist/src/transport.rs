@@ -0,0 +1,191 @@
+use std::collections::VecDeque;
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::{Arc, Mutex};
+use std::sync::atomic::{AtomicI64, Ordering};
+use std::time::Duration;
+
+use once_cell::sync::Lazy;
+use reqwest::{StatusCode, header::{HeaderMap, HeaderName, HeaderValue}};
+
+use crate::AniListError;
+
+/// AniList allows 90 requests a minute, requests are spaced out to stay a little below that.
+const REQUEST_INTERVAL_MS: i64 = 60_000 / 80;
+/// How long to back off when rate limited and AniList didn't say when to come back.
+const RATE_LIMIT_BACKOFF_MS: i64 = 60_000;
+
+/// Shared by every transport made with [`HttpTransport::default`], they all count against the same limit.
+static ANILIST_LIMITER: Lazy<Arc<RateLimiter>> = Lazy::new(Default::default);
+
+/// A response as it came back from the transport, before any GraphQL decoding.
+#[derive(Debug, Clone)]
+pub struct RawResponse {
+	pub status: StatusCode,
+	pub headers: HeaderMap,
+	pub body: String,
+}
+
+/// The future returned by [`Transport::send`].
+pub type TransportFuture<'a> = Pin<Box<dyn Future<Output = Result<RawResponse, AniListError>> + Send + 'a>>;
+
+/// Sends a serialized GraphQL request and hands back the raw response.
+///
+/// [`HttpTransport`] talks to AniList, [`FixtureTransport`] answers with canned
+/// responses so queries can be tested without network.
+pub trait Transport: Send + Sync {
+	/// Sends the JSON `body`, authenticated as the owner of `token` if given.
+	fn send<'a>(&'a self, body: String, token: Option<&'a str>) -> TransportFuture<'a>;
+}
+
+/// Spaces out the requests sent through it so together they stay under the rate limit.
+#[derive(Debug, Default)]
+pub struct RateLimiter {
+	/// Unix time in milliseconds when the next request may be sent
+	next_request: AtomicI64,
+}
+
+impl RateLimiter {
+	pub fn new() -> Self {
+		RateLimiter::default()
+	}
+
+	/// Waits until the next request slot and claims it.
+	pub async fn wait_for_turn(&self) {
+		let now = chrono::Utc::now().timestamp_millis();
+		// The update always succeeds, the closure never gives up
+		let (Ok(previous) | Err(previous)) = self.next_request
+			.fetch_update(Ordering::SeqCst, Ordering::SeqCst, |next| Some(next.max(now) + REQUEST_INTERVAL_MS));
+		let slot = previous.max(now);
+		if slot > now {
+			tokio::time::sleep(Duration::from_millis((slot - now) as u64)).await;
+		}
+	}
+
+	/// Holds back every request until `until`, for when the limit was hit anyway.
+	pub fn hold_until(&self, until: chrono::DateTime<chrono::Utc>) {
+		self.next_request.fetch_max(until.timestamp_millis(), Ordering::SeqCst);
+	}
+}
+
+/// Sends requests over HTTP, to AniList unless another url is given.
+#[derive(Debug, Clone)]
+pub struct HttpTransport {
+	client: reqwest::Client,
+	url: String,
+	limiter: Arc<RateLimiter>,
+}
+
+impl HttpTransport {
+	/// A transport with a rate limit of its own, see [`HttpTransport::with_limiter`] to share one.
+	pub fn new<S>(url: S) -> Self where
+	S: Into<String> {
+		HttpTransport {
+			client: reqwest::Client::new(),
+			url: url.into(),
+			limiter: Arc::new(RateLimiter::new()),
+		}
+	}
+
+	/// Counts the requests of this transport against `limiter`.
+	pub fn with_limiter(mut self, limiter: Arc<RateLimiter>) -> Self {
+		self.limiter = limiter;
+		self
+	}
+}
+
+impl Default for HttpTransport {
+	fn default() -> Self {
+		HttpTransport::new(crate::GQL_URL).with_limiter(ANILIST_LIMITER.clone())
+	}
+}
+
+impl Transport for HttpTransport {
+	fn send<'a>(&'a self, body: String, token: Option<&'a str>) -> TransportFuture<'a> {
+		Box::pin(async move {
+			self.limiter.wait_for_turn().await;
+			let mut request = self.client.post(&self.url)
+				.header(reqwest::header::CONTENT_TYPE, "application/json")
+				.body(body);
+			if let Some(token) = token {
+				request = request.bearer_auth(token);
+			}
+			let response = request.send().await?;
+			let status = response.status();
+			let headers = response.headers().clone();
+			if status == StatusCode::TOO_MANY_REQUESTS {
+				// Holds back every other request as well, a retry waits its turn like they do
+				let backoff = chrono::Utc::now() + chrono::Duration::milliseconds(RATE_LIMIT_BACKOFF_MS);
+				self.limiter.hold_until(crate::rate_limit_reset(&headers).unwrap_or(backoff));
+			}
+			let body = response.text().await?;
+			Ok(RawResponse { status, headers, body })
+		})
+	}
+}
+
+/// Answers requests with canned responses in the order they were added, the last one is repeated.
+///
+/// Every request body is kept so tests can check what was sent.
+/// # Examples
+/// ```rust
+/// use ebina_anilist::transport::FixtureTransport;
+///
+/// let transport = FixtureTransport::new()
+///     .respond(200, r#"{"data":{"Viewer":{"id":5290,"name":"Tracreed"}}}"#)
+///     .respond_with_headers(429, &[("retry-after", "60")], "{}");
+/// ```
+#[derive(Debug, Default)]
+pub struct FixtureTransport {
+	responses: Mutex<VecDeque<RawResponse>>,
+	requests: Mutex<Vec<(String, Option<String>)>>,
+}
+
+impl FixtureTransport {
+	pub fn new() -> Self {
+		FixtureTransport::default()
+	}
+
+	/// Queues a response with the given status and body.
+	pub fn respond<S>(self, status: u16, body: S) -> Self where
+	S: Into<String> {
+		self.respond_with_headers(status, &[], body)
+	}
+
+	/// Queues a response with the given status, headers and body, header names have to be lowercase.
+	pub fn respond_with_headers<S>(self, status: u16, headers: &[(&'static str, &str)], body: S) -> Self where
+	S: Into<String> {
+		let mut header_map = HeaderMap::new();
+		for &(name, value) in headers {
+			header_map.insert(HeaderName::from_static(name), HeaderValue::from_str(value).expect("invalid header value"));
+		}
+		self.responses.lock().unwrap().push_back(RawResponse {
+			status: StatusCode::from_u16(status).expect("invalid status code"),
+			headers: header_map,
+			body: body.into(),
+		});
+		self
+	}
+
+	/// The bodies and tokens of every request sent so far.
+	pub fn requests(&self) -> Vec<(String, Option<String>)> {
+		self.requests.lock().unwrap().clone()
+	}
+}
+
+impl Transport for FixtureTransport {
+	fn send<'a>(&'a self, body: String, token: Option<&'a str>) -> TransportFuture<'a> {
+		self.requests.lock().unwrap().push((body, token.map(str::to_string)));
+		let response = {
+			let mut responses = self.responses.lock().unwrap();
+			if responses.len() > 1 {
+				responses.pop_front()
+			} else {
+				responses.front().cloned()
+			}
+		};
+		Box::pin(async move {
+			Ok(response.expect("no fixture response queued"))
+		})
+	}
+}
@@ -0,0 +1 @@
+DROP TABLE anilist_users;
\ No newline at end of file
@@ -0,0 +1,5 @@
+CREATE TABLE anilist_users (
+	id SERIAL PRIMARY KEY,
+	user_id BIGINT NOT NULL UNIQUE,
+	anilist_name TEXT NOT NULL
+);
\ No newline at end of file
@@ -0,0 +1 @@
+DROP TABLE airing_subscriptions;
\ No newline at end of file
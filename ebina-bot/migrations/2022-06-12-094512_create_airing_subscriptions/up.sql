@@ -0,0 +1,13 @@
+CREATE TABLE airing_subscriptions (
+	id SERIAL PRIMARY KEY,
+	guild_id BIGINT,
+	channel_id BIGINT,
+	user_id BIGINT,
+	media_id INTEGER NOT NULL,
+	media_title TEXT NOT NULL,
+	role_id BIGINT,
+	last_episode INTEGER NOT NULL DEFAULT 0,
+	CHECK ((channel_id IS NULL) <> (user_id IS NULL))
+);
+
+CREATE INDEX airing_subscriptions_media_id ON airing_subscriptions (media_id);
\ No newline at end of file
@@ -0,0 +1 @@
+DROP TABLE airing_digests;
\ No newline at end of file
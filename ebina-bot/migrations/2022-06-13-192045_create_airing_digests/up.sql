@@ -0,0 +1,11 @@
+CREATE TABLE airing_digests (
+	id SERIAL PRIMARY KEY,
+	guild_id BIGINT NOT NULL UNIQUE,
+	channel_id BIGINT NOT NULL,
+	post_hour INTEGER NOT NULL DEFAULT 8,
+	post_minute INTEGER NOT NULL DEFAULT 0,
+	timezone TEXT NOT NULL DEFAULT 'UTC',
+	formats TEXT NOT NULL DEFAULT '',
+	exclude_adult BOOLEAN NOT NULL DEFAULT TRUE,
+	last_posted BIGINT
+);
\ No newline at end of file
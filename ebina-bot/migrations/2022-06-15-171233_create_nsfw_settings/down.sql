@@ -0,0 +1,2 @@
+DROP TABLE nsfw_settings;
+DROP TYPE nsfw_policy;
\ No newline at end of file
@@ -0,0 +1,7 @@
+CREATE TYPE nsfw_policy AS ENUM ('hide', 'censor', 'allow');
+
+CREATE TABLE nsfw_settings (
+	id SERIAL PRIMARY KEY,
+	guild_id BIGINT NOT NULL UNIQUE,
+	policy nsfw_policy NOT NULL DEFAULT 'hide'
+);
\ No newline at end of file
@@ -0,0 +1 @@
+ALTER TABLE anilist_users DROP COLUMN anilist_id, DROP COLUMN access_token;
\ No newline at end of file
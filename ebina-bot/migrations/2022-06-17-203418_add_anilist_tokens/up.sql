@@ -0,0 +1 @@
+ALTER TABLE anilist_users ADD COLUMN anilist_id INTEGER, ADD COLUMN access_token TEXT;
\ No newline at end of file
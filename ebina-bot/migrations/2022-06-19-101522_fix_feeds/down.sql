@@ -0,0 +1,9 @@
+-- Only legacy feeds fit in the old column
+DELETE FROM feeds WHERE group_id IS NULL OR group_id !~ '^[0-9]+$';
+ALTER TABLE feeds DROP CONSTRAINT feeds_source;
+ALTER TABLE feeds DROP COLUMN last_chapter_at;
+ALTER TABLE feeds DROP COLUMN languages;
+ALTER TABLE feeds DROP COLUMN manga_id;
+ALTER TABLE feeds ALTER COLUMN group_id TYPE BIGINT USING group_id::BIGINT;
+ALTER TABLE feeds ALTER COLUMN group_id SET NOT NULL;
+ALTER TABLE feeds RENAME COLUMN group_id TO manga_id;
\ No newline at end of file
@@ -0,0 +1,8 @@
+-- Feeds used to point at legacy numeric group ids, keep them as text so the poll job can map them to v5 UUIDs
+ALTER TABLE feeds RENAME COLUMN manga_id TO group_id;
+ALTER TABLE feeds ALTER COLUMN group_id TYPE TEXT USING group_id::TEXT;
+ALTER TABLE feeds ALTER COLUMN group_id DROP NOT NULL;
+ALTER TABLE feeds ADD COLUMN manga_id TEXT;
+ALTER TABLE feeds ADD COLUMN languages TEXT NOT NULL DEFAULT '';
+ALTER TABLE feeds ADD COLUMN last_chapter_at BIGINT;
+ALTER TABLE feeds ADD CONSTRAINT feeds_source CHECK (group_id IS NOT NULL OR manga_id IS NOT NULL);
\ No newline at end of file
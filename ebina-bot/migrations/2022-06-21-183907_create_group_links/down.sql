@@ -0,0 +1 @@
+DROP TABLE group_links;
\ No newline at end of file
@@ -0,0 +1,9 @@
+CREATE TABLE group_links (
+	id SERIAL PRIMARY KEY,
+	server_id BIGINT NOT NULL,
+	channel_id BIGINT NOT NULL,
+	group_id TEXT NOT NULL,
+	group_name TEXT NOT NULL,
+	known_series TEXT NOT NULL DEFAULT '',
+	UNIQUE (channel_id, group_id)
+);
\ No newline at end of file
@@ -0,0 +1,7 @@
+ALTER TABLE airing_subscriptions ADD COLUMN role_id BIGINT;
+
+UPDATE airing_subscriptions SET role_id = (
+	SELECT role_id FROM feed_roles WHERE feed_roles.airing_subscription_id = airing_subscriptions.id ORDER BY feed_roles.id LIMIT 1
+);
+
+DROP TABLE feed_roles;
\ No newline at end of file
@@ -0,0 +1,17 @@
+-- One row per pinged role instead of a RON map per server, a role belongs to either a MangaDex feed or an airing subscription
+CREATE TABLE feed_roles (
+	id SERIAL PRIMARY KEY,
+	guild_id BIGINT NOT NULL,
+	feed_id INTEGER REFERENCES feeds (id) ON DELETE CASCADE,
+	airing_subscription_id INTEGER REFERENCES airing_subscriptions (id) ON DELETE CASCADE,
+	manga_id TEXT,
+	role_id BIGINT NOT NULL,
+	CHECK ((feed_id IS NULL) <> (airing_subscription_id IS NULL))
+);
+
+INSERT INTO feed_roles (guild_id, airing_subscription_id, role_id)
+	SELECT guild_id, id, role_id FROM airing_subscriptions WHERE guild_id IS NOT NULL AND role_id IS NOT NULL;
+
+ALTER TABLE airing_subscriptions DROP COLUMN role_id;
+
+-- The RON maps in roles are keyed by legacy MangaDex ids, the bot maps them with the MangaDex API and moves them over on start
\ No newline at end of file
@@ -0,0 +1 @@
+DROP TABLE follow_roles;
\ No newline at end of file
@@ -0,0 +1,7 @@
+-- Roles the bot made so members can follow feeds and airing subscriptions themselves, deleted once nothing pings them anymore
+CREATE TABLE follow_roles (
+	id SERIAL PRIMARY KEY,
+	guild_id BIGINT NOT NULL,
+	role_id BIGINT NOT NULL UNIQUE,
+	title TEXT NOT NULL
+);
\ No newline at end of file
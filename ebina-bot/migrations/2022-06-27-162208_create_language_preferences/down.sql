@@ -0,0 +1 @@
+DROP TABLE language_preferences;
\ No newline at end of file
@@ -0,0 +1,8 @@
+-- Languages MangaDex titles and descriptions are shown in, most preferred first. Set by a guild or a user
+CREATE TABLE language_preferences (
+	id SERIAL PRIMARY KEY,
+	guild_id BIGINT UNIQUE,
+	user_id BIGINT UNIQUE,
+	languages TEXT NOT NULL,
+	CHECK ((guild_id IS NULL) <> (user_id IS NULL))
+);
\ No newline at end of file
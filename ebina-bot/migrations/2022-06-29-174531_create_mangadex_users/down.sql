@@ -0,0 +1,2 @@
+DROP TABLE user_feeds;
+DROP TABLE mangadex_users;
\ No newline at end of file
@@ -0,0 +1,18 @@
+-- MangaDex accounts members logged in with, only the refresh token is kept and it's cleared once it stops working
+CREATE TABLE mangadex_users (
+	id SERIAL PRIMARY KEY,
+	user_id BIGINT NOT NULL UNIQUE,
+	username TEXT NOT NULL,
+	refresh_token TEXT,
+	synced_at BIGINT,
+	last_chapter_at BIGINT
+);
+
+-- Series members get DMed new chapters of, imported from their MangaDex follows
+CREATE TABLE user_feeds (
+	id SERIAL PRIMARY KEY,
+	user_id BIGINT NOT NULL,
+	manga_id TEXT NOT NULL,
+	title TEXT NOT NULL,
+	UNIQUE (user_id, manga_id)
+);
\ No newline at end of file
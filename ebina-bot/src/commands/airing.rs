@@ -0,0 +1,782 @@
+use chrono::{DateTime, NaiveDate, NaiveTime, TimeZone, Timelike, Utc};
+use chrono_tz::Tz;
+use serenity::framework::standard::{macros::command, Args, CommandResult};
+use serenity::http::Http;
+use serenity::prelude::*;
+use serenity::model::prelude::*;
+use serenity::builder::CreateEmbedAuthor;
+
+use ebina_anilist::{search_specific, get_schedule_range, queries::queries::{MediaType, AiringSchedule}, AniListError};
+use serenity::utils::parse_channel;
+
+use std::collections::HashMap;
+use std::error::Error;
+
+use tracing::{error, info};
+
+use crate::commands::anilist::{parse_media_format, schedule_pages, send_anilist_error, ANI_LIST_COLOR, ANI_LIST_AUTHOR_NAME, ANI_LIST_AUTHOR_URL, ANI_LIST_AUTHOR_ICON_URL};
+use crate::diesel::prelude::*;
+use crate::establish_connection;
+use crate::models::{AiringDigest, AiringSubscription, FeedRole, NewAiringDigest, NewAiringSubscription, NewFeedRole};
+use crate::utils::follow::{create_follow_role, delete_follow_roles, existing_follow_role, forget_orphaned_follow_roles, save_follow_role, send_follow_panel};
+use crate::utils::options::Options;
+use ebina_macro::tracking;
+
+/// Who gets notified, either a channel or a user by DM.
+enum Target {
+	Channel(GuildId, ChannelId),
+	User(UserId),
+}
+
+impl Target {
+	/// Subscriptions go to the user's DMs if they asked for it or if the command was used in DMs.
+	fn from_message(msg: &Message, dm: bool) -> Self {
+		match msg.guild_id {
+			Some(guild_id) if !dm => Target::Channel(guild_id, msg.channel_id),
+			_ => Target::User(msg.author.id),
+		}
+	}
+
+	fn subscriptions(&self, connection: &PgConnection) -> QueryResult<Vec<AiringSubscription>> {
+		use crate::schema::airing_subscriptions::dsl::*;
+
+		match self {
+			Target::Channel(_, channel) => airing_subscriptions
+				.filter(channel_id.eq(channel.0 as i64))
+				.order(media_title)
+				.load::<AiringSubscription>(connection),
+			Target::User(user) => airing_subscriptions
+				.filter(user_id.eq(user.0 as i64))
+				.order(media_title)
+				.load::<AiringSubscription>(connection),
+		}
+	}
+}
+
+#[tracking("al_subscribe")]
+#[command("subscribe")]
+#[aliases("sub")]
+#[min_args(1)]
+#[usage = "[dm] [@role] <title>"]
+#[example = "dm Spy x Family"]
+#[description = "Get a message when a new episode of an anime airs. Posts in this channel unless `dm` is given, mention a role to ping it."]
+pub async fn anilist_subscribe(ctx: &Context, msg: &Message, mut args: Args) -> CommandResult {
+	let ani_list_author = CreateEmbedAuthor::default()
+		.icon_url(ANI_LIST_AUTHOR_ICON_URL)
+		.name(ANI_LIST_AUTHOR_NAME)
+		.url(ANI_LIST_AUTHOR_URL)
+		.to_owned();
+
+	let mut dm = false;
+	let mut title = Vec::<String>::new();
+	for arg in args.iter::<String>().flatten() {
+		if title.is_empty() && arg.to_lowercase() == "dm" {
+			dm = true;
+		} else if !arg.starts_with("<@&") {
+			title.push(arg);
+		}
+	}
+	if title.is_empty() {
+		msg.reply(ctx, "Give the title of the anime to subscribe to, like `al subscribe [dm] [@role] <title>`").await?;
+		return Ok(())
+	}
+	let target = Target::from_message(msg, dm);
+
+	// Role pings only make sense in a channel
+	let role = match target {
+		Target::Channel(_, _) => msg.mention_roles.first().copied(),
+		Target::User(_) => None,
+	};
+
+	if let Target::Channel(_, _) = target {
+		if !can_manage_channels(ctx, msg).await {
+			msg.reply(ctx, "You need the Manage Channels permission to subscribe this channel, use `dm` to get them in your DMs instead").await?;
+			return Ok(())
+		}
+	}
+
+	let results = match search_specific(title.join(" "), Some(MediaType::Anime)).await {
+		Ok(v) => v,
+		Err(why) => return send_anilist_error(ctx, msg, why).await,
+	};
+	let media_list = results.page.and_then(|p| p.media).unwrap_or_default().into_iter().flatten().collect::<Vec<_>>();
+
+	if media_list.is_empty() {
+		return send_anilist_error(ctx, msg, AniListError::NoData).await;
+	}
+
+	let options = media_list.iter().map(|m| {
+		let title = m.title.as_ref().and_then(|t| t.user_preferred.clone()).unwrap_or_default();
+		match m.status {
+			Some(status) => format!("{} ({})", title, status),
+			None => title,
+		}
+	}).collect::<Vec<String>>();
+
+	let index = Options::new(ctx, msg)
+		.title("Enter the number corresponding the anime you want to subscribe to!")
+		.options(options)
+		.colour(ANI_LIST_COLOR)
+		.author(ani_list_author)
+		.send()
+		.await;
+	if index.is_none() {
+		return Ok(())
+	}
+	let media = &media_list[index.unwrap().0];
+	let title = media.title.as_ref().and_then(|t| t.user_preferred.clone()).unwrap_or_default();
+
+	{
+		use crate::schema::airing_subscriptions::dsl::*;
+
+		let connection = establish_connection();
+		let existing = target.subscriptions(&connection)?
+			.into_iter()
+			.find(|s| s.media_id == media.id);
+
+		let subscription_id = match existing {
+			Some(subscription) => subscription.id,
+			None => {
+				let (guild, channel, user) = match &target {
+					Target::Channel(g, c) => (Some(g.0 as i64), Some(c.0 as i64), None),
+					// Remember where DM subscriptions were made so the guild's digest can highlight them
+					Target::User(u) => (msg.guild_id.map(|g| g.0 as i64), None, Some(u.0 as i64)),
+				};
+				let new_subscription = NewAiringSubscription {
+					guild_id: guild,
+					channel_id: channel,
+					user_id: user,
+					media_id: &media.id,
+					media_title: &title,
+				};
+				diesel::insert_into(airing_subscriptions)
+					.values(&new_subscription)
+					.get_result::<AiringSubscription>(&connection)?
+					.id
+			},
+		};
+
+		// Subscribing again replaces the pinged role, follow roles stay until they're removed with `al unrole`
+		if let Target::Channel(guild, _) = &target {
+			clear_ping_roles(&connection, subscription_id)?;
+			if let Some(role) = role {
+				add_subscription_role(&connection, *guild, subscription_id, role)?;
+			}
+		}
+	}
+
+	let reply = match (&target, role) {
+		(Target::User(_), _) => format!("I'll DM you when a new episode of {} airs", title),
+		(Target::Channel(_, _), Some(r)) => format!("I'll post here and ping <@&{}> when a new episode of {} airs", r.0, title),
+		(Target::Channel(_, _), None) => format!("I'll post here when a new episode of {} airs", title),
+	};
+	msg.reply(ctx, reply).await?;
+
+	Ok(())
+}
+
+#[tracking("al_unsubscribe")]
+#[command("unsubscribe")]
+#[aliases("unsub")]
+#[usage = "[dm]"]
+#[description = "Stop getting messages for an anime, pick from this channel's subscriptions or your DM ones with `dm`"]
+pub async fn anilist_unsubscribe(ctx: &Context, msg: &Message, args: Args) -> CommandResult {
+	use crate::schema::airing_subscriptions::dsl::*;
+
+	let target = Target::from_message(msg, args.rest().trim().eq_ignore_ascii_case("dm"));
+
+	if let Target::Channel(_, _) = target {
+		if !can_manage_channels(ctx, msg).await {
+			msg.reply(ctx, "You need the Manage Channels permission to change this channel's subscriptions").await?;
+			return Ok(())
+		}
+	}
+
+	let connection = establish_connection();
+	let subscriptions = target.subscriptions(&connection)?;
+
+	if subscriptions.is_empty() {
+		msg.reply(ctx, "There are no subscriptions here").await?;
+		return Ok(())
+	}
+
+	let ani_list_author = CreateEmbedAuthor::default()
+		.icon_url(ANI_LIST_AUTHOR_ICON_URL)
+		.name(ANI_LIST_AUTHOR_NAME)
+		.url(ANI_LIST_AUTHOR_URL)
+		.to_owned();
+
+	let index = Options::new(ctx, msg)
+		.title("Enter the number corresponding the anime you want to unsubscribe from!")
+		.options(subscriptions.iter().map(|s| s.media_title.clone()).collect())
+		.colour(ANI_LIST_COLOR)
+		.author(ani_list_author)
+		.send()
+		.await;
+	if index.is_none() {
+		return Ok(())
+	}
+	let subscription = &subscriptions[index.unwrap().0];
+
+	diesel::delete(airing_subscriptions.find(subscription.id)).execute(&connection)?;
+	let orphaned = forget_orphaned_follow_roles(&connection);
+	delete_follow_roles(&ctx.http, orphaned).await;
+
+	msg.reply(ctx, format!("Unsubscribed from {}", subscription.media_title)).await?;
+	Ok(())
+}
+
+#[tracking("al_subscriptions")]
+#[command("subscriptions")]
+#[aliases("subs")]
+#[usage = "[dm]"]
+#[description = "Lists this channel's anime subscriptions, or your DM ones with `dm`"]
+pub async fn anilist_subscriptions(ctx: &Context, msg: &Message, args: Args) -> CommandResult {
+	let target = Target::from_message(msg, args.rest().trim().eq_ignore_ascii_case("dm"));
+
+	let connection = establish_connection();
+	let subscriptions = target.subscriptions(&connection)?;
+
+	let ani_list_author = CreateEmbedAuthor::default()
+		.icon_url(ANI_LIST_AUTHOR_ICON_URL)
+		.name(ANI_LIST_AUTHOR_NAME)
+		.url(ANI_LIST_AUTHOR_URL)
+		.to_owned();
+
+	let roles = subscription_roles(&connection)?;
+
+	let lines = subscriptions.iter().map(|s| {
+		let mut line = format!("[{}](https://anilist.co/anime/{})", s.media_title, s.media_id);
+		if let Some(pinged) = roles.get(&s.id) {
+			line.push_str(&format!(" - {}", mention_roles(pinged)));
+		}
+		line
+	}).collect::<Vec<String>>();
+
+	msg.channel_id.send_message(&ctx.http, |m| {
+		m.embed(|e| {
+			e.title("Airing Subscriptions");
+			if lines.is_empty() {
+				e.description("There are no subscriptions here, add one with `al subscribe <title>`");
+			} else {
+				e.description(lines.join("\n"));
+			}
+			e.color(ANI_LIST_COLOR);
+			e.set_author(ani_list_author);
+			e
+		});
+		m
+	}).await?;
+
+	Ok(())
+}
+
+#[tracking("al_role")]
+#[command("role")]
+#[only_in("guilds")]
+#[required_permissions("MANAGE_ROLES")]
+#[min_args(1)]
+#[usage = "<@role>"]
+#[description = "Pings a role when a new episode of one of this channel's subscriptions airs"]
+pub async fn anilist_role(ctx: &Context, msg: &Message) -> CommandResult {
+	let role = match msg.mention_roles.first() {
+		Some(v) => *v,
+		None => {
+			msg.reply(ctx, "Mention the role to ping").await?;
+			return Ok(())
+		}
+	};
+
+	let subscription = match pick_channel_subscription(ctx, msg, "Enter the number corresponding the anime you want to ping the role for!").await? {
+		Some(v) => v,
+		None => return Ok(()),
+	};
+
+	let connection = establish_connection();
+	add_subscription_role(&connection, msg.guild_id.unwrap(), subscription.id, role)?;
+
+	msg.reply(ctx, format!("I'll ping <@&{}> when a new episode of {} airs", role.0, subscription.media_title)).await?;
+	Ok(())
+}
+
+#[tracking("al_unrole")]
+#[command("unrole")]
+#[only_in("guilds")]
+#[required_permissions("MANAGE_ROLES")]
+#[usage = "[@role]"]
+#[description = "Stops pinging a role for one of this channel's subscriptions, every role if none is mentioned"]
+pub async fn anilist_unrole(ctx: &Context, msg: &Message) -> CommandResult {
+	let role = msg.mention_roles.first().copied();
+
+	let subscription = match pick_channel_subscription(ctx, msg, "Enter the number corresponding the anime you want to stop pinging for!").await? {
+		Some(v) => v,
+		None => return Ok(()),
+	};
+
+	let connection = establish_connection();
+	let removed = clear_subscription_roles(&connection, subscription.id, role)?;
+	let orphaned = forget_orphaned_follow_roles(&connection);
+	delete_follow_roles(&ctx.http, orphaned).await;
+
+	if removed == 0 {
+		msg.reply(ctx, format!("No roles were pinged for {}", subscription.media_title)).await?;
+	} else {
+		msg.reply(ctx, format!("Stopped pinging for {}", subscription.media_title)).await?;
+	}
+	Ok(())
+}
+
+#[tracking("al_follow")]
+#[command("follow")]
+#[only_in("guilds")]
+#[required_permissions("MANAGE_ROLES")]
+#[description = "Posts a button members can press to get pinged when a new episode of one of this channel's subscriptions airs"]
+pub async fn anilist_follow(ctx: &Context, msg: &Message) -> CommandResult {
+	let guild = msg.guild_id.unwrap();
+
+	let subscription = match pick_channel_subscription(ctx, msg, "Enter the number corresponding the anime members should be able to follow!").await? {
+		Some(v) => v,
+		None => return Ok(()),
+	};
+
+	let connection = establish_connection();
+	// Reuse the follow role the subscription already pings, panels can be posted again
+	let pinged = subscription_roles(&connection)?.remove(&subscription.id).unwrap_or_default();
+	let existing = existing_follow_role(&connection, &pinged)?.map(|f| RoleId(f.role_id as u64));
+	let role = match existing {
+		Some(v) => v,
+		None => create_follow_role(&ctx.http, guild, &subscription.media_title).await?,
+	};
+
+	// A new role is saved along with its ping, pruning could delete it in between otherwise
+	let saved = connection.transaction::<_, diesel::result::Error, _>(|| {
+		if existing.is_none() {
+			save_follow_role(&connection, guild, role, &subscription.media_title)?;
+		}
+		add_subscription_role(&connection, guild, subscription.id, role)
+	});
+	if let Err(why) = saved {
+		if existing.is_none() {
+			guild.delete_role(&ctx.http, role).await?;
+		}
+		return Err(why.into());
+	}
+
+	send_follow_panel(&ctx.http, msg.channel_id, role, &subscription.media_title, ANI_LIST_COLOR).await?;
+	Ok(())
+}
+
+/// Lets the author pick one of the channel's subscriptions, if they may manage it.
+async fn pick_channel_subscription(ctx: &Context, msg: &Message, title: &str) -> CommandResult<Option<AiringSubscription>> {
+	if !can_manage_channels(ctx, msg).await {
+		msg.reply(ctx, "You need the Manage Channels permission to change this channel's subscriptions").await?;
+		return Ok(None)
+	}
+
+	let connection = establish_connection();
+	let mut subscriptions = Target::from_message(msg, false).subscriptions(&connection)?;
+
+	if subscriptions.is_empty() {
+		msg.reply(ctx, "There are no subscriptions here, add one with `al subscribe <title>`").await?;
+		return Ok(None)
+	}
+
+	let ani_list_author = CreateEmbedAuthor::default()
+		.icon_url(ANI_LIST_AUTHOR_ICON_URL)
+		.name(ANI_LIST_AUTHOR_NAME)
+		.url(ANI_LIST_AUTHOR_URL)
+		.to_owned();
+
+	let index = Options::new(ctx, msg)
+		.title(title)
+		.options(subscriptions.iter().map(|s| s.media_title.clone()).collect())
+		.colour(ANI_LIST_COLOR)
+		.author(ani_list_author)
+		.send()
+		.await;
+	Ok(index.map(|i| subscriptions.swap_remove(i.0)))
+}
+
+/// Posts every episode that aired between `from` and `to` to its subscribers.
+///
+/// Fails when the subscriptions or the schedule couldn't be loaded, the same window should be checked again then.
+pub async fn check_airing(http: &Http, from: chrono::DateTime<Utc>, to: chrono::DateTime<Utc>) -> Result<(), Box<dyn Error + Send + Sync>> {
+	use crate::schema::airing_subscriptions::dsl::*;
+
+	let connection = establish_connection();
+
+	let subscriptions = airing_subscriptions.load::<AiringSubscription>(&connection)?;
+	if subscriptions.is_empty() {
+		return Ok(());
+	}
+
+	let roles = match subscription_roles(&connection) {
+		Ok(v) => v,
+		Err(why) => {
+			error!("Error loading airing subscription roles: {:?}", why);
+			HashMap::new()
+		}
+	};
+
+	let airings = get_schedule_range(from, to).await?;
+
+	for airing in &airings {
+		let airing_media_id = match &airing.media {
+			Some(v) => v.id,
+			None => continue,
+		};
+		// The last episode check keeps restarts and overlapping windows from posting twice
+		for subscription in subscriptions.iter().filter(|s| s.media_id == airing_media_id && s.last_episode < airing.episode) {
+			let pinged = roles.get(&subscription.id).map(Vec::as_slice).unwrap_or_default();
+			if let Err(why) = notify(http, subscription, pinged, airing).await {
+				error!("Error sending airing notification {}: {:?}", subscription.id, why);
+				continue;
+			}
+			info!("Sent episode {} of {} to subscription {}", airing.episode, subscription.media_title, subscription.id);
+			if let Err(why) = diesel::update(airing_subscriptions.find(subscription.id))
+				.set(last_episode.eq(airing.episode))
+				.execute(&connection) {
+				error!("Error updating airing subscription {}: {:?}", subscription.id, why);
+			}
+		}
+	}
+	Ok(())
+}
+
+async fn notify(http: &Http, subscription: &AiringSubscription, roles: &[i64], airing: &AiringSchedule) -> serenity::Result<()> {
+	let channel = match (subscription.channel_id, subscription.user_id) {
+		(Some(channel), _) => ChannelId(channel as u64),
+		(None, Some(user)) => UserId(user as u64).create_dm_channel(http).await?.id,
+		(None, None) => return Ok(()),
+	};
+
+	let media = airing.media.as_ref();
+	let title = media
+		.and_then(|m| m.title.as_ref())
+		.and_then(|t| t.user_preferred.clone())
+		.unwrap_or_else(|| subscription.media_title.clone());
+	let cover = media
+		.and_then(|m| m.cover_image.as_ref())
+		.and_then(|c| c.large.clone());
+
+	channel.send_message(http, |m| {
+		if !roles.is_empty() {
+			m.content(mention_roles(roles));
+		}
+		m.embed(|e| {
+			e.title(format!("Episode {} of {} just aired!", airing.episode, title));
+			e.url(format!("https://anilist.co/anime/{}", subscription.media_id));
+			if let Some(episodes) = media.and_then(|m| m.episodes) {
+				e.description(format!("Episode {}/{}, aired <t:{}:R>", airing.episode, episodes, airing.airing_at));
+			} else {
+				e.description(format!("Aired <t:{}:R>", airing.airing_at));
+			}
+			if let Some(cover) = cover {
+				e.thumbnail(cover);
+			}
+			e.color(ANI_LIST_COLOR);
+			e.author(|a| {
+				a.name(ANI_LIST_AUTHOR_NAME);
+				a.icon_url(ANI_LIST_AUTHOR_ICON_URL);
+				a.url(ANI_LIST_AUTHOR_URL);
+				a
+			});
+			e
+		});
+		m
+	}).await?;
+	Ok(())
+}
+
+#[tracking("al_digest")]
+#[command("digest")]
+#[only_in("guilds")]
+#[required_permissions("MANAGE_GUILD")]
+#[usage = "[channel [#channel] | time <HH:MM> | timezone <Area/City> | formats <tv,ona,...|all> | adult <on|off> | off]"]
+#[example = "timezone Europe/Stockholm"]
+#[description = "Posts the day's airing schedule to a channel every morning, highlighting shows members are subscribed to. Without arguments it shows the current settings."]
+pub async fn anilist_digest(ctx: &Context, msg: &Message, mut args: Args) -> CommandResult {
+	use crate::schema::airing_digests::dsl::*;
+
+	let guild = msg.guild_id.unwrap();
+	let connection = establish_connection();
+
+	let setting = args.single::<String>().unwrap_or_default().to_lowercase();
+	let value = args.rest().trim().to_string();
+
+	if setting == "channel" {
+		let channel = parse_channel(&value).map(ChannelId).unwrap_or(msg.channel_id);
+		let new_digest = NewAiringDigest {
+			guild_id: &(guild.0 as i64),
+			channel_id: &(channel.0 as i64),
+		};
+		diesel::insert_into(airing_digests)
+			.values(&new_digest)
+			.on_conflict(guild_id)
+			.do_update()
+			.set(channel_id.eq(channel.0 as i64))
+			.execute(&connection)?;
+		msg.reply(ctx, format!("The airing digest will be posted in <#{}>", channel.0)).await?;
+		return Ok(())
+	}
+
+	let digest = match airing_digests.filter(guild_id.eq(guild.0 as i64)).first::<AiringDigest>(&connection).optional()? {
+		Some(v) => v,
+		None => {
+			msg.reply(ctx, "There is no airing digest here yet, pick a channel for it with `al digest channel #channel`").await?;
+			return Ok(())
+		}
+	};
+	let target = airing_digests.find(digest.id);
+
+	let reply = match setting.as_str() {
+		"time" => match NaiveTime::parse_from_str(&value, "%H:%M") {
+			Ok(time) => {
+				diesel::update(target)
+					.set((post_hour.eq(time.hour() as i32), post_minute.eq(time.minute() as i32)))
+					.execute(&connection)?;
+				format!("The airing digest will be posted at {} {}", time.format("%H:%M"), digest.timezone)
+			},
+			Err(_) => "Give the time as HH:MM, for example `08:30`".to_string(),
+		},
+		"timezone" | "tz" => match value.parse::<Tz>() {
+			Ok(tz) => {
+				diesel::update(target)
+					.set(timezone.eq(tz.name()))
+					.execute(&connection)?;
+				format!("The airing digest now follows {}", tz.name())
+			},
+			Err(_) => "Unknown timezone, use a name like `Europe/Stockholm` or `America/New_York`".to_string(),
+		},
+		"formats" | "format" => {
+			let list = value.split(|c: char| c == ',' || c.is_whitespace())
+				.filter(|f| !f.is_empty() && !f.eq_ignore_ascii_case("all"))
+				.map(|f| f.to_lowercase())
+				.collect::<Vec<String>>();
+			if let Some(unknown) = list.iter().find(|f| parse_media_format(f).is_none()) {
+				format!("Unknown format {}, pick from tv, short, movie, ona, ova and special", unknown)
+			} else {
+				diesel::update(target)
+					.set(formats.eq(list.join(",")))
+					.execute(&connection)?;
+				if list.is_empty() {
+					"The airing digest will include every format".to_string()
+				} else {
+					format!("The airing digest will only include {}", list.join(", "))
+				}
+			}
+		},
+		"adult" => match value.to_lowercase().as_str() {
+			"on" | "yes" | "true" => {
+				diesel::update(target).set(exclude_adult.eq(false)).execute(&connection)?;
+				"The airing digest will include adult shows".to_string()
+			},
+			"off" | "no" | "false" => {
+				diesel::update(target).set(exclude_adult.eq(true)).execute(&connection)?;
+				"The airing digest will leave out adult shows".to_string()
+			},
+			_ => "Use `al digest adult on` or `al digest adult off`".to_string(),
+		},
+		"off" | "disable" | "remove" => {
+			diesel::delete(target).execute(&connection)?;
+			"The airing digest has been turned off".to_string()
+		},
+		_ => {
+			let ani_list_author = CreateEmbedAuthor::default()
+				.icon_url(ANI_LIST_AUTHOR_ICON_URL)
+				.name(ANI_LIST_AUTHOR_NAME)
+				.url(ANI_LIST_AUTHOR_URL)
+				.to_owned();
+
+			msg.channel_id.send_message(&ctx.http, |m| {
+				m.embed(|e| {
+					e.title("Airing Digest");
+					e.field("Channel", format!("<#{}>", digest.channel_id), true);
+					e.field("Time", format!("{:02}:{:02} {}", digest.post_hour, digest.post_minute, digest.timezone), true);
+					e.field("Formats", if digest.formats.is_empty() { "All".to_string() } else { digest.formats.replace(',', ", ") }, true);
+					e.field("Adult shows", if digest.exclude_adult { "Excluded" } else { "Included" }, true);
+					e.color(ANI_LIST_COLOR);
+					e.set_author(ani_list_author);
+					e
+				});
+				m
+			}).await?;
+			return Ok(())
+		},
+	};
+	msg.reply(ctx, reply).await?;
+
+	Ok(())
+}
+
+/// Posts the day's schedule for every digest whose post time has passed today in its own timezone.
+pub async fn check_digests(http: &Http) {
+	use crate::schema::airing_digests::dsl::*;
+
+	let connection = establish_connection();
+
+	let digests = match airing_digests.load::<AiringDigest>(&connection) {
+		Ok(v) => v,
+		Err(why) => {
+			error!("Error loading airing digests: {:?}", why);
+			return;
+		}
+	};
+
+	let now = Utc::now();
+	for digest in digests {
+		let tz = digest.timezone.parse::<Tz>().unwrap_or(Tz::UTC);
+		let local_now = now.with_timezone(&tz);
+		let today = local_now.date().naive_local();
+
+		let post_time = NaiveTime::from_hms_opt(digest.post_hour as u32, digest.post_minute as u32, 0).unwrap_or_else(|| NaiveTime::from_hms(0, 0, 0));
+		if local_now.time() < post_time {
+			continue;
+		}
+		if let Some(last) = digest.last_posted {
+			if Utc.timestamp(last, 0).with_timezone(&tz).date().naive_local() == today {
+				continue;
+			}
+		}
+
+		let highlight = {
+			use crate::schema::airing_subscriptions::dsl::*;
+
+			airing_subscriptions
+				.filter(guild_id.eq(digest.guild_id))
+				.select(media_id)
+				.load::<i32>(&connection)
+				.unwrap_or_default()
+		};
+
+		// Leave it for the next check if AniList or Discord are having trouble
+		if let Err(why) = post_digest(http, &digest, &highlight, tz, today).await {
+			error!("Error posting airing digest for guild {}: {}", digest.guild_id, why);
+			continue;
+		}
+
+		if let Err(why) = diesel::update(airing_digests.find(digest.id))
+			.set(last_posted.eq(now.timestamp()))
+			.execute(&connection) {
+			error!("Error updating airing digest {}: {:?}", digest.id, why);
+		}
+	}
+}
+
+/// Posts a guild's digest, `highlight` holds the media the guild is subscribed to.
+///
+/// Fails when the schedule couldn't be loaded or its first page couldn't be sent. Once a page is out the digest
+/// counts as posted, later pages that fail are only logged so the day isn't posted twice.
+async fn post_digest(http: &Http, digest: &AiringDigest, highlight: &[i32], tz: Tz, today: NaiveDate) -> Result<(), Box<dyn Error + Send + Sync>> {
+	let from = local_midnight(tz, today);
+	let to = local_midnight(tz, today.succ());
+	let allowed_formats = digest.formats.split(',').filter_map(parse_media_format).collect::<Vec<_>>();
+
+	let airings = get_schedule_range(from, to).await?
+		.into_iter()
+		.filter(|airing| {
+			let media = match &airing.media {
+				Some(v) => v,
+				None => return false,
+			};
+			if digest.exclude_adult && media.is_adult.unwrap_or(false) {
+				return false;
+			}
+			allowed_formats.is_empty() || media.format.map(|f| allowed_formats.contains(&f)).unwrap_or(false)
+		})
+		.collect::<Vec<AiringSchedule>>();
+
+	let description = format!("Airing on {} ({})", today.format("%A, %B %-d"), tz.name());
+	let channel = ChannelId(digest.channel_id as u64);
+	// Every page gets its own message, a single message can't hold more than 6000 characters of embeds
+	for (index, page) in schedule_pages(description, &airings, Utc::now(), highlight).into_iter().enumerate() {
+		match channel.send_message(http, |m| m.set_embed(page)).await {
+			Ok(_) => {},
+			Err(why) if index == 0 => return Err(why.into()),
+			Err(why) => error!("Error posting page {} of the airing digest for guild {}: {:?}", index + 1, digest.guild_id, why),
+		}
+	}
+	info!("Posted airing digest for guild {}", digest.guild_id);
+
+	Ok(())
+}
+
+/// The start of a day in `tz` as UTC, what AniList's schedule is filtered by.
+fn local_midnight(tz: Tz, date: NaiveDate) -> DateTime<Utc> {
+	let midnight = date.and_hms(0, 0, 0);
+	tz.from_local_datetime(&midnight)
+		.earliest()
+		.map(|d| d.with_timezone(&Utc))
+		.unwrap_or_else(|| Utc.from_utc_datetime(&midnight))
+}
+
+/// The roles pinged for each airing subscription, keyed by subscription id.
+fn subscription_roles(connection: &PgConnection) -> QueryResult<HashMap<i32, Vec<i64>>> {
+	use crate::schema::feed_roles::dsl::*;
+
+	let mut roles = HashMap::<i32, Vec<i64>>::new();
+	for role in feed_roles.filter(airing_subscription_id.is_not_null()).load::<FeedRole>(connection)? {
+		if let Some(subscription) = role.airing_subscription_id {
+			roles.entry(subscription).or_default().push(role.role_id);
+		}
+	}
+	Ok(roles)
+}
+
+fn add_subscription_role(connection: &PgConnection, guild: GuildId, subscription: i32, role: RoleId) -> QueryResult<()> {
+	use crate::schema::feed_roles::dsl::*;
+
+	let exists = feed_roles
+		.filter(airing_subscription_id.eq(subscription))
+		.filter(role_id.eq(role.0 as i64))
+		.first::<FeedRole>(connection)
+		.optional()?
+		.is_some();
+	if !exists {
+		diesel::insert_into(feed_roles)
+			.values(NewFeedRole {
+				guild_id: &(guild.0 as i64),
+				feed_id: None,
+				airing_subscription_id: Some(subscription),
+				manga_id: None,
+				role_id: &(role.0 as i64),
+			})
+			.execute(connection)?;
+	}
+	Ok(())
+}
+
+/// Stops pinging the role for a subscription, or every role when none is given.
+fn clear_subscription_roles(connection: &PgConnection, subscription: i32, role: Option<RoleId>) -> QueryResult<usize> {
+	use crate::schema::feed_roles::dsl::*;
+
+	let rows = feed_roles.filter(airing_subscription_id.eq(subscription));
+	match role {
+		Some(role) => diesel::delete(rows.filter(role_id.eq(role.0 as i64))).execute(connection),
+		None => diesel::delete(rows).execute(connection),
+	}
+}
+
+/// Removes the roles mentioned when subscribing, leaving the subscription's follow roles.
+fn clear_ping_roles(connection: &PgConnection, subscription: i32) -> QueryResult<usize> {
+	use crate::schema::feed_roles::dsl::*;
+
+	let follows = crate::schema::follow_roles::table
+		.select(crate::schema::follow_roles::role_id)
+		.load::<i64>(connection)?;
+	diesel::delete(feed_roles
+		.filter(airing_subscription_id.eq(subscription))
+		.filter(role_id.ne_all(follows)))
+		.execute(connection)
+}
+
+pub fn mention_roles(roles: &[i64]) -> String {
+	roles.iter().map(|r| format!("<@&{}>", r)).collect::<Vec<_>>().join(" ")
+}
+
+async fn can_manage_channels(ctx: &Context, msg: &Message) -> bool {
+	match msg.member(ctx).await {
+		Ok(member) => member.permissions(ctx).map(|p| p.manage_channels()).unwrap_or(false),
+		Err(_) => false,
+	}
+}
@@ -1,23 +1,44 @@
-use chrono::Utc;
+use std::time::Duration;
+
+use chrono::{Datelike, Utc};
 use serenity::framework::standard::{macros::command, Args, CommandResult};
 use serenity::prelude::*;
 use serenity::model::prelude::*;
 use serenity::utils::Colour;
-use serenity::builder::CreateEmbedAuthor;
+use serenity::builder::{CreateActionRow, CreateEmbedAuthor, CreateEmbed, CreateSelectMenu, CreateSelectMenuOption};
+use serenity::futures::stream::StreamExt;
+
+use once_cell::sync::Lazy;
 
-use html2md::parse_html;
+use regex::Regex;
 
-use ebina_anilist::{search, search_specific, get_schedule, queries::queries::MediaType};
+use ebina_anilist::{discover, get_collections, get_random_media, search, search_specific, search_character, search_staff, search_studio, get_media, get_user, get_schedule_range, get_full_season, current_season, AniListError};
+use ebina_anilist::queries::queries::{MediaType, MediaSeason, MediaFormat, MediaSort, MediaStatus, MediaRankType, Media, Media3, Media4, AiringSchedule, Collections};
 
 use crate::anilist_embed;
 use ebina_macro::tracking;
 use crate::utils::options::Options;
+use crate::utils::paginator::Paginator;
+use crate::establish_connection;
+use crate::diesel::prelude::*;
+use crate::models::{AniListUser, NewAniListUser, NsfwPolicy};
+use crate::utils::nsfw::nsfw_policy;
 
-const ANI_LIST_COLOR: serenity::utils::Colour = Colour::from_rgb(43, 45, 66);
+use tracing::error;
+
+pub const ANI_LIST_COLOR: serenity::utils::Colour = Colour::from_rgb(43, 45, 66);
 // Anilist author constants
-const ANI_LIST_AUTHOR_NAME: &str = "Anilist";
-const ANI_LIST_AUTHOR_URL: &str = "https://anilist.co/";
-const ANI_LIST_AUTHOR_ICON_URL: &str = "https://anilist.co/img/icons/apple-touch-icon.png";
+pub const ANI_LIST_AUTHOR_NAME: &str = "Anilist";
+pub const ANI_LIST_AUTHOR_URL: &str = "https://anilist.co/";
+pub const ANI_LIST_AUTHOR_ICON_URL: &str = "https://anilist.co/img/icons/apple-touch-icon.png";
+
+const RELATED_MENU_ID: &str = "anilist_related";
+
+/// Discord rejects embeds with more characters than this in total.
+const EMBED_LIMIT: usize = 6000;
+
+/// AniList's `~!spoiler!~` markup, which can span lines.
+static SPOILER: Lazy<Regex> = Lazy::new(|| Regex::new(r"(?s)~!(.*?)!~").unwrap());
 
 /// Searches Anlist including both manga and anime.
 #[tracking("al_search")]
@@ -44,72 +65,1134 @@ pub async fn anilist_anime(ctx: &Context, msg: &Message, args: Args) -> CommandR
 
 #[tracking("al_schedule")]
 #[command("schedule")]
-pub async fn anilist_schedule(ctx: &Context, msg: &Message) -> CommandResult {
+#[usage = "[today|tomorrow|monday..sunday|week]"]
+#[example = "week"]
+#[description = "Shows the anime airing schedule for a day or the whole week"]
+pub async fn anilist_schedule(ctx: &Context, msg: &Message, args: Args) -> CommandResult {
 	//Convert the current time to UTC
 	let today = Utc::now();
-	//Get the schedule for the current day
-	let results = get_schedule(today).await.unwrap();
+	let today_date = today.date();
+
+	// Figure out which days to show
+	let (first_day, days) = match args.rest().trim().to_lowercase().as_str() {
+		"" | "today" => (today_date, 1),
+		"tomorrow" => (today_date + chrono::Duration::days(1), 1),
+		"week" => (today_date, 7),
+		other => match other.parse::<chrono::Weekday>() {
+			Ok(weekday) => {
+				let days_until = (7 + weekday.num_days_from_monday() as i64 - today_date.weekday().num_days_from_monday() as i64) % 7;
+				(today_date + chrono::Duration::days(days_until), 1)
+			},
+			Err(_) => {
+				msg.reply(ctx, format!("I don't know what `{}` means, try today, tomorrow, a weekday or week", other)).await?;
+				return Ok(())
+			}
+		},
+	};
 
-	let schedule = results.page.unwrap().airing_schedules.unwrap();
+	let from = first_day.and_hms(0, 0, 0);
+	let to = (first_day + chrono::Duration::days(days)).and_hms(0, 0, 0) - chrono::Duration::seconds(1);
 
-	// Anilist author
-	let ani_list_author = CreateEmbedAuthor::default()
-		.icon_url(ANI_LIST_AUTHOR_ICON_URL)
-		.name(ANI_LIST_AUTHOR_NAME)
-		.url(ANI_LIST_AUTHOR_URL)
-		.to_owned();
+	//Get the schedule for the days
+	let mut schedule = match get_schedule_range(from, to).await {
+		Ok(v) => v,
+		Err(why) => return send_anilist_error(ctx, msg, why).await,
+	};
+	if nsfw_policy(ctx, msg).await.hides() {
+		schedule.retain(|a| !a.media.as_ref().and_then(|m| m.is_adult).unwrap_or(false));
+	}
+
+	let mut pages = Vec::<CreateEmbed>::new();
+	for day in 0..days {
+		let date = first_day + chrono::Duration::days(day);
+		let day_start = date.and_hms(0, 0, 0).timestamp();
+		let day_end = day_start + 24 * 60 * 60;
+		let airings = schedule.iter()
+			.filter(|a| (a.airing_at as i64) >= day_start && (a.airing_at as i64) < day_end)
+			.cloned()
+			.collect::<Vec<_>>();
+		let description = format!("Schedule for {}", date.format("%A %Y-%m-%d"));
+		pages.extend(schedule_pages(description, &airings, today, &[]));
+	}
 
-	// temp schedule
-	let temp_schedule = schedule.clone();
+	Paginator::new(ctx, msg)
+		.pages(pages)
+		.send()
+		.await?;
+
+	Ok(())
+}
 
+/// Lists the airings of a day, labelling the next one to air and starring the media in `highlight`.
+pub fn schedule_lines(schedule: &[AiringSchedule], now: chrono::DateTime<Utc>, highlight: &[i32]) -> Vec<String> {
 	// Find the next airing
-	let next_airing = temp_schedule.iter().find(|airing| {
-		let airing_at = chrono::DateTime::<Utc>::from_utc(
-			chrono::NaiveDateTime::from_timestamp(airing.as_ref().unwrap().airing_at as i64, 0),
-			Utc,
-		);
-		airing_at > today
-	});
+	let next_airing = schedule.iter().find(|airing| airing.airing_at as i64 > now.timestamp());
 
 	// Create vector of strings representing the airing schedule
 	let mut schedule_strs: Vec<String> = Vec::new();
-	for schedule in schedule {
-		let mut schedule_str = String::new();
-		// Parse airing at as SystemTime
-		let s = std::time::UNIX_EPOCH + std::time::Duration::from_secs(schedule.as_ref().unwrap().airing_at.try_into().unwrap());
-		// Parse as DateTime with UTC timezone
-		let dt = chrono::DateTime::<Utc>::from(s);
-		let airing_at_str = dt.format("%H:%M").to_string();
-		schedule_str.push_str(airing_at_str.as_str());
-
-		schedule_str.push_str(" - ");
-		schedule_str.push_str(schedule.as_ref().unwrap().media.as_ref().unwrap().title.as_ref().unwrap().user_preferred.as_ref().unwrap());
+	for airing in schedule {
+		let title = airing.media.as_ref()
+			.and_then(|m| m.title.as_ref())
+			.and_then(|t| t.user_preferred.clone())
+			.unwrap_or_default();
+		// Discord shows the timestamp in the timezone of whoever reads it
+		let mut schedule_str = match airing.media.as_ref().map(|m| m.id) {
+			Some(media_id) if highlight.contains(&media_id) => format!("<t:{}:t> - ⭐ **{}** (Ep {})", airing.airing_at, title, airing.episode),
+			_ => format!("<t:{}:t> - {} (Ep {})", airing.airing_at, title, airing.episode),
+		};
 		// Add label to next airing
-		if let Some(airing) = next_airing {
-			if schedule.as_ref().unwrap().media.as_ref().unwrap().id == airing.as_ref().unwrap().media.as_ref().unwrap().id {
+		if let Some(next) = next_airing {
+			if next.airing_at == airing.airing_at && next.episode == airing.episode && next.media.as_ref().map(|m| m.id) == airing.media.as_ref().map(|m| m.id) {
 				schedule_str.push_str(" **(Next)**");
 			}
 		}
 		schedule_strs.push(schedule_str);
 	}
+	schedule_strs
+}
+
+/// Builds the embeds for a day of the schedule, splitting it if it doesn't fit in one.
+pub fn schedule_pages(description: String, schedule: &[AiringSchedule], now: chrono::DateTime<Utc>, highlight: &[i32]) -> Vec<CreateEmbed> {
+	// Anilist author
+	let ani_list_author = CreateEmbedAuthor::default()
+		.icon_url(ANI_LIST_AUTHOR_ICON_URL)
+		.name(ANI_LIST_AUTHOR_NAME)
+		.url(ANI_LIST_AUTHOR_URL)
+		.to_owned();
+
+	let lines = schedule_lines(schedule, now, highlight);
+	let mut chunks = vec![Vec::<String>::new()];
+	let mut length = 0;
+	for line in lines {
+		// Leave room for the description in the 4096 character limit
+		if length + line.len() > 3800 {
+			chunks.push(Vec::new());
+			length = 0;
+		}
+		length += line.len() + 1;
+		chunks.last_mut().unwrap().push(line);
+	}
+
+	chunks.into_iter().map(|chunk| {
+		let mut embed = CreateEmbed::default();
+		embed.title("Anilist Schedule");
+		if chunk.is_empty() {
+			embed.description(format!("{}\n\nNothing airs this day.", description));
+		} else {
+			embed.description(format!("{}\n\n{}", description, chunk.join("\n")));
+		}
+		embed.color(ANI_LIST_COLOR);
+		embed.timestamp(now);
+		embed.set_author(ani_list_author.clone());
+		embed
+	}).collect()
+}
+
+#[tracking("al_next")]
+#[command("next")]
+#[aliases("countdown")]
+#[min_args(1)]
+#[usage = "<title>"]
+#[example = "Spy x Family"]
+#[description = "Shows when the next episode of an anime airs"]
+pub async fn anilist_next(ctx: &Context, msg: &Message, args: Args) -> CommandResult {
+	let ani_list_author = CreateEmbedAuthor::default()
+		.icon_url(ANI_LIST_AUTHOR_ICON_URL)
+		.name(ANI_LIST_AUTHOR_NAME)
+		.url(ANI_LIST_AUTHOR_URL)
+		.to_owned();
+
+	let results = match search_specific(args.rest(), Some(MediaType::Anime)).await {
+		Ok(v) => v,
+		Err(why) => return send_anilist_error(ctx, msg, why).await,
+	};
+	let policy = nsfw_policy(ctx, msg).await;
+	let media_list = results.page.and_then(|p| p.media).unwrap_or_default()
+		.into_iter()
+		.flatten()
+		.filter(|m| !policy.hides() || !m.is_adult.unwrap_or(false))
+		.collect::<Vec<_>>();
+
+	if media_list.is_empty() {
+		return send_anilist_error(ctx, msg, AniListError::NoData).await;
+	}
+
+	let options = media_list.iter().map(|m| {
+		let title = m.title.as_ref().and_then(|t| t.user_preferred.clone()).unwrap_or_default();
+		match m.status {
+			Some(status) => format!("{} ({})", title, status),
+			None => title,
+		}
+	}).collect::<Vec<String>>();
+
+	let index = Options::new(ctx, msg)
+		.title("Enter the number corresponding the anime you want the next episode of!")
+		.options(options)
+		.colour(ANI_LIST_COLOR)
+		.author(ani_list_author.clone())
+		.edit()
+		.send()
+		.await;
+	if index.is_none() {
+		return Ok(())
+	}
+	let (index, message_id, channel_id) = index.unwrap();
+	let media = &media_list[index];
+	let title = media.title.as_ref().and_then(|t| t.user_preferred.clone()).unwrap_or_default();
+
+	let mut message = ctx.http.get_message(channel_id.0, message_id.0).await?;
+	message.edit(&ctx.http, |m| {
+		m.embed(|e| {
+			e.title(&title);
+			e.url(format!("https://anilist.co/anime/{}", media.id));
+			match media.next_airing_episode {
+				Some(next) => {
+					// Round down to whole minutes, the seconds just add noise
+					let left = std::time::Duration::from_secs((next.time_until_airing.max(0) / 60 * 60) as u64);
+					match media.episodes {
+						Some(episodes) => e.field("Episode", format!("{}/{}", next.episode, episodes), true),
+						None => e.field("Episode", next.episode, true),
+					};
+					e.field("Airs", format!("<t:{0}:F> (<t:{0}:R>)", next.airing_at), true);
+					e.field("Time left", humantime::format_duration(left), true);
+				},
+				None => {
+					let status = media.status.map(|s| s.to_string()).unwrap_or_else(|| "Unknown".to_string());
+					e.description(format!("There is no upcoming episode scheduled.\nStatus: {}", status));
+				},
+			}
+			if let Some(cover) = media.cover_image.as_ref().and_then(|c| c.large.as_ref()).filter(|_| !policy.censors(media.is_adult.unwrap_or(false))) {
+				e.thumbnail(cover);
+			}
+			e.set_author(ani_list_author);
+			e.color(ANI_LIST_COLOR);
+			e
+		});
+		m
+	}).await?;
+
+	Ok(())
+}
+
+#[tracking("al_character")]
+#[command("character")]
+#[aliases("char")]
+#[min_args(1)]
+#[usage = "<name>"]
+#[example = "Holo"]
+#[description = "Search for a character on Anilist"]
+pub async fn anilist_character(ctx: &Context, msg: &Message, args: Args) -> CommandResult {
+	let name = args.rest();
+	let ani_list_author = CreateEmbedAuthor::default()
+		.icon_url(ANI_LIST_AUTHOR_ICON_URL)
+		.name(ANI_LIST_AUTHOR_NAME)
+		.url(ANI_LIST_AUTHOR_URL)
+		.to_owned();
+
+	let results = match search_character(name).await {
+		Ok(v) => v,
+		Err(why) => return send_anilist_error(ctx, msg, why).await,
+	};
+	let characters = results.page.and_then(|p| p.characters).unwrap_or_default().into_iter().flatten().collect::<Vec<_>>();
+
+	if characters.is_empty() {
+		return send_anilist_error(ctx, msg, AniListError::NoData).await;
+	}
+
+	let mut options: Vec<String> = Vec::new();
+	for character in &characters {
+		let mut option_string = vec![character_name(character)];
+		// Name the most popular media the character is from, to tell apart characters with the same name
+		let media_title = character.media.as_ref()
+			.and_then(|m| m.edges.as_ref())
+			.and_then(|e| e.iter().flatten().next())
+			.and_then(|e| e.node.as_ref())
+			.and_then(|n| n.title.as_ref())
+			.and_then(|t| t.user_preferred.clone());
+		if let Some(media_title) = media_title {
+			option_string.push(format!(" ({})", media_title));
+		}
+		options.push(option_string.join(""));
+	}
+
+	let index = Options::new(ctx, msg)
+		.title("Enter the number corresponding the character you want info about!")
+		.options(options)
+		.colour(ANI_LIST_COLOR)
+		.author(ani_list_author.clone())
+		.edit()
+		.send()
+		.await;
+	if index.is_none() {
+		return Ok(())
+	}
+	let character = &characters[index.unwrap().0];
+	let mess = &mut ctx.http.get_message(index.unwrap().2.0, index.unwrap().1.0).await?;
+
+	let edges = character.media.as_ref()
+		.and_then(|m| m.edges.clone())
+		.unwrap_or_default()
+		.into_iter()
+		.flatten()
+		.collect::<Vec<_>>();
+
+	let mut appearances = Vec::<String>::new();
+	let mut voice_actors = Vec::<String>::new();
+	for edge in &edges {
+		if let Some(media) = &edge.node {
+			let title = media.title.as_ref().and_then(|t| t.user_preferred.clone()).unwrap_or_default();
+			let media_type = media.type_.map(|t| t.to_string().to_lowercase()).unwrap_or_else(|| "anime".to_string());
+			let mut appearance = format!("[{}](https://anilist.co/{}/{})", title, media_type, media.id);
+			if let Some(role) = edge.character_role {
+				appearance.push_str(&format!(" ({})", role));
+			}
+			if appearances.len() < 5 {
+				appearances.push(appearance);
+			}
+		}
+		for staff in edge.voice_actors.iter().flatten().flatten() {
+			let staff_name = match staff.name.as_ref().and_then(|n| n.full.clone()) {
+				Some(v) => v,
+				None => continue,
+			};
+			let staff_link = format!("[{}](https://anilist.co/staff/{})", staff_name, staff.id);
+			if !voice_actors.contains(&staff_link) {
+				voice_actors.push(staff_link);
+			}
+		}
+	}
+	voice_actors.truncate(5);
+
+	mess.edit(&ctx.http, |m| {
+		m.embed(|e| {
+			e.title(character_name(character));
+			e.url(format!("https://anilist.co/character/{}", character.id));
+
+			if let Some(description) = &character.description {
+				e.description(spoiler_description(description, 2048));
+			}
+
+			if let Some(name) = &character.name {
+				if let Some(native) = &name.native {
+					e.field("Native", native, true);
+				}
+
+				let mut alternative = name.alternative.iter().flatten().flatten()
+					.filter(|n| !n.is_empty())
+					.cloned()
+					.collect::<Vec<String>>();
+				alternative.extend(name.alternative_spoiler.iter().flatten().flatten()
+					.filter(|n| !n.is_empty())
+					.map(|n| format!("||{}||", n)));
+				if !alternative.is_empty() {
+					e.field("Alternative Names", alternative.join(", "), true);
+				}
+			}
+
+			if let Some(gender) = &character.gender {
+				e.field("Gender", gender, true);
+			}
+
+			if let Some(age) = &character.age {
+				e.field("Age", age, true);
+			}
+
+			anilist_embed!(character.favourites, "Favourites", e);
+
+			if !appearances.is_empty() {
+				e.field("Appearances", appearances.join("\n"), false);
+			}
+
+			if !voice_actors.is_empty() {
+				e.field("Voice Actors", voice_actors.join(", "), false);
+			}
+
+			if let Some(image) = character.image.as_ref().and_then(|i| i.large.as_ref()) {
+				e.thumbnail(image);
+			}
+			e.set_author(ani_list_author);
+			e.color(ANI_LIST_COLOR);
+			e
+		});
+		m
+	}).await?;
+
+	Ok(())
+}
+
+#[tracking("al_staff")]
+#[command("staff")]
+#[min_args(1)]
+#[usage = "<name>"]
+#[example = "Hayao Miyazaki"]
+#[description = "Search for a person working on anime or manga on Anilist"]
+pub async fn anilist_staff(ctx: &Context, msg: &Message, args: Args) -> CommandResult {
+	let name = args.rest();
+	let ani_list_author = CreateEmbedAuthor::default()
+		.icon_url(ANI_LIST_AUTHOR_ICON_URL)
+		.name(ANI_LIST_AUTHOR_NAME)
+		.url(ANI_LIST_AUTHOR_URL)
+		.to_owned();
+
+	let results = match search_staff(name).await {
+		Ok(v) => v,
+		Err(why) => return send_anilist_error(ctx, msg, why).await,
+	};
+	let staff_list = results.page.and_then(|p| p.staff).unwrap_or_default().into_iter().flatten().collect::<Vec<_>>();
+
+	if staff_list.is_empty() {
+		return send_anilist_error(ctx, msg, AniListError::NoData).await;
+	}
+
+	let mut options: Vec<String> = Vec::new();
+	for staff in &staff_list {
+		let mut option_string = vec![staff_name(staff)];
+		let occupations = staff.primary_occupations.iter().flatten().flatten().cloned().collect::<Vec<String>>();
+		if !occupations.is_empty() {
+			option_string.push(format!(" ({})", occupations.join(", ")));
+		}
+		options.push(option_string.join(""));
+	}
 
-	// send embed containing the schedule for the day
-	let _ = msg.channel_id.send_message(ctx, |m| {
+	let index = Options::new(ctx, msg)
+		.title("Enter the number corresponding the person you want info about!")
+		.options(options)
+		.colour(ANI_LIST_COLOR)
+		.author(ani_list_author.clone())
+		.edit()
+		.send()
+		.await;
+	if index.is_none() {
+		return Ok(())
+	}
+	let staff = &staff_list[index.unwrap().0];
+	let mess = &mut ctx.http.get_message(index.unwrap().2.0, index.unwrap().1.0).await?;
+
+	let mut works = Vec::<String>::new();
+	for edge in staff.staff_media.iter().flat_map(|m| m.edges.iter().flatten().flatten()) {
+		let media = match &edge.node {
+			Some(v) => v,
+			None => continue,
+		};
+		let title = media.title.as_ref().and_then(|t| t.user_preferred.clone()).unwrap_or_default();
+		let media_type = media.type_.map(|t| t.to_string().to_lowercase()).unwrap_or_else(|| "anime".to_string());
+		let mut work = format!("[{}](https://anilist.co/{}/{})", title, media_type, media.id);
+		if let Some(role) = &edge.staff_role {
+			work.push_str(&format!(" ({})", role));
+		}
+		// The same media shows up once per role, keep only the first
+		if !works.iter().any(|w| w.starts_with(&format!("[{}]", title))) {
+			works.push(work);
+		}
+	}
+	works.truncate(5);
+
+	let characters = staff.characters.iter()
+		.flat_map(|c| c.nodes.iter().flatten().flatten())
+		.take(5)
+		.map(|c| {
+			let name = c.name.as_ref().and_then(|n| n.user_preferred.clone()).unwrap_or_default();
+			format!("[{}](https://anilist.co/character/{})", name, c.id)
+		})
+		.collect::<Vec<String>>();
+
+	mess.edit(&ctx.http, |m| {
 		m.embed(|e| {
-			e.title("Anilist Schedule");
-			// Description including todays date
-			e.description(format!("Today's schedule: {}", today.format("%Y-%m-%d")));
+			e.title(staff_name(staff));
+			e.url(format!("https://anilist.co/staff/{}", staff.id));
+
+			if let Some(description) = &staff.description {
+				e.description(spoiler_description(description, 2048));
+			}
+
+			if let Some(native) = staff.name.as_ref().and_then(|n| n.native.as_ref()) {
+				e.field("Native", native, true);
+			}
+
+			let occupations = staff.primary_occupations.iter().flatten().flatten().cloned().collect::<Vec<String>>();
+			if !occupations.is_empty() {
+				e.field("Occupations", occupations.join(", "), true);
+			}
+
+			if let Some(gender) = &staff.gender {
+				e.field("Gender", gender, true);
+			}
+
+			let years_active = staff.years_active.iter().flatten().flatten().map(|y| y.to_string()).collect::<Vec<String>>();
+			match years_active.len() {
+				0 => {},
+				1 => { e.field("Years Active", format!("{} - Present", years_active[0]), true); },
+				_ => { e.field("Years Active", years_active.join(" - "), true); },
+			}
+
+			if let Some(home_town) = &staff.home_town {
+				e.field("Home Town", home_town, true);
+			}
+
+			anilist_embed!(staff.favourites, "Favourites", e);
+
+			if !works.is_empty() {
+				e.field("Notable Works", works.join("\n"), false);
+			}
+
+			if !characters.is_empty() {
+				e.field("Character Roles", characters.join(", "), false);
+			}
+
+			if let Some(image) = staff.image.as_ref().and_then(|i| i.large.as_ref()) {
+				e.thumbnail(image);
+			}
+			e.set_author(ani_list_author);
 			e.color(ANI_LIST_COLOR);
-			e.timestamp(today);
+			e
+		});
+		m
+	}).await?;
+
+	Ok(())
+}
+
+#[tracking("al_studio")]
+#[command("studio")]
+#[min_args(1)]
+#[usage = "<name>"]
+#[example = "Kyoto Animation"]
+#[description = "Search for an animation studio on Anilist"]
+pub async fn anilist_studio(ctx: &Context, msg: &Message, args: Args) -> CommandResult {
+	let name = args.rest();
+	let ani_list_author = CreateEmbedAuthor::default()
+		.icon_url(ANI_LIST_AUTHOR_ICON_URL)
+		.name(ANI_LIST_AUTHOR_NAME)
+		.url(ANI_LIST_AUTHOR_URL)
+		.to_owned();
+
+	let results = match search_studio(name).await {
+		Ok(v) => v,
+		Err(why) => return send_anilist_error(ctx, msg, why).await,
+	};
+	let studios = results.page.and_then(|p| p.studios).unwrap_or_default().into_iter().flatten().collect::<Vec<_>>();
+
+	if studios.is_empty() {
+		return send_anilist_error(ctx, msg, AniListError::NoData).await;
+	}
+
+	let options = studios.iter().map(|s| s.name.clone()).collect::<Vec<String>>();
+
+	let index = Options::new(ctx, msg)
+		.title("Enter the number corresponding the studio you want info about!")
+		.options(options)
+		.colour(ANI_LIST_COLOR)
+		.author(ani_list_author.clone())
+		.edit()
+		.send()
+		.await;
+	if index.is_none() {
+		return Ok(())
+	}
+	let studio = &studios[index.unwrap().0];
+	let mess = &mut ctx.http.get_message(index.unwrap().2.0, index.unwrap().1.0).await?;
+
+	// Media is already sorted by popularity
+	let media = studio.media.iter()
+		.flat_map(|m| m.nodes.iter().flatten().flatten())
+		.map(|m| {
+			let title = m.title.as_ref().and_then(|t| t.user_preferred.clone()).unwrap_or_default();
+			match m.format {
+				Some(format) => format!("[{}](https://anilist.co/anime/{}) ({})", title, m.id, format),
+				None => format!("[{}](https://anilist.co/anime/{})", title, m.id),
+			}
+		})
+		.collect::<Vec<String>>();
+
+	mess.edit(&ctx.http, |m| {
+		m.embed(|e| {
+			e.title(&studio.name);
+			e.url(format!("https://anilist.co/studio/{}", studio.id));
+
+			anilist_embed!(studio.is_animation_studio, "Yes", "Animation Studio", e);
+
+			anilist_embed!(studio.favourites, "Favourites", e);
+
+			if !media.is_empty() {
+				e.field("Anime", media.join("\n"), false);
+			}
+
 			e.set_author(ani_list_author);
-			e.field("Schedule", schedule_strs.join("\n"), false);
+			e.color(ANI_LIST_COLOR);
 			e
+		});
+		m
+	}).await?;
+
+	Ok(())
+}
+
+#[tracking("al_link")]
+#[command("link")]
+#[min_args(1)]
+#[usage = "<anilist username>"]
+#[example = "Tracreed"]
+#[description = "Links your Anilist account so commands default to it"]
+pub async fn anilist_link(ctx: &Context, msg: &Message, args: Args) -> CommandResult {
+	use crate::schema::anilist_users::dsl::*;
+
+	let name = args.rest().trim();
+
+	// Make sure the account exists before linking it
+	let profile = match get_user(name).await {
+		Ok(v) => v,
+		Err(why) => return send_anilist_error(ctx, msg, why).await,
+	};
+	let account = match profile.user {
+		Some(v) => v,
+		None => return send_anilist_error(ctx, msg, AniListError::NoData).await,
+	};
+
+	let connection = establish_connection();
+	let new_user = NewAniListUser {
+		user_id: &(msg.author.id.0 as i64),
+		anilist_name: &account.name,
+	};
+	let linked = diesel::insert_into(anilist_users)
+		.values(&new_user)
+		.on_conflict(user_id)
+		.do_update()
+		.set(anilist_name.eq(&account.name))
+		.get_result::<AniListUser>(&connection)?;
+
+	// A login token for a different account would update the wrong lists
+	if linked.anilist_id.is_some() && linked.anilist_id != Some(account.id) {
+		diesel::update(anilist_users.find(linked.id))
+			.set((anilist_id.eq(None::<i32>), access_token.eq(None::<String>)))
+			.execute(&connection)?;
+	}
+
+	msg.reply(ctx, format!("Linked your Anilist account {}", account.name)).await?;
+	Ok(())
+}
+
+#[tracking("al_unlink")]
+#[command("unlink")]
+#[description = "Unlinks your Anilist account"]
+pub async fn anilist_unlink(ctx: &Context, msg: &Message) -> CommandResult {
+	use crate::schema::anilist_users::dsl::*;
+
+	let connection = establish_connection();
+	diesel::delete(anilist_users.filter(user_id.eq(msg.author.id.0 as i64))).execute(&connection)?;
+
+	msg.reply(ctx, "Unlinked your Anilist account").await?;
+	Ok(())
+}
+
+#[tracking("al_user")]
+#[command("user")]
+#[aliases("profile")]
+#[usage = "[anilist username|@user]"]
+#[example = "Tracreed"]
+#[description = "Shows an Anilist profile, defaults to your linked account"]
+pub async fn anilist_user(ctx: &Context, msg: &Message, args: Args) -> CommandResult {
+	let name = if let Some(mentioned) = msg.mentions.iter().find(|u| !u.bot) {
+		linked_anilist_name(mentioned.id)
+	} else if args.is_empty() {
+		linked_anilist_name(msg.author.id)
+	} else {
+		Some(args.rest().trim().to_string())
+	};
+
+	let name = match name {
+		Some(v) => v,
+		None => {
+			msg.reply(ctx, "No linked Anilist account, give a username or link one with `al link <username>`").await?;
+			return Ok(())
+		}
+	};
+
+	let profile = match get_user(name).await {
+		Ok(v) => v,
+		Err(why) => return send_anilist_error(ctx, msg, why).await,
+	};
+	let user = match profile.user {
+		Some(v) => v,
+		None => return send_anilist_error(ctx, msg, AniListError::NoData).await,
+	};
+
+	let ani_list_author = CreateEmbedAuthor::default()
+		.icon_url(ANI_LIST_AUTHOR_ICON_URL)
+		.name(ANI_LIST_AUTHOR_NAME)
+		.url(ANI_LIST_AUTHOR_URL)
+		.to_owned();
+
+	let anime = user.statistics.as_ref().and_then(|s| s.anime.clone());
+	let manga = user.statistics.as_ref().and_then(|s| s.manga.clone());
+
+	// Combine the genres from both lists, ordered by how many entries they have
+	let mut genres = Vec::<(String, i32)>::new();
+	for stats in anime.iter().chain(manga.iter()) {
+		for genre in stats.genres.iter().flatten().flatten() {
+			let name = match &genre.genre {
+				Some(v) => v,
+				None => continue,
+			};
+			match genres.iter_mut().find(|(g, _)| g == name) {
+				Some((_, count)) => *count += genre.count,
+				None => genres.push((name.clone(), genre.count)),
+			}
+		}
+	}
+	genres.sort_by(|a, b| b.1.cmp(&a.1));
+	genres.truncate(5);
+
+	let favourite_media = |connection: Option<&ebina_anilist::queries::queries::MediaConnection3>, media_type: &str| {
+		connection.iter()
+			.flat_map(|c| c.nodes.iter().flatten().flatten())
+			.map(|m| {
+				let title = m.title.as_ref().and_then(|t| t.user_preferred.clone()).unwrap_or_default();
+				format!("[{}](https://anilist.co/{}/{})", title, media_type, m.id)
+			})
+			.collect::<Vec<String>>()
+	};
+	let favourites = user.favourites.as_ref();
+	let favourite_anime = favourite_media(favourites.and_then(|f| f.anime.as_ref()), "anime");
+	let favourite_manga = favourite_media(favourites.and_then(|f| f.manga.as_ref()), "manga");
+	let favourite_characters = favourites
+		.and_then(|f| f.characters.as_ref())
+		.iter()
+		.flat_map(|c| c.nodes.iter().flatten().flatten())
+		.map(|c| {
+			let name = c.name.as_ref().and_then(|n| n.user_preferred.clone()).unwrap_or_default();
+			format!("[{}](https://anilist.co/character/{})", name, c.id)
 		})
+		.collect::<Vec<String>>();
+
+	msg.channel_id.send_message(&ctx.http, |m| {
+		m.embed(|e| {
+			e.title(&user.name);
+			e.url(format!("https://anilist.co/user/{}", user.name));
+
+			if let Some(avatar) = user.avatar.as_ref().and_then(|a| a.large.as_ref()) {
+				e.thumbnail(avatar);
+			}
+
+			if let Some(banner) = &user.banner_image {
+				e.image(banner);
+			}
+
+			if let Some(anime) = &anime {
+				let watched = humantime::format_duration(std::time::Duration::from_secs(anime.minutes_watched as u64 * 60));
+				e.field(
+					"Anime",
+					format!(
+						"Count: {}\nEpisodes: {}\nTime watched: {}\nMean score: {:.1}",
+						anime.count, anime.episodes_watched, watched, anime.mean_score
+					),
+					true,
+				);
+			}
+
+			if let Some(manga) = &manga {
+				e.field(
+					"Manga",
+					format!(
+						"Count: {}\nChapters: {}\nVolumes: {}\nMean score: {:.1}",
+						manga.count, manga.chapters_read, manga.volumes_read, manga.mean_score
+					),
+					true,
+				);
+			}
+
+			if !genres.is_empty() {
+				e.field("Top Genres", genres.iter().map(|(g, c)| format!("{} ({})", g, c)).collect::<Vec<String>>().join("\n"), true);
+			}
+
+			if !favourite_anime.is_empty() {
+				e.field("Favourite Anime", favourite_anime.join("\n"), false);
+			}
+
+			if !favourite_manga.is_empty() {
+				e.field("Favourite Manga", favourite_manga.join("\n"), false);
+			}
+
+			if !favourite_characters.is_empty() {
+				e.field("Favourite Characters", favourite_characters.join(", "), false);
+			}
+
+			e.set_author(ani_list_author);
+			e.color(ANI_LIST_COLOR);
+			e
+		});
+		m
 	}).await?;
 
 	Ok(())
 }
 
+/// The Anilist account a discord user has linked.
+pub fn linked_anilist_name(discord_user: UserId) -> Option<String> {
+	use crate::schema::anilist_users::dsl::*;
+
+	let connection = establish_connection();
+	anilist_users
+		.filter(user_id.eq(discord_user.0 as i64))
+		.first::<AniListUser>(&connection)
+		.optional()
+		.ok()
+		.flatten()
+		.map(|u| u.anilist_name)
+}
+
+#[tracking("al_season")]
+#[command("season")]
+#[aliases("seasonal", "chart")]
+#[usage = "[winter|spring|summer|fall] [year] [tv|tv_short|movie|ona|ova|special] [popularity|score|start]"]
+#[example = "spring 2022 tv score"]
+#[description = "Shows the anime of a season, defaults to the current season sorted by popularity"]
+pub async fn anilist_season(ctx: &Context, msg: &Message, mut args: Args) -> CommandResult {
+	let (mut season, mut year) = current_season(Utc::now());
+	let mut formats = Vec::<MediaFormat>::new();
+	let mut sort = MediaSort::PopularityDesc;
+
+	for arg in args.iter::<String>().flatten() {
+		match arg.to_lowercase().as_str() {
+			"winter" => season = MediaSeason::Winter,
+			"spring" => season = MediaSeason::Spring,
+			"summer" => season = MediaSeason::Summer,
+			"fall" | "autumn" => season = MediaSeason::Fall,
+			"popularity" | "popular" => sort = MediaSort::PopularityDesc,
+			"score" | "rating" => sort = MediaSort::ScoreDesc,
+			"start" | "date" | "start_date" => sort = MediaSort::StartDate,
+			other => match (other.parse::<i32>(), parse_media_format(other)) {
+				(Ok(v), _) if (1940..=2100).contains(&v) => year = v,
+				(_, Some(format)) => formats.push(format),
+				_ => {
+					msg.reply(ctx, format!("I don't know what `{}` means, see `help al season`", other)).await?;
+					return Ok(())
+				}
+			},
+		}
+	}
+
+	let mut media = match get_full_season(season, year, formats.clone(), sort).await {
+		Ok(v) => v,
+		Err(why) => return send_anilist_error(ctx, msg, why).await,
+	};
+	let policy = nsfw_policy(ctx, msg).await;
+	if policy.hides() {
+		media.retain(|m| !m.is_adult.unwrap_or(false));
+	}
+
+	if media.is_empty() {
+		return send_anilist_error(ctx, msg, AniListError::NoData).await;
+	}
+
+	let sort_name = match sort {
+		MediaSort::ScoreDesc => "score",
+		MediaSort::StartDate => "start date",
+		_ => "popularity",
+	};
+	let mut description = format!("{} anime sorted by {}", media.len(), sort_name);
+	if !formats.is_empty() {
+		description.push_str(&format!(", only {}", formats.iter().map(|f| f.to_string()).collect::<Vec<String>>().join(", ")));
+	}
+
+	let pages = media_list_pages(format!("{} {}", season, year), description, &media, policy);
+	Paginator::new(ctx, msg)
+		.pages(pages)
+		.send()
+		.await?;
+
+	Ok(())
+}
+
+#[tracking("al_trending")]
+#[command("trending")]
+#[usage = "[anime|manga]"]
+#[example = "manga"]
+#[description = "Shows what's trending on AniList right now"]
+pub async fn anilist_trending(ctx: &Context, msg: &Message, args: Args) -> CommandResult {
+	let media_type = match args.rest().trim().to_lowercase().as_str() {
+		"anime" => Some(MediaType::Anime),
+		"manga" => Some(MediaType::Manga),
+		_ => None,
+	};
+
+	let policy = nsfw_policy(ctx, msg).await;
+	let results = match discover(media_type, MediaSort::TrendingDesc, Vec::new(), Vec::new(), adult_filter(policy), 1, 50).await {
+		Ok(v) => v,
+		Err(why) => return send_anilist_error(ctx, msg, why).await,
+	};
+	let media = results.page.and_then(|p| p.media).unwrap_or_default().into_iter().flatten().collect::<Vec<Media4>>();
+
+	if media.is_empty() {
+		return send_anilist_error(ctx, msg, AniListError::NoData).await;
+	}
+
+	let title = match media_type {
+		Some(t) => format!("Trending {}", t),
+		None => "Trending".to_string(),
+	};
+	let pages = media_list_pages(title, "What people are watching and reading right now".to_string(), &media, policy);
+	Paginator::new(ctx, msg)
+		.pages(pages)
+		.send()
+		.await?;
+
+	Ok(())
+}
+
+#[tracking("al_top")]
+#[command("top")]
+#[usage = "[anime|manga] [genre or tag, ...]"]
+#[example = "manga romance, time skip"]
+#[description = "Shows the highest rated anime or manga, optionally only of some genres or tags"]
+pub async fn anilist_top(ctx: &Context, msg: &Message, args: Args) -> CommandResult {
+	let collections = match get_collections().await {
+		Ok(v) => v,
+		Err(why) => return send_anilist_error(ctx, msg, why).await,
+	};
+	let policy = nsfw_policy(ctx, msg).await;
+	let (media_type, genres, tags) = match parse_discover_filters(args.rest(), &collections, policy) {
+		Ok(v) => v,
+		Err(unknown) => {
+			msg.reply(ctx, format!("`{}` isn't a genre or tag on AniList", unknown)).await?;
+			return Ok(())
+		}
+	};
+	let media_type = media_type.unwrap_or(MediaType::Anime);
+
+	let results = match discover(Some(media_type), MediaSort::ScoreDesc, genres.clone(), tags.clone(), adult_filter(policy), 1, 50).await {
+		Ok(v) => v,
+		Err(why) => return send_anilist_error(ctx, msg, why).await,
+	};
+	let media = results.page.and_then(|p| p.media).unwrap_or_default().into_iter().flatten().collect::<Vec<Media4>>();
+
+	if media.is_empty() {
+		return send_anilist_error(ctx, msg, AniListError::NoData).await;
+	}
+
+	let filters = genres.into_iter().chain(tags).collect::<Vec<String>>();
+	let description = if filters.is_empty() {
+		format!("The highest rated {} of all time", media_type.to_string().to_lowercase())
+	} else {
+		format!("The highest rated {} with {}", media_type.to_string().to_lowercase(), filters.join(", "))
+	};
+	let pages = media_list_pages(format!("Top {}", media_type), description, &media, policy);
+	Paginator::new(ctx, msg)
+		.pages(pages)
+		.send()
+		.await?;
+
+	Ok(())
+}
+
+#[tracking("al_random")]
+#[command("random")]
+#[usage = "[anime|manga] [genre or tag, ...]"]
+#[example = "anime comedy, isekai"]
+#[description = "Picks a random anime or manga, optionally only of some genres or tags"]
+pub async fn anilist_random(ctx: &Context, msg: &Message, args: Args) -> CommandResult {
+	let collections = match get_collections().await {
+		Ok(v) => v,
+		Err(why) => return send_anilist_error(ctx, msg, why).await,
+	};
+	let policy = nsfw_policy(ctx, msg).await;
+	let (media_type, genres, tags) = match parse_discover_filters(args.rest(), &collections, policy) {
+		Ok(v) => v,
+		Err(unknown) => {
+			msg.reply(ctx, format!("`{}` isn't a genre or tag on AniList", unknown)).await?;
+			return Ok(())
+		}
+	};
+
+	let random = match get_random_media(media_type, genres, tags, adult_filter(policy)).await {
+		Ok(Some(v)) => v,
+		Ok(None) => return send_anilist_error(ctx, msg, AniListError::NoData).await,
+		Err(why) => return send_anilist_error(ctx, msg, why).await,
+	};
+	// The random pick only has the list fields, get the rest for the full embed
+	let media = match get_media(random.id).await {
+		Ok(v) => v.media,
+		Err(why) => return send_anilist_error(ctx, msg, why).await,
+	};
+	let media = match media {
+		Some(v) => v,
+		None => return send_anilist_error(ctx, msg, AniListError::NoData).await,
+	};
+
+	Paginator::new(ctx, msg)
+		.page(media_embed(&media, policy))
+		.send()
+		.await?;
+
+	Ok(())
+}
+
+/// The `is_adult` filter for AniList queries, adult media is only left out when the policy hides it.
+fn adult_filter(policy: NsfwPolicy) -> Option<bool> {
+	if policy.hides() {
+		Some(false)
+	} else {
+		None
+	}
+}
+
+/// Reads an optional `anime`/`manga` followed by comma separated genres and tags,
+/// matched against AniList's own names. `Err` holds the first term that is neither.
+fn parse_discover_filters(args: &str, collections: &Collections, policy: NsfwPolicy) -> Result<(Option<MediaType>, Vec<String>, Vec<String>), String> {
+	let args = args.trim();
+	let (media_type, rest) = match args.split_once(char::is_whitespace).unwrap_or((args, "")) {
+		(first, rest) if first.eq_ignore_ascii_case("anime") => (Some(MediaType::Anime), rest),
+		(first, rest) if first.eq_ignore_ascii_case("manga") => (Some(MediaType::Manga), rest),
+		_ => (None, args),
+	};
+
+	let known_genres = collections.genre_collection.as_ref()
+		.map(|g| g.iter().flatten().collect::<Vec<&String>>())
+		.unwrap_or_default();
+	// Adult tags are left out along with adult media
+	let known_tags = collections.media_tag_collection.as_ref()
+		.map(|t| t.iter().flatten().filter(|t| !policy.hides() || !t.is_adult.unwrap_or(false)).map(|t| &t.name).collect::<Vec<&String>>())
+		.unwrap_or_default();
+
+	let mut genres = Vec::new();
+	let mut tags = Vec::new();
+	for term in rest.split(',').map(str::trim).filter(|t| !t.is_empty()) {
+		if let Some(genre) = known_genres.iter().find(|g| g.eq_ignore_ascii_case(term)) {
+			genres.push(genre.to_string());
+		} else if let Some(tag) = known_tags.iter().find(|t| t.eq_ignore_ascii_case(term)) {
+			tags.push(tag.to_string());
+		} else {
+			return Err(term.to_string());
+		}
+	}
+	Ok((media_type, genres, tags))
+}
+
+/// Parses the anime formats users can filter by.
+pub fn parse_media_format(format: &str) -> Option<MediaFormat> {
+	match format.to_lowercase().as_str() {
+		"tv" => Some(MediaFormat::Tv),
+		"tv_short" | "short" => Some(MediaFormat::TvShort),
+		"movie" => Some(MediaFormat::Movie),
+		"ona" => Some(MediaFormat::Ona),
+		"ova" => Some(MediaFormat::Ova),
+		"special" => Some(MediaFormat::Special),
+		_ => None,
+	}
+}
+
+/// Splits a list of media into embeds of ten entries each for the paginator.
+pub fn media_list_pages(title: String, description: String, media: &[Media4], policy: NsfwPolicy) -> Vec<CreateEmbed> {
+	let ani_list_author = CreateEmbedAuthor::default()
+		.icon_url(ANI_LIST_AUTHOR_ICON_URL)
+		.name(ANI_LIST_AUTHOR_NAME)
+		.url(ANI_LIST_AUTHOR_URL)
+		.to_owned();
+
+	let mut pages = Vec::<CreateEmbed>::new();
+	for (page, chunk) in media.chunks(10).enumerate() {
+		let mut lines = Vec::<String>::new();
+		for (i, m) in chunk.iter().enumerate() {
+			let title = m.title.as_ref().and_then(|t| t.user_preferred.clone()).unwrap_or_default();
+			let media_type = m.type_.map(|t| t.to_string().to_lowercase()).unwrap_or_else(|| "anime".to_string());
+			let mut line = format!("**{}.** [{}](https://anilist.co/{}/{})", page * 10 + i + 1, title, media_type, m.id);
+			let mut details = Vec::<String>::new();
+			if let Some(format) = m.format {
+				details.push(format.to_string());
+			}
+			if let Some(score) = m.average_score {
+				details.push(format!("{}%", score));
+			}
+			let rated = m.rankings.as_ref()
+				.and_then(|r| r.iter().flatten().find(|r| r.type_ == MediaRankType::Rated && r.all_time.unwrap_or(false)));
+			if let Some(rank) = rated {
+				details.push(format!("#{} {}", rank.rank, rank.context));
+			}
+			if let Some(episodes) = m.episodes {
+				details.push(format!("{} eps", episodes));
+			}
+			if let Some(chapters) = m.chapters {
+				details.push(format!("{} chapters", chapters));
+			}
+			if let Some(start_date) = m.start_date {
+				if let (Some(year), Some(month), Some(day)) = (start_date.year, start_date.month, start_date.day) {
+					details.push(format!("{}-{:02}-{:02}", year, month, day));
+				}
+			}
+			if !details.is_empty() {
+				line.push_str(&format!("\n{}", details.join(" · ")));
+			}
+			lines.push(line);
+		}
+
+		let mut embed = CreateEmbed::default();
+		embed.title(&title);
+		embed.description(format!("{}\n\n{}", description, lines.join("\n")));
+		let first = chunk.first().filter(|m| !policy.censors(m.is_adult.unwrap_or(false)));
+		if let Some(cover) = first.and_then(|m| m.cover_image.as_ref()).and_then(|c| c.large.as_ref()) {
+			embed.thumbnail(cover);
+		}
+		embed.set_author(ani_list_author.clone());
+		embed.color(ANI_LIST_COLOR);
+		pages.push(embed);
+	}
+	pages
+}
+
+/// Names of the staff of a media with any of the given roles.
+fn staff_with_roles(media: &ebina_anilist::queries::queries::Media, roles: &[&str]) -> Vec<String> {
+	let mut names = Vec::<String>::new();
+	for edge in media.staff.iter().flat_map(|s| s.edges.iter().flatten().flatten()) {
+		let role = match &edge.role {
+			Some(v) => v,
+			None => continue,
+		};
+		if !roles.contains(&role.as_str()) {
+			continue;
+		}
+		let staff = match &edge.node {
+			Some(v) => v,
+			None => continue,
+		};
+		if let Some(name) = staff.name.as_ref().and_then(|n| n.full.clone()) {
+			let link = format!("[{}](https://anilist.co/staff/{})", name, staff.id);
+			if !names.contains(&link) {
+				names.push(link);
+			}
+		}
+	}
+	names
+}
+
+/// The name used for a staff member in titles and option lists.
+fn staff_name(staff: &ebina_anilist::queries::queries::Staff2) -> String {
+	staff.name.as_ref()
+		.and_then(|n| n.user_preferred.clone().or_else(|| n.full.clone()))
+		.unwrap_or_else(|| format!("Staff {}", staff.id))
+}
+
+/// The name used for a character in titles and option lists.
+fn character_name(character: &ebina_anilist::queries::queries::Character) -> String {
+	character.name.as_ref()
+		.and_then(|n| n.user_preferred.clone().or_else(|| n.full.clone()))
+		.unwrap_or_else(|| format!("Character {}", character.id))
+}
+
+/// Converts an AniList markdown description to Discord markdown, turning AniList's
+/// `~!spoiler!~` markup into Discord spoilers and cutting it to `max` characters
+/// without leaving a spoiler open.
+pub fn spoiler_description(description: &str, max: usize) -> String {
+	let desc = SPOILER.replace_all(description, "||$1||").to_string();
+	if desc.chars().count() <= max {
+		return desc;
+	}
+	let mut desc = desc.chars().take(max).collect::<String>();
+	if desc.matches("||").count() % 2 == 1 {
+		desc.push_str("||");
+	}
+	desc.push_str("...");
+	desc
+}
+
 pub async fn anilist_media(ctx: &Context, msg: &Message, args: Args, media_type: Option<MediaType>) -> CommandResult {
 	let title = args.rest();
 	let media_list;
@@ -120,13 +1203,25 @@ pub async fn anilist_media(ctx: &Context, msg: &Message, args: Args, media_type:
 		.to_owned();
 
 	if media_type.is_none() {
-		let results = search(title).await.unwrap();
-		media_list = results.page.unwrap().media.unwrap();
+		let results = match search(title).await {
+			Ok(v) => v,
+			Err(why) => return send_anilist_error(ctx, msg, why).await,
+		};
+		media_list = results.page.and_then(|p| p.media).unwrap_or_default();
 	} else {
-		let results = search_specific(title, media_type).await.unwrap();
-		media_list = results.page.unwrap().media.unwrap();
+		let results = match search_specific(title, media_type).await {
+			Ok(v) => v,
+			Err(why) => return send_anilist_error(ctx, msg, why).await,
+		};
+		media_list = results.page.and_then(|p| p.media).unwrap_or_default();
 	}
 
+	let policy = nsfw_policy(ctx, msg).await;
+	let media_list = media_list.into_iter()
+		.flatten()
+		.filter(|m| !policy.hides() || !m.is_adult.unwrap_or(false))
+		.collect::<Vec<Media>>();
+
 	if media_list.is_empty() {
 		msg.channel_id.send_message(&ctx.http, |m| {
 			m.embed(|e| {
@@ -141,10 +1236,11 @@ pub async fn anilist_media(ctx: &Context, msg: &Message, args: Args, media_type:
 	}
 
 	let mut options: Vec<String> = Vec::new();
-	for m in &media_list {
-		let manga = m.as_ref().unwrap();
-		let mut option_string = vec![manga.title.as_ref().unwrap().user_preferred.as_ref().unwrap().to_string()];
-		option_string.push(format!(" ({})", manga.format.unwrap()));
+	for manga in &media_list {
+		let mut option_string = vec![manga.title.as_ref().and_then(|t| t.user_preferred.clone()).unwrap_or_default()];
+		if let Some(format) = manga.format {
+			option_string.push(format!(" ({})", format));
+		}
 
 		if manga.is_adult.is_some() && manga.is_adult.unwrap() {
 			option_string.push(" (NSFW)".to_string());
@@ -174,74 +1270,294 @@ pub async fn anilist_media(ctx: &Context, msg: &Message, args: Args, media_type:
 	if index.is_none() {
 		return Ok(())
 	}
-	let media = media_list[index.unwrap().0].as_ref().unwrap();
-	let mess = &mut ctx.http.get_message(index.unwrap().2.0, index.unwrap().1.0).await?;
-
+	let media = &media_list[index.unwrap().0];
+	let mut mess = ctx.http.get_message(index.unwrap().2.0, index.unwrap().1.0).await?;
 
 	mess.edit(&ctx.http, |m| {
-		m.embed(|e| {
-			e.title(media.title.as_ref().unwrap().user_preferred.as_ref().unwrap());
-			e.url(format!("https://anilist.co/{}/{}", media.type_.unwrap().to_string().to_lowercase(), media.id));
+		m.set_embed(media_embed(media, policy));
+		m.components(|c| {
+			if let Some(row) = related_menu(media, policy) {
+				c.add_action_row(row);
+			}
+			c
+		})
+	}).await?;
+
+	// Let the author jump between related entries until they stop picking
+	let mut interactions = mess
+		.await_component_interactions(ctx)
+		.author_id(msg.author.id)
+		.timeout(Duration::from_secs(120))
+		.build();
 
-			if media.description.is_some() {
-				e.description(parse_html(media.description.as_ref().unwrap()));
+	while let Some(interaction) = interactions.next().await {
+		if interaction.data.custom_id != RELATED_MENU_ID {
+			continue;
+		}
+		let related_id = match interaction.data.values.first().and_then(|v| v.parse::<i32>().ok()) {
+			Some(v) => v,
+			None => continue,
+		};
+
+		let related = match get_media(related_id).await {
+			Ok(v) => v.media,
+			Err(why) => {
+				error!("Error getting related media {}: {}", related_id, why);
+				None
 			}
+		};
+		let related = match related {
+			Some(v) => v,
+			None => {
+				interaction.create_interaction_response(&ctx.http, |r| {
+					r.kind(InteractionResponseType::ChannelMessageWithSource)
+						.interaction_response_data(|d| d.content("Couldn't get that entry from AniList, try again later.").ephemeral(true))
+				}).await?;
+				continue;
+			}
+		};
 
-			anilist_embed!(media.format, "Format", e);
+		interaction.create_interaction_response(&ctx.http, |r| {
+			r.kind(InteractionResponseType::UpdateMessage)
+				.interaction_response_data(|d| {
+					d.set_embed(media_embed(&related, policy));
+					d.components(|c| {
+						if let Some(row) = related_menu(&related, policy) {
+							c.add_action_row(row);
+						}
+						c
+					})
+				})
+		}).await?;
+	}
 
-			anilist_embed!(media.type_, "Type", e);
+	// Remove the menu once nobody can use it anymore
+	mess.edit(&ctx.http, |m| m.components(|c| c)).await?;
 
-			anilist_embed!(media.chapters, "Chapters", e);
+	Ok(())
+}
 
-			anilist_embed!(media.volumes, "Volumes", e);
+/// Builds the embed shown for an anime or manga, leaving out what the NSFW policy doesn't allow.
+pub fn media_embed(media: &Media, policy: NsfwPolicy) -> CreateEmbed {
+	let ani_list_author = CreateEmbedAuthor::default()
+		.icon_url(ANI_LIST_AUTHOR_ICON_URL)
+		.name(ANI_LIST_AUTHOR_NAME)
+		.url(ANI_LIST_AUTHOR_URL)
+		.to_owned();
 
-			anilist_embed!(media.episodes, "Episodes", e);
+	let mut e = CreateEmbed::default();
+	e.title(media.title.as_ref().and_then(|t| t.user_preferred.as_deref()).unwrap_or("Untitled"));
+	let kind = media.type_.map(|t| t.to_string().to_lowercase()).unwrap_or_else(|| "anime".to_string());
+	e.url(format!("https://anilist.co/{}/{}", kind, media.id));
 
-			anilist_embed!(media.status, "Status", e);
+	let censored = policy.censors(media.is_adult.unwrap_or(false));
+	if censored {
+		e.description("The cover and description of adult entries are hidden outside of NSFW channels.");
+	} else if let Some(description) = &media.description {
+		// Leave room for the other fields in the 6000 character limit
+		e.description(spoiler_description(description, 2000));
+	}
 
-			anilist_embed!(media.mean_score.is_some(), format!("{}%", media.mean_score.unwrap()) , "Mean Score", e);
+	anilist_embed!(media.format, "Format", e);
 
-			anilist_embed!(media.is_adult.unwrap(), "Yes", "NSFW", e);
+	anilist_embed!(media.type_, "Type", e);
 
-			if !media.genres.as_ref().unwrap().is_empty() {
-				let genres = media.genres.as_ref().unwrap().iter().map(|g| g.as_ref().unwrap().to_string()).collect::<Vec<String>>();
-				e.field("Genres", genres.join(", "), true);
-			}
+	anilist_embed!(media.chapters, "Chapters", e);
 
-			if let Some(start_date) = media.start_date {
-				if start_date.year.is_some() && start_date.month.is_some() && start_date.day.is_some(){
-					e.field(
-						"Start Date",
-						chrono::naive::NaiveDate::from_ymd(
-							start_date.year.unwrap(),
-							start_date.month.unwrap().try_into().unwrap(),
-							start_date.day.unwrap().try_into().unwrap(),
-						),
-							true
-					);
-				}
-			}
+	anilist_embed!(media.volumes, "Volumes", e);
 
-			if let Some(end_date) = media.end_date {
-				if end_date.year.is_some() && end_date.month.is_some() && end_date.day.is_some(){
-					e.field(
-						"End Date",
-						chrono::naive::NaiveDate::from_ymd(
-							end_date.year.unwrap(),
-							end_date.month.unwrap().try_into().unwrap(),
-							end_date.day.unwrap().try_into().unwrap(),
-						),
-						true
-					);
-				}
+	anilist_embed!(media.episodes, "Episodes", e);
+
+	anilist_embed!(media.status, "Status", e);
+
+	anilist_embed!(media.mean_score.is_some(), format!("{}%", media.mean_score.unwrap()) , "Mean Score", e);
+
+	anilist_embed!(media.is_adult.unwrap_or(false), "Yes", "NSFW", e);
+
+	let genres = media.genres.iter().flatten().flatten().cloned().collect::<Vec<String>>();
+	if !genres.is_empty() {
+		e.field("Genres", genres.join(", "), true);
+	}
+
+	let fuzzy_date = |year: Option<i32>, month: Option<i32>, day: Option<i32>| {
+		chrono::naive::NaiveDate::from_ymd_opt(year?, month?.try_into().ok()?, day?.try_into().ok()?)
+	};
+	if let Some(start_date) = media.start_date.and_then(|d| fuzzy_date(d.year, d.month, d.day)) {
+		e.field("Start Date", start_date, true);
+	}
+	if let Some(end_date) = media.end_date.and_then(|d| fuzzy_date(d.year, d.month, d.day)) {
+		e.field("End Date", end_date, true);
+	}
+	if let (Some(MediaStatus::Releasing), Some(next)) = (media.status, media.next_airing_episode) {
+		e.field("Next episode", format!("Episode {} <t:{}:R>", next.episode, next.airing_at), true);
+	}
+
+	let directors = staff_with_roles(media, &["Director", "Chief Director"]);
+	if !directors.is_empty() {
+		e.field("Director", directors.join(", "), true);
+	}
+
+	let creators = staff_with_roles(media, &["Original Creator", "Original Story", "Story & Art", "Story"]);
+	if !creators.is_empty() {
+		e.field("Original Creator", creators.join(", "), true);
+	}
+
+	let studios = media.studios.as_ref()
+		.and_then(|s| s.nodes.as_ref())
+		.map(|nodes| nodes.iter().flatten().map(|studio| match &studio.site_url {
+			Some(url) => format!("[{}]({})", studio.name, url),
+			None => studio.name.clone(),
+		}).collect::<Vec<String>>())
+		.unwrap_or_default();
+	if !studios.is_empty() {
+		e.field("Studios", studios.join(", "), true);
+	}
+
+	let relations = media.relations.as_ref()
+		.and_then(|r| r.edges.as_ref())
+		.map(|edges| edges.iter().flatten().filter_map(|edge| {
+			let node = edge.node.as_ref().filter(|n| !policy.hides() || !n.is_adult.unwrap_or(false))?;
+			let relation = edge.relation_type.map(|r| r.to_string()).unwrap_or_else(|| "Related".to_string());
+			Some(format!("{}: {}", relation, media_link(node)))
+		}).collect::<Vec<String>>())
+		.unwrap_or_default();
+	if !relations.is_empty() {
+		e.field("Relations", field_lines(&relations), false);
+	}
+
+	let recommendations = media.recommendations.as_ref()
+		.and_then(|r| r.nodes.as_ref())
+		.map(|nodes| nodes.iter().flatten()
+			.filter_map(|r| r.media_recommendation.as_ref())
+			.filter(|m| !policy.hides() || !m.is_adult.unwrap_or(false))
+			.map(media_link)
+			.collect::<Vec<String>>())
+		.unwrap_or_default();
+	if !recommendations.is_empty() {
+		e.field("Recommendations", field_lines(&recommendations), false);
+	}
+
+	let mut links = media.external_links.as_ref()
+		.map(|links| links.iter().flatten().map(|l| format!("[{}]({})", l.site, l.url)).collect::<Vec<String>>())
+		.unwrap_or_default();
+	if let Some(trailer) = media.trailer.as_ref().and_then(|t| t.url()) {
+		links.insert(0, format!("[Trailer]({})", trailer));
+	}
+	if !links.is_empty() {
+		e.field("Links", field_lines(&links).replace('\n', ", "), false);
+	}
+
+	if let Some(cover) = media.cover_image.as_ref().and_then(|c| c.large.as_ref()).filter(|_| !censored) {
+		e.thumbnail(cover);
+	}
+	e.set_author(ani_list_author);
+	e.color(ANI_LIST_COLOR);
+	fit_embed(&mut e);
+	e
+}
+
+/// Drops fields from the end until the embed fits in Discord's total character limit.
+fn fit_embed(e: &mut CreateEmbed) {
+	let text_len = |v: Option<&serde_json::Value>| v.and_then(|v| v.as_str()).map_or(0, |s| s.chars().count());
+	let fixed = text_len(e.0.get("title"))
+		+ text_len(e.0.get("description"))
+		+ e.0.get("author").map_or(0, |a| text_len(a.get("name")))
+		+ e.0.get("footer").map_or(0, |f| text_len(f.get("text")));
+
+	if let Some(serde_json::Value::Array(fields)) = e.0.get_mut("fields") {
+		let field_len = |f: &serde_json::Value| text_len(f.get("name")) + text_len(f.get("value"));
+		while fixed + fields.iter().map(field_len).sum::<usize>() > EMBED_LIMIT {
+			fields.pop();
+		}
+	}
+}
+
+/// A select menu of the entries related to `media`, if it has any.
+fn related_menu(media: &Media, policy: NsfwPolicy) -> Option<CreateActionRow> {
+	let options = media.relations.as_ref()
+		.and_then(|r| r.edges.as_ref())?
+		.iter()
+		.flatten()
+		.filter_map(|edge| {
+			let node = edge.node.as_ref().filter(|n| !policy.hides() || !n.is_adult.unwrap_or(false))?;
+			let title = node.title.as_ref().and_then(|t| t.user_preferred.clone()).unwrap_or_default();
+			let mut option = CreateSelectMenuOption::new(title.chars().take(100).collect::<String>(), node.id);
+			let mut description = vec![edge.relation_type.map(|r| r.to_string()).unwrap_or_else(|| "Related".to_string())];
+			if let Some(format) = node.format {
+				description.push(format.to_string());
 			}
-			e.thumbnail(media.cover_image.as_ref().unwrap().large.as_ref().unwrap());
-			e.set_author(ani_list_author);
+			option.description(description.join(" · "));
+			Some(option)
+		})
+		// Discord allows at most 25 options
+		.take(25)
+		.collect::<Vec<CreateSelectMenuOption>>();
+
+	if options.is_empty() {
+		return None;
+	}
+
+	let mut menu = CreateSelectMenu::default();
+	menu.custom_id(RELATED_MENU_ID);
+	menu.placeholder("Jump to a related entry");
+	menu.options(|o| o.set_options(options));
+
+	let mut row = CreateActionRow::default();
+	row.add_select_menu(menu);
+	Some(row)
+}
+
+fn media_link(media: &Media3) -> String {
+	let title = media.title.as_ref().and_then(|t| t.user_preferred.clone()).unwrap_or_default();
+	let kind = media.type_.map(|t| t.to_string().to_lowercase()).unwrap_or_else(|| "anime".to_string());
+	format!("[{}](https://anilist.co/{}/{})", title, kind, media.id)
+}
+
+/// Joins lines for an embed field, dropping the ones that would go over the 1024 character limit.
+fn field_lines(lines: &[String]) -> String {
+	let mut value = String::new();
+	for line in lines {
+		if value.len() + line.len() + 1 > 1024 {
+			break;
+		}
+		if !value.is_empty() {
+			value.push('\n');
+		}
+		value.push_str(line);
+	}
+	value
+}
+
+/// Tells the user why an AniList request failed.
+pub async fn send_anilist_error(ctx: &Context, msg: &Message, why: AniListError) -> CommandResult {
+	error!("AniList request failed: {}", why);
+	let description = match why {
+		AniListError::Transport(_) => "Couldn't reach AniList, try again later.".to_string(),
+		AniListError::Http { status, .. } => format!("AniList responded with an error ({}), try again later.", status),
+		AniListError::RateLimited { reset: Some(reset) } => format!("AniList is rate limiting us, try again <t:{}:R>.", reset.timestamp()),
+		AniListError::RateLimited { reset: None } => "AniList is rate limiting us, try again in a minute.".to_string(),
+		AniListError::GraphQl(errors) => {
+			let messages = errors.iter().map(|e| e.message.as_str()).collect::<Vec<_>>();
+			format!("AniList couldn't handle the request: {}", messages.join(", "))
+		}
+		AniListError::Decode(_) => "AniList sent a response I couldn't understand.".to_string(),
+		AniListError::NoData => "No results!".to_string(),
+	};
+
+	let ani_list_author = CreateEmbedAuthor::default()
+		.icon_url(ANI_LIST_AUTHOR_ICON_URL)
+		.name(ANI_LIST_AUTHOR_NAME)
+		.url(ANI_LIST_AUTHOR_URL)
+		.to_owned();
+
+	msg.channel_id.send_message(&ctx.http, |m| {
+		m.embed(|e| {
+			e.description(description);
 			e.color(ANI_LIST_COLOR);
+			e.set_author(ani_list_author);
 			e
 		});
 		m
 	}).await?;
-
 	Ok(())
-}
\ No newline at end of file
+}
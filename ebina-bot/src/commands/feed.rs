@@ -1,260 +1,930 @@
-use serenity::{framework::standard::{macros::command, Args, CommandResult}, futures::future::MapOk};
+use serenity::framework::standard::{macros::command, Args, CommandResult};
 use serenity::model::prelude::*;
 use serenity::http::Http;
 use serenity::prelude::*;
-use serenity::utils::*;
 use serenity::utils::MessageBuilder;
-use crate::establish_connection;
-use mangadex_api::MangaDexClient;
-use ron::*;
-use uuid::Uuid;
 
-use crate::models::*;
-use crate::schema::*;
+use chrono::Utc;
 
-use crate::diesel::prelude::*;
+use once_cell::sync::Lazy;
+
+use regex::Regex;
+
+use url::Url;
+
+use uuid::Uuid;
 
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 
-use tracing::{info};
+use tracing::{error, info};
 
-#[derive(Clone, Debug)]
-struct FeedGroup {
-	manga_id: u64,
+use crate::commands::airing::mention_roles;
+use crate::commands::mangadex::{mangadex_author, MANGADEX_COLOR};
+use crate::diesel::prelude::*;
+use crate::establish_connection;
+use crate::models::*;
+use crate::utils::mangadex::{cover_thumbnail, from_timestamp, is_adult_rating, ApiClient, Chapter, FeedSource};
+use crate::utils::follow::{create_follow_role, delete_follow_roles, existing_follow_role, forget_orphaned_follow_roles, save_follow_role, send_follow_panel};
+use crate::utils::nsfw::guild_nsfw_policy;
+use ebina_macro::tracking;
+
+/// What MangaDex language codes look like, `en` or `pt-br`.
+static LANGUAGE_CODE: Lazy<Regex> = Lazy::new(|| Regex::new(r"^[a-z]{2}(-[a-z]{2})?$").unwrap());
+
+/// What a feed follows, resolved from a link or id given to a command.
+enum FeedTarget {
+	Group(String, String),
+	Manga(String, String),
+}
+
+/// Chapters of one series in one language that came out since the last check, posted as a single message.
+struct ChapterDrop {
+	manga_id: String,
 	title: String,
-	last: f64,
-	last_id: u64,
-	first: f64,
-	first_id: u64,
-	group: Vec<String>,
-	chapters: u64,
+	language: String,
+	adult: bool,
+	chapters: Vec<Chapter>,
 }
 
-trait Update {
-	fn set_last(&mut self, last: f64, last_id: u64);
-	fn set_first(&mut self, first: f64, first_id: u64);
+/// Where new chapters are posted.
+pub enum DropTarget<'a> {
+	/// A feed's channel, pinging the roles of the series each message is about.
+	Channel { server: i64, channel: i64, roles: &'a [FeedRole] },
+	/// A member's DMs, adult series are sent like everything else since they're private.
+	User(UserId),
 }
 
-impl Update for FeedGroup {
-	fn set_last(&mut self, last: f64, last_id: u64) {
-		self.last = last;
-		self.last_id = last_id;
-	}
+#[tracking("md_feed_set")]
+#[command("set")]
+#[only_in("guilds")]
+#[required_permissions("MANAGE_CHANNELS")]
+#[min_args(1)]
+#[usage = "<group or title link> [title link]"]
+#[example = "https://mangadex.org/group/4f1de6a2-f0c5-4ac5-bce5-02c7dbb67deb"]
+#[description = "Posts new chapters of a scanlation group or a series in this channel. Give a title after a group to only follow that series of the group."]
+pub async fn feed_set(ctx: &Context, msg: &Message, mut args: Args) -> CommandResult {
+	let guild = msg.guild_id.unwrap();
+
+	let target = match resolve_target(&args.single::<String>()?).await {
+		Some(v) => v,
+		None => {
+			msg.reply(ctx, "That's not a MangaDex group or title").await?;
+			return Ok(())
+		}
+	};
+	let series = match args.single::<String>() {
+		Ok(arg) => match resolve_target(&arg).await {
+			Some(FeedTarget::Manga(id, title)) => Some((id, title)),
+			_ => {
+				msg.reply(ctx, "The second link has to be a MangaDex title").await?;
+				return Ok(())
+			}
+		},
+		Err(_) => None,
+	};
+
+	let (group, manga, name) = match (&target, &series) {
+		(FeedTarget::Group(group, group_name), Some((manga, title))) => (Some(group.as_str()), Some(manga.as_str()), format!("{} from {}", title, group_name)),
+		(FeedTarget::Group(group, group_name), None) => (Some(group.as_str()), None, group_name.clone()),
+		(FeedTarget::Manga(manga, title), _) => (None, Some(manga.as_str()), title.clone()),
+	};
+
+	{
+		use crate::schema::feeds::dsl::*;
+
+		let connection = establish_connection();
+		let channel_feeds = feeds
+			.filter(channel_id.eq(msg.channel_id.0 as i64))
+			.load::<Feed>(&connection)?;
 
-	fn set_first(&mut self, first: f64, first_id: u64) {
-		self.first = first;
-		self.first_id = first_id;
+		if channel_feeds.iter().any(|f| f.group.as_deref() == group && f.manga.as_deref() == manga) {
+			msg.reply(ctx, format!("This channel already gets new chapters of {}", name)).await?;
+			return Ok(())
+		}
+
+		// Language filters are per channel, so new feeds start out with the channel's filter
+		let channel_languages = channel_feeds.first().map(|f| f.languages.clone()).unwrap_or_default();
+		let new_feed = NewFeed {
+			server_id: &(guild.0 as i64),
+			channel_id: &(msg.channel_id.0 as i64),
+			group_id: group,
+			manga_id: manga,
+			languages: &channel_languages,
+		};
+		diesel::insert_into(feeds)
+			.values(&new_feed)
+			.execute(&connection)?;
 	}
-}
 
-#[command]
-#[owners_only]
-#[min_args(1)]
-pub async fn set(ctx: &Context, msg: &Message, mut args: Args) -> CommandResult {
-	let data = ctx.data.read().await;
-	let client = MangaDexClient::default();
-	let guild_id = msg.guild_id.unwrap();
-	let channel_id = msg.channel_id;
-	let group_id = Uuid::parse_str(args.single::<String>().unwrap().as_str()).unwrap();
-	let group_res = client.scanlation_group()
-		.get()
-		.group_id(&group_id)
-		.build()?
-		.send()
-		.await?;
-	let group = group_res.data;
-	let conn = establish_connection();
-
-	create_feed(&conn, &(guild_id.0 as i64), &(channel_id.0 as i64), &group_id);
-	&msg.channel_id.send_message(&ctx.http, |m| {
+	msg.channel_id.send_message(&ctx.http, |m| {
 		m.embed(|e| {
-			e.title("Ebina");
-			let message = MessageBuilder::new()
+			e.set_author(mangadex_author());
+			e.color(MANGADEX_COLOR);
+			e.description(MessageBuilder::new()
 				.push("Set ")
-				.mention(&channel_id)
+				.mention(&msg.channel_id)
 				.push(" as announcement channel for ")
-				.push(group.name())
-				.build();
-			e.description(message);
+				.push_safe(&name)
+				.build());
 			e
-		});
-		m
-	})
-	.await.unwrap();
-	println!("{:?}", group);
+		})
+	}).await?;
+	Ok(())
+}
+
+#[tracking("md_feed_unset")]
+#[command("unset")]
+#[only_in("guilds")]
+#[required_permissions("MANAGE_CHANNELS")]
+#[usage = "[group or title link]"]
+#[description = "Stops posting new chapters in this channel, only for the given group or title if one is given"]
+pub async fn feed_unset(ctx: &Context, msg: &Message, mut args: Args) -> CommandResult {
+	use crate::schema::feeds::dsl::*;
+
+	let connection = establish_connection();
+	let channel_feeds = feeds.filter(channel_id.eq(msg.channel_id.0 as i64));
+	let everything = args.is_empty();
+
+	let (removed, group) = match args.single::<String>() {
+		Ok(arg) => match resolve_target(&arg).await {
+			Some(FeedTarget::Group(group, _)) => (diesel::delete(channel_feeds.filter(group_id.eq(&group))).execute(&connection)?, Some(group)),
+			Some(FeedTarget::Manga(manga, _)) => (diesel::delete(channel_feeds.filter(manga_id.eq(manga))).execute(&connection)?, None),
+			None => {
+				msg.reply(ctx, "That's not a MangaDex group or title").await?;
+				return Ok(())
+			}
+		},
+		Err(_) => (diesel::delete(channel_feeds).execute(&connection)?, None),
+	};
+
+	// Groups linked with `md link` stop announcing new series along with their chapters
+	{
+		use crate::schema::group_links::dsl::{group_links, channel_id as link_channel, group_id as link_group};
+
+		let channel_links = group_links.filter(link_channel.eq(msg.channel_id.0 as i64));
+		match (&group, everything) {
+			(Some(group), _) => diesel::delete(channel_links.filter(link_group.eq(group))).execute(&connection)?,
+			(None, true) => diesel::delete(channel_links).execute(&connection)?,
+			(None, false) => 0,
+		};
+	}
+
+	let orphaned = forget_orphaned_follow_roles(&connection);
+	delete_follow_roles(&ctx.http, orphaned).await;
+
+	if removed == 0 {
+		msg.reply(ctx, "This channel has no feeds to remove").await?;
+	} else {
+		msg.reply(ctx, format!("Removed {} feed{} from this channel", removed, if removed == 1 { "" } else { "s" })).await?;
+	}
 	Ok(())
 }
 
-#[command]
-#[owners_only]
-pub async fn unset(ctx: &Context, msg: &Message) -> CommandResult {
-	let channel_id = msg.channel_id;
-	let conn = establish_connection();
-	delete_feed(&conn, &(channel_id.0 as i64));
+#[tracking("md_feed_languages")]
+#[command("languages")]
+#[aliases("language", "lang")]
+#[only_in("guilds")]
+#[required_permissions("MANAGE_CHANNELS")]
+#[min_args(1)]
+#[usage = "<language codes or all>"]
+#[example = "en,pt-br"]
+#[description = "Only posts chapters in the given languages in this channel, `all` posts every language"]
+pub async fn feed_languages(ctx: &Context, msg: &Message, args: Args) -> CommandResult {
+	use crate::schema::feeds::dsl::*;
+
+	let input = args.rest().to_lowercase();
+	let codes = if input.trim() == "all" {
+		Vec::new()
+	} else {
+		input.split(|c: char| c == ',' || c.is_whitespace())
+			.filter(|c| !c.is_empty())
+			.map(str::to_string)
+			.collect::<Vec<_>>()
+	};
+
+	if let Some(invalid) = codes.iter().find(|c| !LANGUAGE_CODE.is_match(c)) {
+		msg.reply(ctx, format!("`{}` isn't a MangaDex language code, they look like `en` or `pt-br`", invalid)).await?;
+		return Ok(())
+	}
+
+	let connection = establish_connection();
+	let updated = diesel::update(feeds.filter(channel_id.eq(msg.channel_id.0 as i64)))
+		.set(languages.eq(codes.join(",")))
+		.execute(&connection)?;
+
+	if updated == 0 {
+		msg.reply(ctx, "This channel has no feeds, add one with `md feed set` first").await?;
+	} else if codes.is_empty() {
+		msg.reply(ctx, "This channel now gets chapters in every language").await?;
+	} else {
+		msg.reply(ctx, format!("This channel now only gets chapters in {}", codes.join(", "))).await?;
+	}
 	Ok(())
 }
 
-#[command]
-#[owners_only]
-pub async fn role(ctx: &Context, msg: &Message, mut args: Args) -> CommandResult {
-	let manga_id = args.single::<u64>()?;
+#[tracking("md_feed_list")]
+#[command("list")]
+#[only_in("guilds")]
+#[description = "Lists the feeds posting in this server"]
+pub async fn feed_list(ctx: &Context, msg: &Message) -> CommandResult {
+	use crate::schema::feeds::dsl::*;
+
+	let connection = establish_connection();
+	let server_feeds = feeds
+		.filter(server_id.eq(msg.guild_id.unwrap().0 as i64))
+		.order((channel_id, id))
+		.load::<Feed>(&connection)?;
+
+	if server_feeds.is_empty() {
+		msg.reply(ctx, "This server has no feeds").await?;
+		return Ok(())
+	}
+
+	let lines = server_feeds.iter().map(|feed| {
+		let source = match (&feed.group, &feed.manga) {
+			(Some(group), Some(manga)) => format!("[Group](https://mangadex.org/group/{}) - [Title](https://mangadex.org/title/{})", group, manga),
+			(Some(group), None) => format!("[Group](https://mangadex.org/group/{})", group),
+			(None, Some(manga)) => format!("[Title](https://mangadex.org/title/{})", manga),
+			(None, None) => "Nothing".to_string(),
+		};
+		let feed_languages = if feed.languages.is_empty() { "all languages" } else { feed.languages.as_str() };
+		format!("<#{}> {} ({})", feed.channel, source, feed_languages)
+	}).collect::<Vec<_>>();
+
+	msg.channel_id.send_message(&ctx.http, |m| {
+		m.embed(|e| {
+			e.set_author(mangadex_author());
+			e.color(MANGADEX_COLOR);
+			e.title("Feeds");
+			e.description(lines.join("\n"));
+			e
+		})
+	}).await?;
+	Ok(())
+}
+
+#[tracking("md_feed_role")]
+#[command("role")]
+#[only_in("guilds")]
+#[required_permissions("MANAGE_ROLES")]
+#[min_args(2)]
+#[usage = "<title link or all> <@role>"]
+#[example = "all @Chapters"]
+#[description = "Pings a role when a new chapter of a title is posted in this channel, `all` pings it for every title"]
+pub async fn feed_role(ctx: &Context, msg: &Message, mut args: Args) -> CommandResult {
 	let guild_id = msg.guild_id.unwrap();
-	let role_id: u64;
+
+	let scope = match role_scope(&args.single::<String>()?).await {
+		Some(v) => v,
+		None => {
+			msg.reply(ctx, "That's not a MangaDex title").await?;
+			return Ok(())
+		}
+	};
+	let role = match msg.mention_roles.first() {
+		Some(v) => *v,
+		None => {
+			msg.reply(ctx, "Mention the role to ping").await?;
+			return Ok(())
+		}
+	};
+
 	let connection = establish_connection();
+	let covering = channel_feeds(&connection, msg.channel_id)?
+		.into_iter()
+		.filter(|f| scope.covers(f))
+		.collect::<Vec<_>>();
+
+	if covering.is_empty() {
+		msg.reply(ctx, "No feed in this channel posts that title, add one with `md feed set` first").await?;
+		return Ok(())
+	}
 
-	if msg.mention_roles.len() > 0 {
-		role_id = msg.mention_roles[0].0;
-	} else {
-		return Ok(());
+	for feed in covering {
+		add_feed_role(&connection, guild_id, feed.id, scope.manga_id(), role)?;
 	}
 
-	use crate::schema::roles::dsl::*;
+	msg.reply(ctx, format!("I'll ping <@&{}> when new chapters of {} are posted here", role.0, scope.name())).await?;
+	Ok(())
+}
 
-	let mut map: HashMap<u64, u64>;
+#[tracking("md_feed_unrole")]
+#[command("unrole")]
+#[only_in("guilds")]
+#[required_permissions("MANAGE_ROLES")]
+#[min_args(1)]
+#[usage = "<title link or all> [@role]"]
+#[description = "Stops pinging a role for a title in this channel, every role if none is mentioned"]
+pub async fn feed_unrole(ctx: &Context, msg: &Message, mut args: Args) -> CommandResult {
+	use crate::schema::feed_roles::dsl::*;
+
+	let scope = match role_scope(&args.single::<String>()?).await {
+		Some(v) => v,
+		None => {
+			msg.reply(ctx, "That's not a MangaDex title").await?;
+			return Ok(())
+		}
+	};
+	let role = msg.mention_roles.first().copied();
+
+	let connection = establish_connection();
+	let feed_ids = channel_feeds(&connection, msg.channel_id)?
+		.into_iter()
+		.map(|f| f.id)
+		.collect::<Vec<_>>();
+
+	let rows = feed_roles.filter(feed_id.eq_any(feed_ids));
+	let removed = match (scope.manga_id(), role) {
+		(Some(manga), Some(role)) => diesel::delete(rows.filter(manga_id.eq(manga)).filter(role_id.eq(role.0 as i64))).execute(&connection)?,
+		(Some(manga), None) => diesel::delete(rows.filter(manga_id.eq(manga))).execute(&connection)?,
+		(None, Some(role)) => diesel::delete(rows.filter(manga_id.is_null()).filter(role_id.eq(role.0 as i64))).execute(&connection)?,
+		(None, None) => diesel::delete(rows.filter(manga_id.is_null())).execute(&connection)?,
+	};
+	let orphaned = forget_orphaned_follow_roles(&connection);
+	delete_follow_roles(&ctx.http, orphaned).await;
 
-	let roles_res = roles
-		.filter(crate::schema::roles::columns::server_id.eq(guild_id.0 as i64))
-		.load::<crate::models::Role>(&connection)
-		.unwrap();
-	if roles_res.len() > 0 {
-		map = ron::from_str(&roles_res[0].data).unwrap_or(HashMap::new());
+	if removed == 0 {
+		msg.reply(ctx, format!("No roles were pinged for {} here", scope.name())).await?;
 	} else {
-		map = HashMap::new();
+		msg.reply(ctx, format!("Stopped pinging for {} here", scope.name())).await?;
 	}
+	Ok(())
+}
 
-	map.insert(manga_id, role_id);
+#[tracking("md_feed_follow")]
+#[command("follow")]
+#[only_in("guilds")]
+#[required_permissions("MANAGE_ROLES")]
+#[min_args(1)]
+#[usage = "<title link or all>"]
+#[example = "https://mangadex.org/title/a96676e5-8ae2-425e-b549-7f15dd34a6d8"]
+#[description = "Posts a button members can press to get pinged for new chapters of a title in this channel, `all` for every title"]
+pub async fn feed_follow(ctx: &Context, msg: &Message, mut args: Args) -> CommandResult {
+	use crate::schema::feed_roles::dsl::*;
+
+	let guild = msg.guild_id.unwrap();
+
+	let scope = match role_scope(&args.single::<String>()?).await {
+		Some(v) => v,
+		None => {
+			msg.reply(ctx, "That's not a MangaDex title").await?;
+			return Ok(())
+		}
+	};
 
-	if roles_res.len() > 0 {
-		diesel::update(roles).set(data.eq(ron::to_string(&map).unwrap())).execute(&connection).unwrap();
-	} else {
-		let new_role = crate::models::NewRole{
-			server_id: &(guild_id.0 as i64),
-			data: &ron::to_string(&map).unwrap()
-		};
-		diesel::insert_into(crate::schema::roles::table)
-		.values(new_role)
-		.get_result::<crate::models::Role>(&connection)
-		.expect("Error adding new role");
+	let connection = establish_connection();
+	let covering = channel_feeds(&connection, msg.channel_id)?
+		.into_iter()
+		.filter(|f| scope.covers(f))
+		.collect::<Vec<_>>();
+
+	if covering.is_empty() {
+		msg.reply(ctx, "No feed in this channel posts that title, add one with `md feed set` first").await?;
+		return Ok(())
 	}
 
+	let title = match &scope {
+		RoleScope::All => format!("#{} chapters", msg.channel_id.name(&ctx.cache).await.unwrap_or_default()),
+		RoleScope::Series(_, series) => series.clone(),
+	};
+
+	// Reuse the follow role these feeds already ping for the title, panels can be posted again
+	let pinged = feed_roles
+		.filter(feed_id.eq_any(covering.iter().map(|f| f.id).collect::<Vec<_>>()))
+		.load::<FeedRole>(&connection)?
+		.into_iter()
+		.filter(|r| r.manga_id.as_deref() == scope.manga_id())
+		.map(|r| r.role_id)
+		.collect::<Vec<_>>();
+	let existing = existing_follow_role(&connection, &pinged)?.map(|f| RoleId(f.role_id as u64));
+	let role = match existing {
+		Some(v) => v,
+		None => create_follow_role(&ctx.http, guild, &title).await?,
+	};
+
+	// A new role is saved along with its pings, pruning could delete it in between otherwise
+	let saved = connection.transaction::<_, diesel::result::Error, _>(|| {
+		if existing.is_none() {
+			save_follow_role(&connection, guild, role, &title)?;
+		}
+		for feed in &covering {
+			add_feed_role(&connection, guild, feed.id, scope.manga_id(), role)?;
+		}
+		Ok(())
+	});
+	if let Err(why) = saved {
+		if existing.is_none() {
+			guild.delete_role(&ctx.http, role).await?;
+		}
+		return Err(why.into());
+	}
+
+	send_follow_panel(&ctx.http, msg.channel_id, role, &title, MANGADEX_COLOR).await?;
 	Ok(())
 }
 
+/// Which chapters of a channel's feeds a role gets pinged for.
+enum RoleScope {
+	All,
+	Series(String, String),
+}
+
+impl RoleScope {
+	/// Whether the feed posts chapters this role gets pinged for, group feeds post every series.
+	fn covers(&self, feed: &Feed) -> bool {
+		match self {
+			RoleScope::All => true,
+			RoleScope::Series(manga, _) => feed.manga.as_ref() == Some(manga) || (feed.manga.is_none() && feed.group.is_some()),
+		}
+	}
+
+	fn manga_id(&self) -> Option<&str> {
+		match self {
+			RoleScope::All => None,
+			RoleScope::Series(manga, _) => Some(manga),
+		}
+	}
+
+	fn name(&self) -> &str {
+		match self {
+			RoleScope::All => "every title",
+			RoleScope::Series(_, title) => title,
+		}
+	}
+}
+
+async fn role_scope(arg: &str) -> Option<RoleScope> {
+	if arg.eq_ignore_ascii_case("all") {
+		return Some(RoleScope::All);
+	}
+	match resolve_target(arg).await? {
+		FeedTarget::Manga(manga, title) => Some(RoleScope::Series(manga, title)),
+		FeedTarget::Group(_, _) => None,
+	}
+}
 
-pub async fn check_feeds(token: String) {
+fn channel_feeds(connection: &PgConnection, channel: ChannelId) -> QueryResult<Vec<Feed>> {
 	use crate::schema::feeds::dsl::*;
-	use crate::schema::roles::dsl::*;
 
-	let connection = establish_connection();
+	feeds.filter(channel_id.eq(channel.0 as i64)).load::<Feed>(connection)
+}
 
-	let http = Http::new_with_token(&token);
+/// Pings the role for a feed's chapters, of one series or all of them, unless it already is.
+fn add_feed_role(connection: &PgConnection, guild: GuildId, feed: i32, manga: Option<&str>, role: RoleId) -> QueryResult<()> {
+	use crate::schema::feed_roles::dsl::*;
+
+	let existing = feed_roles
+		.filter(feed_id.eq(feed))
+		.filter(role_id.eq(role.0 as i64))
+		.load::<FeedRole>(connection)?;
+	if existing.iter().any(|r| r.manga_id.as_deref() == manga) {
+		return Ok(());
+	}
 
-	let md_client = MangaDexClient::default();
+	diesel::insert_into(feed_roles)
+		.values(NewFeedRole {
+			guild_id: &(guild.0 as i64),
+			feed_id: Some(feed),
+			airing_subscription_id: None,
+			manga_id: manga,
+			role_id: &(role.0 as i64),
+		})
+		.execute(connection)?;
+	Ok(())
+}
 
-	let results = feeds
-		.load::<Feed>(&connection)
-		.expect("Error loading posts");
-	for feed in results {
-		let group_res = md_client.group_chapters(feed.manga as u64).limit(10).send().await.unwrap().ok().unwrap();
-		let chapters = group_res.data().chapters();
-		let groups = group_res.data().groups();
-		let roles_res = roles
-			.filter(crate::schema::roles::columns::server_id.eq(feed.server as i64))
-			.load::<crate::models::Role>(&connection)
-			.unwrap();
-		let role_data: HashMap<u64, u64>;
-		if roles_res.len() > 0 {
-			role_data = ron::from_str(&roles_res[0].data).unwrap();
-		} else {
-			role_data = HashMap::new();
+/// Moves the RON role maps of the old `roles` table over to `feed_roles`.
+///
+/// They're keyed by manga ids from the old MangaDex site, which only MangaDex can map
+/// to v5 ids, so this runs on start instead of in a migration. A server's map is only
+/// removed once its roles are moved, and moving is retried on the next start otherwise.
+pub async fn migrate_legacy_roles() {
+	use crate::schema::feeds::dsl::*;
+
+	let connection = establish_connection();
+
+	let legacy = match crate::schema::roles::table.load::<Role>(&connection) {
+		Ok(v) => v,
+		Err(why) => {
+			error!("Error loading legacy roles: {:?}", why);
+			return;
 		}
-		let mut chapters_group = HashMap::new();
-		for chapter in chapters {
-			if chrono::offset::Local::now().signed_duration_since(*chapter.timestamp()).num_minutes() > 10 {
-				break;
-			}
+	};
 
-			let mut groups_vec = Vec::<String>::new();
-			for group in groups {
-				groups_vec.push(group.name().clone());
-			};
+	for server_roles in legacy {
+		// Roles that can't be read are kept as they are rather than dropped with the row
+		let map = match ron::from_str::<HashMap<u64, u64>>(&server_roles.data) {
+			Ok(v) => v,
+			Err(why) => {
+				error!("Error parsing legacy roles of guild {}, keeping them: {:?}", server_roles.server, why);
+				continue;
+			}
+		};
+		let legacy_manga = map.keys().copied().collect::<Vec<_>>();
+		let mapped = match ApiClient::default().legacy_ids("manga", &legacy_manga).await {
+			Ok(v) => v,
+			Err(why) => {
+				error!("Error mapping legacy roles of guild {}: {:?}", server_roles.server, why);
+				continue;
+			}
+		};
+		let server_feeds = match feeds.filter(server_id.eq(server_roles.server)).load::<Feed>(&connection) {
+			Ok(v) => v,
+			Err(why) => {
+				error!("Error loading feeds of guild {}: {:?}", server_roles.server, why);
+				continue;
+			}
+		};
 
-			let feed_group = FeedGroup {
-			    manga_id: *chapter.manga_id(),
-			    title: chapter.manga_title().to_string(),
-			    last: chapter.chapter().parse::<f64>().unwrap(),
-			    last_id: *chapter.id(),
-			    first: chapter.chapter().parse::<f64>().unwrap(), 
-			    first_id: *chapter.id(),
-			    group: groups_vec,
-				chapters: 1,
-			};
-			info!("{:?}", feed_group);
-			let chapter_group = match chapters_group.get_mut(chapter.manga_id()) {
-				Some(v) => {v},
+		let mut moved = Vec::new();
+		for (legacy_id, role) in &map {
+			let manga = match mapped.get(legacy_id) {
+				Some(v) => v,
 				None => {
-					chapters_group.insert(chapter.manga_id(), feed_group.clone());
+					info!("Legacy manga {} has no v5 id, dropping its role {}", legacy_id, role);
 					continue;
-				},
+				}
 			};
+			let scope = RoleScope::Series(manga.clone(), String::new());
+			moved.extend(server_feeds.iter().filter(|f| scope.covers(f)).map(|f| (f.id, manga.clone(), *role)));
+		}
+
+		let result = connection.transaction::<_, diesel::result::Error, _>(|| {
+			for (feed, manga, role) in &moved {
+				add_feed_role(&connection, GuildId(server_roles.server as u64), *feed, Some(manga), RoleId(*role))?;
+			}
+			diesel::delete(crate::schema::roles::table.find(server_roles.id)).execute(&connection)?;
+			Ok(())
+		});
+		match result {
+			Ok(_) => info!("Moved {} legacy roles of guild {}", moved.len(), server_roles.server),
+			Err(why) => error!("Error moving legacy roles of guild {}: {:?}", server_roles.server, why),
+		}
+	}
+}
 
-			if chapter_group.last < chapter.chapter().parse::<f64>().unwrap() {
-				chapter_group.set_last(chapter.chapter().parse::<f64>().unwrap(), *chapter.id());
+/// Works out what a link, UUID or legacy numeric group id points at.
+async fn resolve_target(arg: &str) -> Option<FeedTarget> {
+	let arg = arg.trim_matches(|c| c == '<' || c == '>');
+
+	if let Ok(url) = Url::parse(arg) {
+		let mut path_segments = url.path_segments()?;
+		let kind = path_segments.next()?;
+		let id = Uuid::parse_str(path_segments.next()?).ok()?.to_string();
+		return match kind {
+			"group" => group_target(&id).await,
+			"title" | "manga" => manga_target(&id).await,
+			_ => None,
+		};
+	}
+
+	if let Ok(legacy) = arg.parse::<u64>() {
+		let id = ApiClient::default().legacy_group_id(legacy).await.ok()??;
+		return group_target(&id).await;
+	}
+
+	// A bare UUID could be either
+	let id = Uuid::parse_str(arg).ok()?.to_string();
+	match group_target(&id).await {
+		Some(v) => Some(v),
+		None => manga_target(&id).await,
+	}
+}
+
+async fn group_target(id: &str) -> Option<FeedTarget> {
+	let group = ApiClient::default().get_group(id).await.ok()?;
+	Some(FeedTarget::Group(group.id, group.attributes.name))
+}
+
+async fn manga_target(id: &str) -> Option<FeedTarget> {
+	let manga = ApiClient::default().get_manga(id).await.ok()?;
+	let title = manga.title().unwrap_or_else(|| manga.id.clone());
+	Some(FeedTarget::Manga(manga.id, title))
+}
+
+/// Posts the chapters that came out since the last check to every feed.
+pub async fn check_feeds(http: &Http) {
+	use crate::schema::feeds::dsl::*;
+
+	let connection = establish_connection();
+
+	let results = match feeds.load::<Feed>(&connection) {
+		Ok(v) => v,
+		Err(why) => {
+			error!("Error loading feeds: {:?}", why);
+			return;
+		}
+	};
+	let mut pinged = HashMap::<i32, Vec<FeedRole>>::new();
+	match crate::schema::feed_roles::table.load::<FeedRole>(&connection) {
+		Ok(v) => {
+			for role in v {
+				if let Some(feed) = role.feed_id {
+					pinged.entry(feed).or_default().push(role);
+				}
 			}
+		},
+		Err(why) => error!("Error loading feed roles: {:?}", why),
+	}
 
-			if chapter_group.first > chapter.chapter().parse::<f64>().unwrap() {
-				chapter_group.set_first(chapter.chapter().parse::<f64>().unwrap(), *chapter.id());
+	// Everything is saved after the checks, a connection can't be borrowed across them
+	for feed in results {
+		let feed_id = feed.id;
+		let roles = pinged.remove(&feed_id).unwrap_or_default();
+		let progress = check_feed(http, feed, &roles).await;
+
+		if let Some(new_id) = progress.group {
+			if let Err(why) = diesel::update(feeds.find(feed_id))
+				.set(group_id.eq(&new_id))
+				.execute(&connection) {
+				error!("Error updating group of feed {}: {:?}", feed_id, why);
+			}
+		}
+		if let Some(newest) = progress.last_chapter_at {
+			if let Err(why) = diesel::update(feeds.find(feed_id))
+				.set(last_chapter_at.eq(newest))
+				.execute(&connection) {
+				error!("Error updating feed {}: {:?}", feed_id, why);
 			}
 		}
-		let channel = http.get_channel(feed.channel as u64).await.unwrap().guild().unwrap();
-
-		for (_, chapter) in chapters_group.iter() {
-			channel.id.send_message(&http, |m| {
-				m.embed(|e| {
-					if chapter.last != chapter.first {
-						e.title(format!("Ch. {} - {} - {}", chapter.first, chapter.last, chapter.title));
-					} else {
-						e.title(format!("Ch. {} - {}", chapter.last, chapter.title));
-					}
-					e.description(format!("New chapter is out! [Click here to read](https://mangadex.org/chapter/{})", chapter.first_id));
-					e.author(|a| {
-						a.name("MangaDex");
-						a.icon_url("https://i.imgur.com/gFzVv6g.png");
-						a.url("https://mangadex.org/");
-						a
-					});
-					e.thumbnail(format!("https://mangadex.org/images/manga/{}.jpg", chapter.manga_id));
-					e.color(Colour::from_rgb(246, 131, 40));
-					e
-				});
-				if role_data.contains_key(&chapter.manga_id) {
-					let r = RoleId{0: *role_data.get(&chapter.manga_id).unwrap()};
-					let message = MessageBuilder::new()
-					.mention(&r)
-					.build();
-					m.content(message);
+	}
+
+	let links = {
+		use crate::schema::group_links::dsl::*;
+
+		match group_links.load::<GroupLink>(&connection) {
+			Ok(v) => v,
+			Err(why) => {
+				error!("Error loading group links: {:?}", why);
+				return;
+			}
+		}
+	};
+
+	for link in links {
+		let link_id = link.id;
+		if let Some(series) = check_group_link(http, link).await {
+			use crate::schema::group_links::dsl::*;
+
+			if let Err(why) = diesel::update(group_links.find(link_id))
+				.set(known_series.eq(series.join(",")))
+				.execute(&connection) {
+				error!("Error updating group link {}: {:?}", link_id, why);
+			}
+		}
+	}
+
+	// Catches follow roles of feeds removed some other way than the commands
+	let orphaned = forget_orphaned_follow_roles(&connection);
+	delete_follow_roles(http, orphaned).await;
+}
+
+/// The NSFW policy for a feed's channel, NSFW channels allow everything.
+async fn channel_policy(http: &Http, server: i64, channel: i64) -> Option<NsfwPolicy> {
+	match http.get_channel(channel as u64).await {
+		Ok(Channel::Guild(c)) if c.is_nsfw() => Some(NsfwPolicy::Allow),
+		Ok(_) => Some(guild_nsfw_policy(GuildId(server as u64))),
+		Err(why) => {
+			error!("Error getting feed channel {}: {:?}", channel, why);
+			None
+		}
+	}
+}
+
+/// Announces series a linked group started uploading since the last check.
+///
+/// Returns every series of the group when there were new ones, to be saved as known.
+async fn check_group_link(http: &Http, link: GroupLink) -> Option<Vec<String>> {
+	let series = match ApiClient::default().group_manga(&link.group_id).await {
+		Ok(v) => v,
+		Err(why) => {
+			error!("Error getting series of MangaDex group {}: {:?}", link.group_id, why);
+			return None;
+		}
+	};
+
+	let known = link.known_series.split(',').collect::<HashSet<_>>();
+	let new_series = series.iter().filter(|m| !known.contains(m.id.as_str())).collect::<Vec<_>>();
+	if new_series.is_empty() {
+		return None;
+	}
+
+	let policy = channel_policy(http, link.server_id, link.channel_id).await?;
+
+	// Oldest first, the group's series come newest first
+	for manga in new_series.into_iter().rev() {
+		let adult = is_adult_rating(manga.content_rating());
+		if adult && policy.hides() {
+			continue;
+		}
+		let title = manga.title().unwrap_or_else(|| manga.id.clone());
+		let cover = manga.cover_file().filter(|_| !policy.censors(adult)).map(|file| cover_thumbnail(&manga.id, &file));
+
+		let posted = ChannelId(link.channel_id as u64).send_message(http, |m| {
+			m.embed(|e| {
+				e.title(&title);
+				e.url(format!("https://mangadex.org/title/{}", manga.id));
+				e.description(format!("[{}](https://mangadex.org/group/{}) started a new series!", link.group_name, link.group_id));
+				if let Some(cover) = &cover {
+					e.thumbnail(cover);
 				}
-				m
+				e.set_author(mangadex_author());
+				e.color(MANGADEX_COLOR);
+				e
 			})
-			.await.unwrap();
+		}).await;
+		if let Err(why) = posted {
+			error!("Error announcing {} of group link {}: {:?}", manga.id, link.id, why);
 		}
 	}
+
+	Some(series.into_iter().map(|m| m.id).collect())
 }
 
-pub fn create_feed<'a>(conn: &PgConnection, server: &'a i64, channel: &'a i64, manga: &'a String) -> Feed {
-	let new_feed = NewFeed {
-		server_id: server,
-		channel_id: channel,
-		manga_id: manga
+/// What a feed check wants saved.
+#[derive(Default)]
+struct FeedProgress {
+	/// The v5 id of a feed's legacy numeric group id
+	group: Option<String>,
+	/// When the newest chapter came out, or now for a feed that just started
+	last_chapter_at: Option<i64>,
+}
+
+async fn check_feed(http: &Http, mut feed: Feed, pinged: &[FeedRole]) -> FeedProgress {
+	let mut progress = FeedProgress::default();
+
+	// Feeds made before MangaDex v5 still hold the numeric group id
+	if let Some(legacy) = feed.group.as_ref().and_then(|g| g.parse::<u64>().ok()) {
+		let new_id = match ApiClient::default().legacy_group_id(legacy).await {
+			Ok(Some(v)) => v,
+			Ok(None) => {
+				error!("Legacy MangaDex group {} of feed {} has no v5 id", legacy, feed.id);
+				return progress;
+			},
+			Err(why) => {
+				error!("Error mapping legacy MangaDex group {}: {:?}", legacy, why);
+				return progress;
+			}
+		};
+		info!("Mapped legacy MangaDex group {} to {}", legacy, new_id);
+		feed.group = Some(new_id.clone());
+		progress.group = Some(new_id);
+	}
+
+	// Start from now instead of posting the whole backlog of a new feed
+	let last = match feed.last_chapter_at {
+		Some(v) => v,
+		None => {
+			progress.last_chapter_at = Some(Utc::now().timestamp());
+			return progress;
+		}
 	};
-	diesel::insert_into(feeds::table)
-		.values(&new_feed)
-		.get_result(conn)
-		.expect("Error saving new feed")
+
+	let source = match (&feed.group, &feed.manga) {
+		(Some(group), manga) => FeedSource::Group(group, manga.as_deref()),
+		(None, Some(manga)) => FeedSource::Manga(manga),
+		(None, None) => return progress,
+	};
+	let feed_languages = feed.languages.split(',').filter(|l| !l.is_empty()).collect::<Vec<_>>();
+
+	let target = DropTarget::Channel { server: feed.server, channel: feed.channel, roles: pinged };
+	progress.last_chapter_at = post_new_chapters(http, vec![source], &feed_languages, last, target, &format!("feed {}", feed.id)).await;
+	progress
 }
 
-pub fn delete_feed<'a>(conn: &PgConnection, channel: &'a i64) {
-	use crate::schema::feeds::dsl::*;
+/// Posts the chapters published after `last` to `target`, `name` says whose they are in the logs.
+///
+/// Returns when the newest chapter that was posted came out, to be checked from next time. Nothing is returned
+/// when there was nothing new or it should all be tried again.
+pub async fn post_new_chapters(http: &Http, sources: Vec<FeedSource<'_>>, languages: &[&str], last: i64, target: DropTarget<'_>, name: &str) -> Option<i64> {
+	let mangadex = ApiClient::default();
+	let mut chapters = Vec::new();
+	for source in sources {
+		match mangadex.chapter_feed(source, languages, from_timestamp(last)).await {
+			Ok(v) => chapters.extend(v),
+			Err(why) => {
+				// Leave it for the next check if MangaDex is having trouble
+				error!("Error getting chapters of {}: {:?}", name, why);
+				return None;
+			}
+		}
+	}
+	// The publish time filter is inclusive, the last posted chapter comes back every time
+	let chapters = chapters.into_iter()
+		.filter(|c| c.published_at().timestamp() > last)
+		.collect::<Vec<_>>();
+	let newest = chapters.iter().map(|c| c.published_at().timestamp()).max()?;
+
+	let (channel, policy, pinged) = match target {
+		DropTarget::Channel { server, channel, roles } => {
+			let policy = channel_policy(http, server, channel).await?;
+			(ChannelId(channel as u64), policy, roles)
+		},
+		DropTarget::User(user) => match user.create_dm_channel(http).await {
+			Ok(dm) => (dm.id, NsfwPolicy::Allow, &[][..]),
+			Err(why) => {
+				// Members who closed their DMs miss these chapters instead of getting all of them at once later
+				error!("Error opening DMs with {}: {:?}", user, why);
+				return Some(newest);
+			}
+		},
+	};
+
+	let drops = group_chapters(chapters)
+		.into_iter()
+		.filter(|d| !(d.adult && policy.hides()))
+		.collect::<Vec<_>>();
+
+	let manga_ids = drops.iter()
+		.filter(|d| !policy.censors(d.adult))
+		.map(|d| d.manga_id.as_str())
+		.collect::<Vec<_>>();
+	let covers = mangadex.cover_thumbnails(&manga_ids).await;
+
+	for (i, batch) in drops.iter().enumerate() {
+		let mut roles = pinged.iter()
+			.filter(|r| r.manga_id.is_none() || r.manga_id.as_ref() == Some(&batch.manga_id))
+			.map(|r| r.role_id)
+			.collect::<Vec<_>>();
+		roles.sort_unstable();
+		roles.dedup();
+		if let Err(why) = post_drop(http, channel, batch, covers.get(&batch.manga_id), &roles).await {
+			error!("Error posting chapters of {} to {}: {:?}", batch.manga_id, name, why);
+			// Pick up again from the first chapter that wasn't posted, moving past it would skip it for good
+			let unposted = drops[i..].iter().flat_map(|d| &d.chapters).map(|c| c.published_at().timestamp()).min();
+			return unposted.map(|t| t - 1).filter(|t| *t > last);
+		}
+	}
+
+	Some(newest)
+}
+
+/// Groups chapters by series and language so a batch upload becomes one message, keeping the order they came out in.
+fn group_chapters(chapters: Vec<Chapter>) -> Vec<ChapterDrop> {
+	let mut drops = Vec::<ChapterDrop>::new();
+
+	for chapter in chapters {
+		let manga = match chapter.manga() {
+			Some(v) => v,
+			None => continue,
+		};
+		let language = chapter.attributes.translated_language.clone();
+
+		match drops.iter_mut().find(|d| d.manga_id == manga.id && d.language == language) {
+			Some(drop) => drop.chapters.push(chapter),
+			None => drops.push(ChapterDrop {
+				manga_id: manga.id.clone(),
+				title: manga.manga_title().unwrap_or_else(|| manga.id.clone()),
+				language,
+				adult: is_adult_rating(manga.content_rating()),
+				chapters: vec![chapter],
+			}),
+		}
+	}
 
-	diesel::delete(feeds.filter(channel_id.eq(channel))).execute(conn).unwrap();
-}
\ No newline at end of file
+	for drop in drops.iter_mut() {
+		drop.chapters.sort_by(|a, b| a.number().partial_cmp(&b.number()).unwrap_or(std::cmp::Ordering::Equal));
+	}
+	drops
+}
+
+async fn post_drop(http: &Http, channel: ChannelId, drop: &ChapterDrop, cover: Option<&String>, roles: &[i64]) -> serenity::Result<Message> {
+	let first = &drop.chapters[0];
+	let last = &drop.chapters[drop.chapters.len() - 1];
+	let chapter_name = |c: &Chapter| c.attributes.chapter.clone().unwrap_or_else(|| "Oneshot".to_string());
+
+	let mut groups = drop.chapters.iter().flat_map(|c| c.group_names()).collect::<Vec<_>>();
+	groups.sort();
+	groups.dedup();
+
+	channel.send_message(http, |m| {
+		m.embed(|e| {
+			if drop.chapters.len() > 1 {
+				e.title(format!("Ch. {} - {} - {}", chapter_name(first), chapter_name(last), drop.title));
+				e.description(format!("{} new chapters are out! [Click here to start reading]({})", drop.chapters.len(), first.url()));
+			} else {
+				e.title(format!("Ch. {} - {}", chapter_name(first), drop.title));
+				match &first.attributes.title {
+					Some(title) if !title.is_empty() => e.description(format!("{}\nNew chapter is out! [Click here to read]({})", title, first.url())),
+					_ => e.description(format!("New chapter is out! [Click here to read]({})", first.url())),
+				};
+			}
+			e.url(first.url());
+			if !groups.is_empty() {
+				e.field("Groups", groups.join(", "), true);
+			}
+			e.field("Language", &drop.language, true);
+			if let Some(cover) = cover {
+				e.thumbnail(cover);
+			}
+			e.set_author(mangadex_author());
+			e.color(MANGADEX_COLOR);
+			e.timestamp(last.published_at());
+			e
+		});
+		if !roles.is_empty() {
+			m.content(mention_roles(roles));
+		}
+		m
+	}).await
+}
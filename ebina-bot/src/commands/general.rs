@@ -15,6 +15,7 @@ use crate::diesel::prelude::*;
 use crate::establish_connection;
 use crate::models::*;
 use crate::schema::*;
+use crate::utils::nsfw::guild_nsfw_policy;
 
 //use tracing::{error, info};
 
@@ -323,6 +324,59 @@ pub async fn prefix(ctx: &Context, msg: &Message, args: Args) -> CommandResult {
     Ok(())
 }
 
+#[tracking("nsfw")]
+#[command]
+#[only_in("guilds")]
+#[required_permissions("MANAGE_GUILD")]
+#[usage = "[hide|censor|allow]"]
+#[example = "censor"]
+#[description = "Sets how adult anime, manga and visual novels are shown outside of NSFW channels: left out of results, shown without covers and descriptions, or shown as is."]
+pub async fn nsfw(ctx: &Context, msg: &Message, args: Args) -> CommandResult {
+    use crate::schema::nsfw_settings::dsl::*;
+
+    let guild = msg.guild_id.unwrap();
+
+    let new_policy = match args.rest().trim().to_lowercase().as_str() {
+        "hide" => NsfwPolicy::Hide,
+        "censor" => NsfwPolicy::Censor,
+        "allow" => NsfwPolicy::Allow,
+        "" => {
+            let current = match guild_nsfw_policy(guild) {
+                NsfwPolicy::Hide => "hidden",
+                NsfwPolicy::Censor => "shown without covers and descriptions",
+                NsfwPolicy::Allow => "shown",
+            };
+            msg.reply(ctx, format!("Adult entries are {} outside of NSFW channels", current)).await?;
+            return Ok(());
+        }
+        other => {
+            msg.reply(ctx, format!("`{}` isn't a policy, use hide, censor or allow", other)).await?;
+            return Ok(());
+        }
+    };
+
+    let connection = establish_connection();
+    let new_setting = NewNsfwSetting {
+        guild_id: &(guild.0 as i64),
+        policy: &new_policy,
+    };
+    diesel::insert_into(nsfw_settings)
+        .values(&new_setting)
+        .on_conflict(guild_id)
+        .do_update()
+        .set(policy.eq(new_policy))
+        .execute(&connection)?;
+
+    let reply = match new_policy {
+        NsfwPolicy::Hide => "Adult entries will be left out of results outside of NSFW channels",
+        NsfwPolicy::Censor => "Adult entries will be shown without covers and descriptions outside of NSFW channels",
+        NsfwPolicy::Allow => "Adult entries will be shown everywhere",
+    };
+    msg.reply(ctx, reply).await?;
+
+    Ok(())
+}
+
 pub fn create_server_settings<'a>(
     conn: &PgConnection,
     server: &'a i64,
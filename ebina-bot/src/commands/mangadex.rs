@@ -2,43 +2,79 @@ use serenity::framework::standard::{macros::command, Args, CommandResult};
 use serenity::model::prelude::*;
 use serenity::prelude::*;
 use serenity::utils::*;
-use serenity::builder::{CreateEmbedAuthor, CreateEmbed, CreateSelectMenu, CreateActionRow, CreateSelectMenuOptions, CreateSelectMenuOption};
+use serenity::builder::{CreateEmbedAuthor, CreateEmbed, CreateSelectMenu, CreateActionRow, CreateSelectMenuOption, CreateButton};
+use serenity::model::interactions::message_component::{ButtonStyle, MessageComponentInteraction};
+use serenity::model::interactions::application_command::{ApplicationCommand, ApplicationCommandInteraction, ApplicationCommandOptionType};
+use serenity::futures::stream::StreamExt;
 
-use mangadex_api::types::{Language, TagGroup};
+use mangadex_api::types::{ContentRating, Language, TagGroup};
 use mangadex_api::types::{RelationshipType, ReferenceExpansionResource};
-use mangadex_api::v5::schema::RelatedAttributes;
+use mangadex_api::v5::schema::{ApiObject, ChapterAttributes, MangaAttributes, RelatedAttributes};
 use mangadex_api::MangaDexClient;
 use mangadex_api::CDN_URL;
 
-use tracing::{info, error};
+use tracing::error;
 
 use regex::Regex;
 
 use uuid::Uuid;
 
+use num_format::{Locale, ToFormattedString};
+
 use url::Url;
 
-use std::collections::HashMap;
+use std::sync::Arc;
+use std::time::Duration;
 
+use crate::diesel::prelude::*;
+use crate::establish_connection;
+use crate::MangaDexTagsContainer;
+use crate::models::{NewFeed, NewGroupLink, NsfwPolicy};
+use crate::utils::mangadex;
+use crate::utils::flags::Flags;
+use crate::utils::follow::{delete_follow_roles, forget_orphaned_follow_roles};
+use crate::utils::language::{preferred_languages, resolve, resolve_title, save_languages, saved_languages, LanguageScope, FALLBACK_LANGUAGES};
+use crate::utils::nsfw::{channel_nsfw_policy, nsfw_policy};
 use crate::utils::options::Options;
+use crate::utils::paginator::Paginator;
 use ebina_macro::tracking;
 
-const MANGADEX_COLOR: serenity::utils::Colour = Colour::from_rgb(246, 131, 40);
+pub const MANGADEX_COLOR: serenity::utils::Colour = Colour::from_rgb(246, 131, 40);
+
+/// The author line every MangaDex embed starts with.
+pub fn mangadex_author() -> CreateEmbedAuthor {
+	CreateEmbedAuthor::default()
+		.icon_url("https://i.imgur.com/gFzVv6g.png")
+		.name("MangaDex")
+		.url("https://mangadex.org/")
+		.to_owned()
+}
+
+const LINK_CHANNEL_MENU_ID: &str = "md_link_channel_select";
+const LINK_GROUP_MENU_ID: &str = "md_link_group_select";
+const LINK_SERIES_MENU_ID: &str = "md_link_series_select";
+/// Value of the series menu option for following everything the group uploads.
+const LINK_ALL_SERIES: &str = "all";
+const READ_PREVIOUS_CHAPTER_ID: &str = "md_read_previous_chapter";
+const READ_PREVIOUS_PAGE_ID: &str = "md_read_previous_page";
+const READ_NEXT_PAGE_ID: &str = "md_read_next_page";
+const READ_NEXT_CHAPTER_ID: &str = "md_read_next_chapter";
 
 #[tracking("md_manga")]
 #[command]
 pub async fn manga(ctx: &Context, msg: &Message, args: Args) -> CommandResult {
     let client = MangaDexClient::default();
 
-	let mangadex_author = CreateEmbedAuthor::default().icon_url("https://i.imgur.com/gFzVv6g.png").name("MangaDex").url("https://mangadex.org/").clone();
-
     let title = args.rest();
 
+	let policy = nsfw_policy(ctx, msg).await;
+
     let manga_res = client
         .manga()
         .search()
         .title(title)
 		.limit(10u32)
+		.content_rating(content_ratings(policy))
         .build()?
         .send()
         .await.unwrap();
@@ -48,7 +84,7 @@ pub async fn manga(ctx: &Context, msg: &Message, args: Args) -> CommandResult {
 			m.embed(|e| {
 				e.description("No results!");
 				e.color(MANGADEX_COLOR);
-				e.set_author(mangadex_author);
+				e.set_author(mangadex_author());
 				e
 			});
 			m
@@ -56,24 +92,673 @@ pub async fn manga(ctx: &Context, msg: &Message, args: Args) -> CommandResult {
 		return Ok(())
 	}
 
-	let mut options: Vec<String> = Vec::new();
+	let preferred = preferred_languages(msg.guild_id, msg.author.id);
+	let options = manga_res.data.iter()
+		.map(|m| md_title(&m.attributes, &preferred))
+		.collect::<Vec<String>>();
+
+	let (index, message_id, channel_id) = match Options::new(ctx, msg)
+		.title("Enter the number corresponding the Manga you want info about!")
+		.options(options)
+		.colour(MANGADEX_COLOR)
+		.author(mangadex_author())
+		.edit()
+		.send()
+		.await {
+		Some(v) => v,
+		None => return Ok(()),
+	};
+    let manga = manga_res.data[index].clone();
+
+	send_md_embed(ctx, msg, manga.id, policy, true, Some(message_id), Some(channel_id)).await;
+    Ok(())
+}
+
+/// Name of the slash command form of `md search`.
+pub const MD_SEARCH_SLASH: &str = "mdsearch";
+const SEARCH_FLAGS: [&str; 7] = ["tag", "exclude", "demographic", "status", "rating", "lang", "sort"];
+const SEARCH_DEMOGRAPHICS: [&str; 5] = ["shounen", "shoujo", "josei", "seinen", "none"];
+const SEARCH_STATUSES: [&str; 4] = ["ongoing", "completed", "hiatus", "cancelled"];
+const SEARCH_RATINGS: [&str; 4] = ["safe", "suggestive", "erotica", "pornographic"];
+/// Sort names users pick from, with the order parameter and direction they stand for.
+const SEARCH_SORTS: [(&str, &str, &str); 7] = [
+	("relevance", "order[relevance]", "desc"),
+	("follows", "order[followedCount]", "desc"),
+	("rating", "order[rating]", "desc"),
+	("latest", "order[latestUploadedChapter]", "desc"),
+	("newest", "order[createdAt]", "desc"),
+	("title", "order[title]", "asc"),
+	("year", "order[year]", "desc"),
+];
+
+#[tracking("md_search")]
+#[command]
+#[min_args(1)]
+#[usage = "[--tag <tag>] [--exclude <tag>] [--demographic <demographic>] [--status <status>] [--rating <rating>] [--lang <code>] [--sort <order>] [title]"]
+#[example = "--tag Romance --tag \"Slice of Life\" --exclude Tragedy --status completed --sort follows"]
+#[description = "Searches MangaDex with filters, flags can be repeated or take comma separated values.\nDemographics: shounen, shoujo, josei, seinen, none\nStatuses: ongoing, completed, hiatus, cancelled\nRatings: safe, suggestive, erotica, pornographic\nSorts: relevance, follows, rating, latest, newest, title, year"]
+pub async fn search(ctx: &Context, msg: &Message, args: Args) -> CommandResult {
+	let flags = match Flags::parse(args.rest(), &SEARCH_FLAGS) {
+		Ok(v) => v,
+		Err(why) => {
+			msg.reply(ctx, format!("{}, the options are {}", why, SEARCH_FLAGS.iter().map(|f| format!("`--{}`", f)).collect::<Vec<_>>().join(", "))).await?;
+			return Ok(())
+		}
+	};
+
+	let policy = nsfw_policy(ctx, msg).await;
+
+	let search = match manga_search(ctx, &flags, policy).await {
+		Ok(v) => v,
+		Err(why) => {
+			msg.reply(ctx, why).await?;
+			return Ok(())
+		}
+	};
+
+	let results = mangadex::ApiClient::default().search_manga(&search, 10).await?;
 
-	for m in &manga_res.data {
-		options.push(m.attributes.title.values().next().unwrap().to_string())
+	if results.is_empty() {
+		msg.channel_id.send_message(&ctx.http, |m| {
+			m.embed(|e| {
+				e.description("No results!");
+				e.color(MANGADEX_COLOR);
+				e.set_author(mangadex_author());
+				e
+			});
+			m
+		}).await?;
+		return Ok(())
 	}
 
-	let index = Options::new(ctx, msg)
+	let preferred = preferred_languages(msg.guild_id, msg.author.id);
+	let options = results.iter()
+		.map(|m| m.localized_title(&preferred).unwrap_or_else(|| "Untitled".to_string()))
+		.collect::<Vec<_>>();
+
+	let (index, message_id, channel_id) = match Options::new(ctx, msg)
 		.title("Enter the number corresponding the Manga you want info about!")
 		.options(options)
 		.colour(MANGADEX_COLOR)
-		.author(mangadex_author)
+		.author(mangadex_author())
 		.edit()
 		.send()
+		.await {
+		Some(v) => v,
+		None => return Ok(()),
+	};
+
+	let id = Uuid::parse_str(&results[index].id)?;
+	send_md_embed(ctx, msg, id, policy, true, Some(message_id), Some(channel_id)).await;
+	Ok(())
+}
+
+/// MangaDex's tags, fetched the first time they're needed.
+async fn md_tags(ctx: &Context) -> reqwest::Result<Vec<mangadex::Tag>> {
+	{
+		let data = ctx.data.read().await;
+		if let Some(tags) = data.get::<MangaDexTagsContainer>() {
+			if !tags.is_empty() {
+				return Ok(tags.clone());
+			}
+		}
+	}
+
+	let tags = mangadex::ApiClient::default().get_tags().await?;
+	ctx.data.write().await.insert::<MangaDexTagsContainer>(tags.clone());
+	Ok(tags)
+}
+
+/// Turns search flags into a search, the error is meant for the user.
+async fn manga_search(ctx: &Context, flags: &Flags, policy: NsfwPolicy) -> Result<mangadex::MangaSearch, String> {
+	let mut search = mangadex::MangaSearch {
+		title: flags.rest(),
+		..Default::default()
+	};
+
+	let (included, excluded) = (flags.all("tag"), flags.all("exclude"));
+	if !included.is_empty() || !excluded.is_empty() {
+		let tags = md_tags(ctx).await.map_err(|why| {
+			error!("Error getting MangaDex tags: {:?}", why);
+			"Couldn't get the tags from MangaDex, try again later".to_string()
+		})?;
+		search.included_tags = tag_ids(&tags, &included)?;
+		search.excluded_tags = tag_ids(&tags, &excluded)?;
+	}
+
+	search.demographics = one_of("demographic", flags.all("demographic"), &SEARCH_DEMOGRAPHICS)?;
+	search.statuses = one_of("status", flags.all("status"), &SEARCH_STATUSES)?;
+	search.content_ratings = one_of("rating", flags.all("rating"), &SEARCH_RATINGS)?;
+	if policy.hides() {
+		if search.content_ratings.iter().any(|r| mangadex::is_adult_rating(Some(r.as_str()))) {
+			return Err("Erotica and pornographic results can't be searched for here".to_string());
+		}
+		if search.content_ratings.is_empty() {
+			search.content_ratings = vec!["safe".to_string(), "suggestive".to_string()];
+		}
+	}
+
+	for code in flags.all("lang") {
+		let code = code.to_lowercase();
+		if parse_language(&code).is_none() {
+			return Err(format!("`{}` isn't a MangaDex language code, they look like `ja` or `zh-hk`", code));
+		}
+		search.original_languages.push(code);
+	}
+
+	if let Some(sort) = flags.get("sort") {
+		let sort = sort.to_lowercase();
+		match SEARCH_SORTS.iter().find(|(name, _, _)| *name == sort) {
+			Some((_, field, direction)) => search.order = Some((*field, *direction)),
+			None => return Err(format!("`{}` isn't a way to sort, use one of {}", sort, SEARCH_SORTS.iter().map(|(name, _, _)| format!("`{}`", name)).collect::<Vec<_>>().join(", "))),
+		}
+	}
+
+	Ok(search)
+}
+
+/// Ids of the tags with the given names, suggesting similar tags for names that don't exist.
+fn tag_ids(tags: &[mangadex::Tag], names: &[&str]) -> Result<Vec<String>, String> {
+	names.iter().map(|name| {
+		if let Some(tag) = tags.iter().find(|t| t.name().eq_ignore_ascii_case(name)) {
+			return Ok(tag.id.clone());
+		}
+
+		let lower = name.to_lowercase();
+		let similar = tags.iter()
+			.filter(|t| t.name().to_lowercase().contains(&lower))
+			.map(|t| format!("`{}`", t.name()))
+			.take(5)
+			.collect::<Vec<_>>();
+		if similar.is_empty() {
+			Err(format!("`{}` isn't a MangaDex tag", name))
+		} else {
+			Err(format!("`{}` isn't a MangaDex tag, did you mean {}?", name, similar.join(", ")))
+		}
+	}).collect()
+}
+
+/// Checks that every value of a flag is one of `allowed`.
+fn one_of(flag: &str, values: Vec<&str>, allowed: &[&str]) -> Result<Vec<String>, String> {
+	values.into_iter().map(|value| {
+		let value = value.to_lowercase();
+		if allowed.contains(&value.as_str()) {
+			Ok(value)
+		} else {
+			Err(format!("`{}` isn't a {}, use one of {}", value, flag, allowed.iter().map(|a| format!("`{}`", a)).collect::<Vec<_>>().join(", ")))
+		}
+	}).collect()
+}
+
+/// Registers the slash command form of `md search`.
+pub async fn register_search_command(ctx: &Context) {
+	let registered = ApplicationCommand::create_global_application_command(&ctx.http, |c| {
+		c.name(MD_SEARCH_SLASH).description("Search MangaDex with filters");
+		c.create_option(|o| o.name("title").description("Words in the title").kind(ApplicationCommandOptionType::String));
+		c.create_option(|o| o.name("tag").description("Tags every result has, comma separated").kind(ApplicationCommandOptionType::String));
+		c.create_option(|o| o.name("exclude").description("Tags no result has, comma separated").kind(ApplicationCommandOptionType::String));
+		c.create_option(|o| {
+			o.name("demographic").description("Publication demographic").kind(ApplicationCommandOptionType::String);
+			for d in SEARCH_DEMOGRAPHICS {
+				o.add_string_choice(d, d);
+			}
+			o
+		});
+		c.create_option(|o| {
+			o.name("status").description("Publication status").kind(ApplicationCommandOptionType::String);
+			for s in SEARCH_STATUSES {
+				o.add_string_choice(s, s);
+			}
+			o
+		});
+		c.create_option(|o| {
+			o.name("rating").description("Content rating").kind(ApplicationCommandOptionType::String);
+			for r in SEARCH_RATINGS {
+				o.add_string_choice(r, r);
+			}
+			o
+		});
+		c.create_option(|o| o.name("lang").description("Original language code, like ja or ko").kind(ApplicationCommandOptionType::String));
+		c.create_option(|o| {
+			o.name("sort").description("How to order the results").kind(ApplicationCommandOptionType::String);
+			for (name, _, _) in SEARCH_SORTS {
+				o.add_string_choice(name, name);
+			}
+			o
+		})
+	}).await;
+
+	if let Err(why) = registered {
+		error!("Error registering the {} command: {:?}", MD_SEARCH_SLASH, why);
+	}
+}
+
+/// Answers the slash command form of `md search` with a list of results.
+pub async fn search_interaction(ctx: &Context, command: &ApplicationCommandInteraction) {
+	// Searches can take longer than Discord waits for a response
+	if let Err(why) = command.create_interaction_response(&ctx.http, |r| {
+		r.kind(InteractionResponseType::DeferredChannelMessageWithSource)
+	}).await {
+		error!("Error deferring {} response: {:?}", MD_SEARCH_SLASH, why);
+		return;
+	}
+
+	let mut title = String::new();
+	let mut pairs = Vec::new();
+	for option in &command.data.options {
+		let value = match option.value.as_ref().and_then(|v| v.as_str()) {
+			Some(v) => v.to_string(),
+			None => continue,
+		};
+		match option.name.as_str() {
+			"title" => title = value,
+			name => pairs.push((name.to_string(), value)),
+		}
+	}
+	let flags = Flags::from_pairs(pairs).with_rest(title);
+
+	let policy = channel_nsfw_policy(ctx, command.guild_id, command.channel_id).await;
+
+	let embed = match manga_search(ctx, &flags, policy).await {
+		Ok(search) => match mangadex::ApiClient::default().search_manga(&search, 10).await {
+			Ok(results) => search_results_embed(&results, &preferred_languages(command.guild_id, command.user.id)),
+			Err(why) => {
+				error!("Error searching MangaDex: {:?}", why);
+				search_error_embed("Couldn't reach MangaDex, try again later")
+			}
+		},
+		Err(why) => search_error_embed(why),
+	};
+
+	if let Err(why) = command.edit_original_interaction_response(&ctx.http, |r| r.add_embed(embed)).await {
+		error!("Error sending {} response: {:?}", MD_SEARCH_SLASH, why);
+	}
+}
+
+fn search_results_embed(results: &[mangadex::Manga], preferred: &[String]) -> CreateEmbed {
+	let mut embed = CreateEmbed::default();
+	embed.set_author(mangadex_author());
+	embed.color(MANGADEX_COLOR);
+
+	if results.is_empty() {
+		embed.description("No results!");
+		return embed;
+	}
+
+	embed.title("Search results");
+	embed.description(results.iter().enumerate().map(|(i, m)| {
+		let status = m.attributes.get("status").and_then(|s| s.as_str()).unwrap_or("unknown");
+		format!("**{}.** [{}](https://mangadex.org/title/{}) · {}", i + 1, m.localized_title(preferred).unwrap_or_else(|| "Untitled".to_string()), m.id, status)
+	}).collect::<Vec<_>>().join("\n"));
+	embed
+}
+
+fn search_error_embed<S: ToString>(why: S) -> CreateEmbed {
+	let mut embed = CreateEmbed::default();
+	embed.description(why.to_string());
+	embed.color(MANGADEX_COLOR);
+	embed
+}
+
+#[tracking("md_chapters")]
+#[command]
+#[min_args(1)]
+#[usage = "[lang:<code>] [group:<name>] <title>"]
+#[example = "lang:en group:mangaplus Chainsaw Man"]
+#[description = "Lists the chapters of a manga by volume, optionally only in a language or by a scanlation group"]
+pub async fn chapters(ctx: &Context, msg: &Message, args: Args) -> CommandResult {
+	let client = MangaDexClient::default();
+
+	let mut language = None;
+	let mut group = None;
+	let mut title = Vec::new();
+	for arg in args.raw() {
+		if let Some(code) = arg.strip_prefix("lang:") {
+			match parse_language(code) {
+				Some(v) => language = Some(v),
+				None => {
+					msg.reply(ctx, format!("`{}` isn't a MangaDex language code, they look like `en` or `pt-br`", code)).await?;
+					return Ok(())
+				}
+			}
+		} else if let Some(name) = arg.strip_prefix("group:") {
+			group = Some(name.to_lowercase());
+		} else {
+			title.push(arg);
+		}
+	}
+
+	let policy = nsfw_policy(ctx, msg).await;
+
+	let manga_res = client
+		.manga()
+		.search()
+		.title(title.join(" "))
+		.limit(10u32)
+		.content_rating(content_ratings(policy))
+		.build()?
+		.send()
+		.await?;
+
+	if manga_res.data.is_empty() {
+		msg.channel_id.send_message(&ctx.http, |m| {
+			m.embed(|e| {
+				e.description("No results!");
+				e.color(MANGADEX_COLOR);
+				e.set_author(mangadex_author());
+				e
+			});
+			m
+		}).await?;
+		return Ok(())
+	}
+
+	let preferred = preferred_languages(msg.guild_id, msg.author.id);
+	let options = manga_res.data.iter()
+		.map(|m| md_title(&m.attributes, &preferred))
+		.collect::<Vec<String>>();
+
+	let index = Options::new(ctx, msg)
+		.title("Enter the number corresponding the Manga you want the chapters of!")
+		.options(options)
+		.colour(MANGADEX_COLOR)
+		.author(mangadex_author())
+		.send()
 		.await;
-    let manga = manga_res.data[index.unwrap().0].clone();
+	let manga = match index {
+		Some(v) => &manga_res.data[v.0],
+		None => return Ok(()),
+	};
+	let manga_title = md_title(&manga.attributes, &preferred);
 
-	send_md_embed(ctx, msg, manga.id, true, Some(index.unwrap().1), Some(index.unwrap().2)).await;
-    Ok(())
+	let code = language.as_ref()
+		.and_then(|l| serde_json::to_value(l).ok())
+		.and_then(|v| v.as_str().map(str::to_string));
+	let languages = code.iter().map(String::as_str).collect::<Vec<_>>();
+	let chapter_list = match mangadex::ApiClient::default().manga_chapters(&manga.id.to_string(), &languages).await {
+		Ok(v) => v,
+		Err(why) => {
+			error!("Error getting chapters of {}: {:?}", manga.id, why);
+			msg.reply(ctx, "Couldn't get the chapters from MangaDex, try again later").await?;
+			return Ok(())
+		}
+	};
+	let chapter_list = chapter_list.into_iter()
+		.filter(|c| match &group {
+			Some(name) => c.group_names().iter().any(|g| g.to_lowercase().contains(name)),
+			None => true,
+		})
+		.collect::<Vec<_>>();
+
+	let mut lines = Vec::new();
+	let mut volume = None;
+	for chapter in &chapter_list {
+		if lines.is_empty() || chapter.attributes.volume != volume {
+			volume = chapter.attributes.volume.clone();
+			lines.push(match &volume {
+				Some(v) => format!("**Volume {}**", v),
+				None => "**No Volume**".to_string(),
+			});
+		}
+		let mut line = format!("[Ch. {}]({})", chapter.attributes.chapter.as_deref().unwrap_or("Oneshot"), chapter.url());
+		if let Some(chapter_title) = chapter.attributes.title.as_ref().filter(|t| !t.is_empty()) {
+			line.push_str(&format!(" - {}", chapter_title));
+		}
+		// The language is obvious when filtering by it
+		if language.is_none() {
+			line.push_str(&format!(" `{}`", chapter.attributes.translated_language));
+		}
+		let groups = chapter.group_names();
+		if !groups.is_empty() {
+			line.push_str(&format!(" · {}", groups.join(", ")));
+		}
+		lines.push(line);
+	}
+
+	let mut filters = Vec::new();
+	if let Some(language) = &language {
+		filters.push(format!("in {:?}", language));
+	}
+	if let Some(name) = &group {
+		filters.push(format!("by groups matching \"{}\"", name));
+	}
+
+	let pages = if lines.is_empty() {
+		vec![chapters_page(&manga_title, &manga.id.to_string(), &filters, "No chapters found!".to_string(), mangadex_author())]
+	} else {
+		lines.chunks(15)
+			.map(|chunk| chapters_page(&manga_title, &manga.id.to_string(), &filters, chunk.join("\n"), mangadex_author()))
+			.collect()
+	};
+
+	Paginator::new(ctx, msg)
+		.pages(pages)
+		.send()
+		.await?;
+	Ok(())
+}
+
+fn chapters_page(title: &str, id: &str, filters: &[String], description: String, author: CreateEmbedAuthor) -> CreateEmbed {
+	let mut embed = CreateEmbed::default();
+	embed.title(format!("Chapters of {}", title));
+	embed.url(format!("https://mangadex.org/title/{}", id));
+	if filters.is_empty() {
+		embed.description(description);
+	} else {
+		embed.description(format!("Only chapters {}\n\n{}", filters.join(" "), description));
+	}
+	embed.color(MANGADEX_COLOR);
+	embed.set_author(author);
+	embed
+}
+
+#[tracking("md_covers")]
+#[command]
+#[min_args(1)]
+#[usage = "<title>"]
+#[example = "Yotsuba&!"]
+#[description = "Pages through every volume cover of a manga"]
+pub async fn covers(ctx: &Context, msg: &Message, args: Args) -> CommandResult {
+	let client = MangaDexClient::default();
+
+	let policy = nsfw_policy(ctx, msg).await;
+
+	let manga_res = client
+		.manga()
+		.search()
+		.title(args.rest())
+		.limit(10u32)
+		.content_rating(content_ratings(policy))
+		.build()?
+		.send()
+		.await?;
+
+	if manga_res.data.is_empty() {
+		msg.channel_id.send_message(&ctx.http, |m| {
+			m.embed(|e| {
+				e.description("No results!");
+				e.color(MANGADEX_COLOR);
+				e.set_author(mangadex_author());
+				e
+			});
+			m
+		}).await?;
+		return Ok(())
+	}
+
+	let preferred = preferred_languages(msg.guild_id, msg.author.id);
+	let options = manga_res.data.iter()
+		.map(|m| md_title(&m.attributes, &preferred))
+		.collect::<Vec<String>>();
+
+	let index = Options::new(ctx, msg)
+		.title("Enter the number corresponding the Manga you want the covers of!")
+		.options(options)
+		.colour(MANGADEX_COLOR)
+		.author(mangadex_author())
+		.send()
+		.await;
+	let manga = match index {
+		Some(v) => &manga_res.data[v.0],
+		None => return Ok(()),
+	};
+	let manga_title = md_title(&manga.attributes, &preferred);
+
+	// A gallery is nothing but covers, so there's nothing left to show when they're censored
+	if policy.censors(is_adult(&manga.attributes)) {
+		msg.reply(ctx, "Covers of adult titles can only be shown in NSFW channels").await?;
+		return Ok(())
+	}
+
+	let manga_id = manga.id.to_string();
+	let cover_list = match mangadex::ApiClient::default().manga_covers(&manga_id).await {
+		Ok(v) => v,
+		Err(why) => {
+			error!("Error getting covers of {}: {:?}", manga_id, why);
+			msg.reply(ctx, "Couldn't get the covers from MangaDex, try again later").await?;
+			return Ok(())
+		}
+	};
+	if cover_list.is_empty() {
+		msg.reply(ctx, format!("{} has no covers on MangaDex", manga_title)).await?;
+		return Ok(())
+	}
+
+	let pages = cover_list.iter()
+		.map(|cover| cover_page(&manga_title, &manga_id, cover, mangadex_author()))
+		.collect();
+
+	Paginator::new(ctx, msg)
+		.pages(pages)
+		.send()
+		.await?;
+	Ok(())
+}
+
+fn cover_page(title: &str, manga_id: &str, cover: &mangadex::Cover, author: CreateEmbedAuthor) -> CreateEmbed {
+	let mut embed = CreateEmbed::default();
+	embed.title(format!("Covers of {}", title));
+	embed.url(format!("https://mangadex.org/title/{}?tab=art", manga_id));
+
+	let mut description = match cover.attributes.volume.as_deref().filter(|v| !v.is_empty()) {
+		Some(v) => format!("**Volume {}**", v),
+		None => "**No Volume**".to_string(),
+	};
+	if let Some(locale) = &cover.attributes.locale {
+		description.push_str(&format!(" `{}`", locale));
+	}
+	if let Some(text) = cover.attributes.description.as_deref().filter(|d| !d.is_empty()) {
+		description.push_str(&format!("\n{}", text));
+	}
+	embed.description(description);
+
+	embed.image(mangadex::cover_url(manga_id, &cover.attributes.file_name));
+	embed.color(MANGADEX_COLOR);
+	embed.set_author(author);
+	embed
+}
+
+#[tracking("md_language")]
+#[command]
+#[usage = "[codes|clear]"]
+#[example = "ja-ro en"]
+#[description = "Sets the languages you'd like MangaDex titles and descriptions in, most preferred first. Languages set for the server are tried before yours."]
+pub async fn language(ctx: &Context, msg: &Message, args: Args) -> CommandResult {
+	language_preference(ctx, msg, args, LanguageScope::User(msg.author.id)).await
+}
+
+#[tracking("md_serverlanguage")]
+#[command("serverlanguage")]
+#[only_in("guilds")]
+#[required_permissions("MANAGE_GUILD")]
+#[usage = "[codes|clear]"]
+#[example = "pt-br en"]
+#[description = "Sets the languages MangaDex titles and descriptions are shown in on this server, most preferred first. They're tried before the languages members set for themselves."]
+pub async fn server_language(ctx: &Context, msg: &Message, args: Args) -> CommandResult {
+	language_preference(ctx, msg, args, LanguageScope::Guild(msg.guild_id.unwrap())).await
+}
+
+/// Shows, clears or replaces a language preference.
+async fn language_preference(ctx: &Context, msg: &Message, args: Args, scope: LanguageScope) -> CommandResult {
+	let connection = establish_connection();
+	let whom = match scope {
+		LanguageScope::Guild(_) => "this server",
+		LanguageScope::User(_) => "you",
+	};
+
+	let input = args.rest().trim().to_lowercase();
+	if input.is_empty() {
+		let saved = saved_languages(&connection, scope)?;
+		if saved.is_empty() {
+			msg.reply(ctx, format!("No languages are set for {}, titles are shown in {} when there's one", whom, FALLBACK_LANGUAGES.join(", "))).await?;
+		} else {
+			msg.reply(ctx, format!("Languages set for {}: {}", whom, saved.join(", "))).await?;
+		}
+		return Ok(())
+	}
+
+	if input == "clear" {
+		save_languages(&connection, scope, &[])?;
+		msg.reply(ctx, format!("Cleared the languages set for {}", whom)).await?;
+		return Ok(())
+	}
+
+	let mut codes: Vec<String> = Vec::new();
+	for code in input.split(|c: char| c == ',' || c.is_whitespace()).filter(|c| !c.is_empty()) {
+		if parse_language(code).is_none() {
+			msg.reply(ctx, format!("`{}` isn't a MangaDex language code, they look like `en`, `ja-ro` or `pt-br`", code)).await?;
+			return Ok(())
+		}
+		let code = code.to_lowercase();
+		if !codes.contains(&code) {
+			codes.push(code);
+		}
+	}
+
+	save_languages(&connection, scope, &codes)?;
+	msg.reply(ctx, format!("MangaDex titles and descriptions will be shown in {} when there's one", codes.join(", "))).await?;
+	Ok(())
+}
+
+/// The content ratings to search for, MangaDex leaves out pornographic titles unless asked.
+fn content_ratings(policy: NsfwPolicy) -> Vec<ContentRating> {
+	let mut ratings = vec![ContentRating::Safe, ContentRating::Suggestive];
+	if !policy.hides() {
+		ratings.push(ContentRating::Erotica);
+		ratings.push(ContentRating::Pornographic);
+	}
+	ratings
+}
+
+/// A language code MangaDex knows, like `en` or `pt-br`, in any case.
+fn parse_language(code: &str) -> Option<Language> {
+	serde_json::from_value(serde_json::Value::String(code.to_lowercase())).ok()
+}
+
+/// Whether a manga is only shown in NSFW channels.
+fn is_adult(attributes: &MangaAttributes) -> bool {
+	mangadex::is_adult_rating(attributes.content_rating.as_ref().and_then(api_name).as_deref())
+}
+
+/// The title of a manga in the first preferred language that has one, alt titles included.
+fn md_title(attributes: &MangaAttributes, preferred: &[String]) -> String {
+	let alt_titles = attributes.alt_titles.iter().map(|titles| localized(titles)).collect();
+	let original = api_name(&attributes.original_language);
+	resolve_title(localized(&attributes.title), alt_titles, preferred, original.as_deref()).unwrap_or_default().to_string()
+}
+
+/// Localized texts keyed by the language codes MangaDex uses.
+fn localized<'a, M>(texts: &'a M) -> Vec<(String, &'a str)> where
+&'a M: IntoIterator<Item = (&'a Language, &'a String)> {
+	texts.into_iter()
+		.filter_map(|(language, text)| Some((api_name(language)?, text.as_str())))
+		.collect()
+}
+
+/// The name the API uses for a value, like `ja-ro` for a language or `erotica` for a content rating.
+fn api_name<T>(value: &T) -> Option<String> where
+T: serde::Serialize {
+	serde_json::to_value(value).ok()?.as_str().map(str::to_string)
 }
 
 pub async fn manage_md_url(ctx: &Context, msg: &Message, url: Url) {
@@ -91,10 +776,11 @@ pub async fn manage_md_url(ctx: &Context, msg: &Message, url: Url) {
 		None => return,
 	};
 
-	send_md_embed(ctx, msg, id, false, None, None).await;
+	let policy = nsfw_policy(ctx, msg).await;
+	send_md_embed(ctx, msg, id, policy, false, None, None).await;
 }
 
-async fn send_md_embed(ctx: &Context, msg: &Message, id: Uuid, edit: bool, message_id: Option<MessageId>, channel_id: Option<ChannelId>) {
+async fn send_md_embed(ctx: &Context, msg: &Message, id: Uuid, policy: NsfwPolicy, edit: bool, message_id: Option<MessageId>, channel_id: Option<ChannelId>) {
 	let client = MangaDexClient::default();
 
 	let manga_res = client
@@ -110,34 +796,29 @@ async fn send_md_embed(ctx: &Context, msg: &Message, id: Uuid, edit: bool, messa
 
 	let manga = manga_res.data.clone();
 
-	info!("{:#?}", manga);
-
-	let mangadex_author = CreateEmbedAuthor::default().icon_url("https://i.imgur.com/gFzVv6g.png").name("MangaDex").url("https://mangadex.org/").clone();
+	let adult = is_adult(&manga.attributes);
+	// Links to adult titles get no embed at all when they're hidden
+	if adult && policy.hides() && !edit {
+		return;
+	}
+	let censored = policy.censors(adult);
 
 
-	let manga_title = manga
-        .attributes
-        .title
-		.values()
-		.next()
-        .unwrap();
-    let manga_description = manga
-        .attributes
-        .description
-        .get_key_value(&Language::English);
-    let manga_cover_id = manga
-        .relationships
-        .iter()
-        .find(|related| related.type_ == RelationshipType::CoverArt)
-        .expect("no cover art found for manga")
-        .id;
-    let manga_cover = client
-        .cover()
-        .get()
-        .cover_id(&manga_cover_id)
-        .build().unwrap()
-        .send()
-        .await.unwrap();
+	let preferred = preferred_languages(msg.guild_id, msg.author.id);
+	let manga_title = md_title(&manga.attributes, &preferred);
+	let original = api_name(&manga.attributes.original_language);
+	let manga_description = resolve(&localized(&manga.attributes.description), &preferred, original.as_deref());
+	// Titles without a cover just go without a thumbnail
+	let mut manga_cover = None;
+	if let Some(cover) = manga.relationships.iter().find(|related| related.type_ == RelationshipType::CoverArt) {
+		match client.cover().get().cover_id(&cover.id).build() {
+			Ok(request) => match request.send().await {
+				Ok(v) => manga_cover = Some(v.data.attributes.file_name),
+				Err(why) => error!("Error getting cover {} of {}: {:?}", cover.id, manga.id, why),
+			},
+			Err(why) => error!("Error building cover request for {}: {:?}", manga.id, why),
+		}
+	}
     let manga_authors = manga
         .relationships
         .iter()
@@ -157,19 +838,21 @@ async fn send_md_embed(ctx: &Context, msg: &Message, id: Uuid, edit: bool, messa
 	let manga_format = manga.attributes.tags.iter().filter(|tag| tag.attributes.group == TagGroup::Format).collect::<Vec<_>>();
 
 	let mut embed = CreateEmbed::default();
-	info!("{:#?}", manga_title);
 	embed.title(manga_title);
 	embed.color(Colour::from_rgb(246, 131, 40));
 	embed.url(format!("https://mangadex.org/title/{}", manga.id));
-	embed.thumbnail(&format!(
-		"{}/covers/{}/{}",
-		CDN_URL, manga.id, manga_cover.data.attributes.file_name
-	));
+	if censored {
+		embed.description("The cover and description of adult titles are hidden outside of NSFW channels.");
+	} else {
+		if let Some(file_name) = &manga_cover {
+			embed.thumbnail(&format!("{}/covers/{}/{}", CDN_URL, manga.id, file_name));
+		}
 
-	if let Some(desc) = manga_description {
-		embed.description(fix_description(desc.1));
+		if let Some(desc) = manga_description {
+			embed.description(fix_description(desc));
+		}
 	}
-	embed.set_author(mangadex_author);
+	embed.set_author(mangadex_author());
 
 	if !manga_authors.is_empty() {
 		embed.field(
@@ -257,22 +940,33 @@ async fn send_md_embed(ctx: &Context, msg: &Message, id: Uuid, edit: bool, messa
 		);
 	}
 	embed.field("Publication Status", manga.attributes.status, true);
-
-	/*if manga.artists().len() > 0 {
-		e.field("Artist", manga.artists().join(", "), true);
+	match mangadex::ApiClient::default().latest_chapter(&manga.id.to_string()).await {
+		Ok(Some(latest)) => {
+			let number = latest.attributes.chapter.clone().unwrap_or_else(|| "Oneshot".to_string());
+			embed.field("Latest Chapter", format!("[Ch. {}]({})", number, latest.url()), true);
+		},
+		Ok(None) => {},
+		Err(why) => error!("Error getting the latest chapter of {}: {:?}", manga.id, why),
 	}
-
-	if contents.len() > 0 {
-		e.field("Content", contents.join(", "), true);
+	if let Some(rating) = manga.attributes.content_rating {
+		embed.field("Content Rating", format!("{:?}", rating), true);
 	}
-	if formats.len() > 0 {
-		e.field("Format", formats.join(", "), true);
+
+	match mangadex::ApiClient::default().manga_statistics(&manga.id.to_string()).await {
+		Ok(Some(stats)) => {
+			let mut lines = Vec::new();
+			if let Some(bayesian) = stats.rating.bayesian {
+				lines.push(format!("Bayesian: {:.2}", bayesian));
+			}
+			if let Some(average) = stats.rating.average {
+				lines.push(format!("Mean: {:.2} ({} votes)", average, stats.rating.votes().to_formatted_string(&Locale::en)));
+			}
+			lines.push(format!("Follows: {}", stats.follows.to_formatted_string(&Locale::en)));
+			embed.field("Rating", lines.join("\n"), true);
+		},
+		Ok(None) => {},
+		Err(why) => error!("Error getting the statistics of {}: {:?}", manga.id, why),
 	}
-	e.field("Rating", format!("Bayesian rating: {}\nMean rating: {}\nUsers: {}", manga.rating().bayesian(), manga.rating().mean(), manga.rating().users()), true);
-	e.field("Pub. Status", manga.publication().status(), true);
-	if genres.len() > 0 {
-		e.field("Genre", genres.join(", "), true);
-	}*/
 
 	// Check if edit is true and if so edit the message instead
 	if edit {
@@ -291,16 +985,300 @@ async fn send_md_embed(ctx: &Context, msg: &Message, id: Uuid, edit: bool, messa
 
 }
 
+/// A chapter loaded into the reader, with its neighbours in the same language.
+struct ReaderChapter {
+	chapter: ApiObject<ChapterAttributes>,
+	title: String,
+	pages: Vec<String>,
+	previous: Option<Uuid>,
+	next: Option<Uuid>,
+}
+
+#[tracking("md_read")]
+#[command]
+#[min_args(1)]
+#[usage = "<chapter link or id> [saver]"]
+#[example = "https://mangadex.org/chapter/5e8bc984-5f3f-4fb9-b6a3-9ee3a5f7e22e saver"]
+#[description = "Reads a chapter page by page, `saver` loads the smaller data-saver images"]
+pub async fn read(ctx: &Context, msg: &Message, mut args: Args) -> CommandResult {
+	let client = MangaDexClient::default();
+
+	let chapter_id = match parse_chapter_id(&args.single::<String>()?) {
+		Some(v) => v,
+		None => {
+			msg.reply(ctx, "That's not a MangaDex chapter").await?;
+			return Ok(())
+		}
+	};
+	let data_saver = args.single::<String>()
+		.map(|a| matches!(a.to_lowercase().as_str(), "saver" | "datasaver" | "data-saver"))
+		.unwrap_or(false);
+	let policy = nsfw_policy(ctx, msg).await;
+	let preferred = preferred_languages(msg.guild_id, msg.author.id);
+
+	let mut reader = match load_chapter(&client, &chapter_id, data_saver, policy, &preferred).await {
+		Ok(v) => v,
+		Err(why) => {
+			msg.reply(ctx, why.to_string()).await?;
+			return Ok(())
+		}
+	};
+
+	let mut page = 0usize;
+	let mut message = msg.channel_id.send_message(&ctx.http, |m| {
+		m.set_embed(reader_embed(&reader, page));
+		m.components(|c| c.add_action_row(reader_buttons(&reader, page)))
+	}).await?;
+
+	let mut interactions = message
+		.await_component_interactions(ctx)
+		.author_id(msg.author.id)
+		.timeout(Duration::from_secs(300))
+		.build();
+
+	while let Some(interaction) = interactions.next().await {
+		let chapter = match interaction.data.custom_id.as_str() {
+			READ_PREVIOUS_PAGE_ID => {
+				page = page.saturating_sub(1);
+				None
+			},
+			READ_NEXT_PAGE_ID => {
+				page = (page + 1).min(reader.pages.len().saturating_sub(1));
+				None
+			},
+			READ_PREVIOUS_CHAPTER_ID => reader.previous,
+			READ_NEXT_CHAPTER_ID => reader.next,
+			_ => continue,
+		};
+
+		if let Some(chapter) = chapter {
+			match load_chapter(&client, &chapter, data_saver, policy, &preferred).await {
+				Ok(v) => {
+					reader = v;
+					page = 0;
+				},
+				Err(why) => {
+					interaction.create_interaction_response(&ctx.http, |r| {
+						r.kind(InteractionResponseType::ChannelMessageWithSource)
+							.interaction_response_data(|d| d.content(why.to_string()).ephemeral(true))
+					}).await?;
+					continue;
+				}
+			}
+		}
+
+		interaction.create_interaction_response(&ctx.http, |r| {
+			r.kind(InteractionResponseType::UpdateMessage)
+				.interaction_response_data(|d| {
+					d.set_embed(reader_embed(&reader, page));
+					d.components(|c| c.add_action_row(reader_buttons(&reader, page)))
+				})
+		}).await?;
+	}
+
+	// Remove the buttons once nobody can use them anymore
+	message.edit(&ctx.http, |m| m.components(|c| c)).await?;
+	Ok(())
+}
+
+/// The chapter id of a chapter link or a bare UUID.
+fn parse_chapter_id(arg: &str) -> Option<Uuid> {
+	let arg = arg.trim_matches(|c| c == '<' || c == '>');
+	match Url::parse(arg) {
+		Ok(url) => {
+			let mut path_segments = url.path_segments()?;
+			if path_segments.next()? != "chapter" {
+				return None;
+			}
+			Uuid::parse_str(path_segments.next()?).ok()
+		},
+		Err(_) => Uuid::parse_str(arg).ok(),
+	}
+}
+
+/// Looks up a chapter and where its pages are on the MangaDex@Home network.
+async fn load_chapter(client: &MangaDexClient, id: &Uuid, data_saver: bool, policy: NsfwPolicy, preferred: &[String]) -> CommandResult<ReaderChapter> {
+	let chapter = match client.chapter().get().chapter_id(id).includes(vec![ReferenceExpansionResource::Manga]).build()?.send().await {
+		Ok(v) => v.data,
+		Err(why) => {
+			error!("Error getting MangaDex chapter {}: {:?}", id, why);
+			return Err("Couldn't find that chapter on MangaDex".into());
+		}
+	};
+	let (manga_id, manga) = chapter.relationships.iter()
+		.find_map(|related| match &related.attributes {
+			Some(RelatedAttributes::Manga(m)) => Some((related.id, m.clone())),
+			_ => None,
+		})
+		.ok_or("That chapter doesn't belong to a manga")?;
+
+	// Pages can't be censored, so adult chapters are only read where they'd be shown in full
+	if policy.censors(is_adult(&manga)) {
+		return Err("Adult chapters can only be read in NSFW channels".into());
+	}
+	if let Some(external) = &chapter.attributes.external_url {
+		return Err(format!("This chapter is only available on the publisher's site: <{}>", external).into());
+	}
+
+	let at_home = client
+		.at_home()
+		.server()
+		.chapter_id(id)
+		.build()?
+		.send()
+		.await?;
+
+	let (quality, files) = if data_saver {
+		("data-saver", &at_home.chapter.data_saver)
+	} else {
+		("data", &at_home.chapter.data)
+	};
+	let base_url = at_home.base_url.as_str().trim_end_matches('/');
+	let pages = files.iter()
+		.map(|file| format!("{}/{}/{}/{}", base_url, quality, at_home.chapter.hash, file))
+		.collect::<Vec<_>>();
+	if pages.is_empty() {
+		return Err("That chapter has no pages".into());
+	}
+
+	// The reader still works without navigation if the chapter list can't be loaded
+	let aggregate = match client.manga().aggregate().manga_id(&manga_id).translated_language(vec![chapter.attributes.translated_language]).build()?.send().await {
+		Ok(v) => v.volumes,
+		Err(why) => {
+			error!("Error getting chapters of {}: {:?}", manga_id, why);
+			Vec::new()
+		}
+	};
+	// Every upload of a chapter number, the first one is the one MangaDex prefers
+	let mut chapters = aggregate.into_iter()
+		.flat_map(|volume| volume.chapters)
+		.map(|c| (c.chapter, std::iter::once(c.id).chain(c.others).collect::<Vec<_>>()))
+		.collect::<Vec<_>>();
+	chapters.sort_by(|(a, _), (b, _)| {
+		let number = |c: &str| c.parse::<f64>().unwrap_or(0.0);
+		number(a).partial_cmp(&number(b)).unwrap_or(std::cmp::Ordering::Equal)
+	});
+	let position = chapters.iter().position(|(_, ids)| ids.contains(&chapter.id));
+	let previous = position
+		.and_then(|p| p.checked_sub(1))
+		.and_then(|p| chapters.get(p))
+		.and_then(|(_, ids)| ids.first().copied());
+	let next = position
+		.and_then(|p| chapters.get(p + 1))
+		.and_then(|(_, ids)| ids.first().copied());
+
+	Ok(ReaderChapter {
+		title: md_title(&manga, preferred),
+		chapter,
+		pages,
+		previous,
+		next,
+	})
+}
+
+fn reader_embed(reader: &ReaderChapter, page: usize) -> CreateEmbed {
+	let number = reader.chapter.attributes.chapter.clone().unwrap_or_else(|| "Oneshot".to_string());
+
+	let mut embed = CreateEmbed::default();
+	embed.title(format!("Ch. {} - {}", number, reader.title));
+	embed.url(format!("https://mangadex.org/chapter/{}/{}", reader.chapter.id, page + 1));
+	embed.image(&reader.pages[page]);
+	embed.footer(|f| f.text(format!("Page {}/{}", page + 1, reader.pages.len())));
+	embed.color(MANGADEX_COLOR);
+	embed.set_author(mangadex_author());
+	embed
+}
+
+fn reader_buttons(reader: &ReaderChapter, page: usize) -> CreateActionRow {
+	let mut row = CreateActionRow::default();
+	row.add_button(reader_button(READ_PREVIOUS_CHAPTER_ID, "⏮", reader.previous.is_none()));
+	row.add_button(reader_button(READ_PREVIOUS_PAGE_ID, "◀", page == 0));
+	row.add_button(reader_button(READ_NEXT_PAGE_ID, "▶", page + 1 >= reader.pages.len()));
+	row.add_button(reader_button(READ_NEXT_CHAPTER_ID, "⏭", reader.next.is_none()));
+	row
+}
+
+fn reader_button(custom_id: &str, label: &str, disabled: bool) -> CreateButton {
+	let mut button = CreateButton::default();
+	button.custom_id(custom_id);
+	button.label(label);
+	button.style(ButtonStyle::Secondary);
+	button.disabled(disabled);
+	button
+}
+
+/// Series of the group to post chapters of.
 pub struct MDLinkOptions {
-	pub track: Vec<u64>,
-	pub roles: HashMap<u64, u64>
+	/// Manga ids, every series of the group when empty
+	pub track: Vec<String>,
 }
 
+/// An announcement channel for a scanlation group, as picked in `md link`.
 pub struct MDLink {
-	pub guild_id: Option<u64>,
-	pub channel_id: Option<u64>,
-	pub group_id: Option<u64>,
-	pub options: MDLinkOptions
+	pub guild_id: u64,
+	pub channel_id: u64,
+	pub group_id: String,
+	pub group_name: String,
+	pub options: MDLinkOptions,
+}
+
+impl MDLink {
+	/// Saves the link, replacing what the channel followed of the group before.
+	///
+	/// Chapters go through the channel's feeds, the series the group has now are
+	/// remembered so only series it picks up later get announced.
+	fn save(&self, known: &[String]) -> QueryResult<()> {
+		let connection = establish_connection();
+		let guild = self.guild_id as i64;
+		let channel = self.channel_id as i64;
+
+		{
+			use crate::schema::feeds::dsl::*;
+
+			let channel_languages = feeds
+				.filter(channel_id.eq(channel))
+				.select(languages)
+				.first::<String>(&connection)
+				.optional()?
+				.unwrap_or_default();
+
+			diesel::delete(feeds.filter(channel_id.eq(channel)).filter(group_id.eq(&self.group_id)))
+				.execute(&connection)?;
+
+			let series = if self.options.track.is_empty() {
+				vec![None]
+			} else {
+				self.options.track.iter().map(|t| Some(t.as_str())).collect()
+			};
+			let new_feeds = series.into_iter().map(|s| NewFeed {
+				server_id: &guild,
+				channel_id: &channel,
+				group_id: Some(&self.group_id),
+				manga_id: s,
+				languages: &channel_languages,
+			}).collect::<Vec<_>>();
+			diesel::insert_into(feeds)
+				.values(&new_feeds)
+				.execute(&connection)?;
+		}
+
+		{
+			use crate::schema::group_links::dsl::*;
+
+			diesel::delete(group_links.filter(channel_id.eq(channel)).filter(group_id.eq(&self.group_id)))
+				.execute(&connection)?;
+			diesel::insert_into(group_links)
+				.values(NewGroupLink {
+					server_id: &guild,
+					channel_id: &channel,
+					group_id: &self.group_id,
+					group_name: &self.group_name,
+					known_series: &known.join(","),
+				})
+				.execute(&connection)?;
+		}
+		Ok(())
+	}
 }
 
 // Sets a announcement channel for the bot to post updates regarding a mangadex group
@@ -308,63 +1286,258 @@ pub struct MDLink {
 #[tracking("md_link")]
 #[command]
 #[description = "Sets a channel to post updates regarding a mangadex group"]
+#[usage = "[#channel]"]
 #[aliases("link")]
 #[only_in("guilds")]
 #[required_permissions("ADMINISTRATOR")]
-pub async fn link(ctx: &Context, msg: &Message) -> CommandResult {
-
+pub async fn link(ctx: &Context, msg: &Message, args: Args) -> CommandResult {
 	// Get the channel ID from prompt
 	// First get all the channels in the guild
 	let guild_id = msg.guild_id.unwrap();
-	let channels = guild_id.channels(ctx).await.unwrap();
+	let mut channels = guild_id.channels(ctx).await?
+		.into_values()
+		.filter(|c| c.kind == ChannelType::Text)
+		.collect::<Vec<_>>();
+	channels.sort_by_key(|c| c.position);
 
-	let mut channel_options = Vec::<CreateSelectMenuOption>::new();
+	// A mentioned channel skips the menu, which can't list more than 25 channels
+	let (channel_id, mut message) = if let Some(mention) = args.current() {
+		let channel_id = match parse_channel(mention).filter(|id| channels.iter().any(|c| c.id.0 == *id)) {
+			Some(v) => v,
+			None => {
+				msg.reply(ctx, "Mention a text channel of this server to post updates in").await?;
+				return Ok(())
+			}
+		};
+		let message = msg.channel_id.send_message(&ctx.http, |m| m.embed(link_group_prompt)).await?;
+		(channel_id, message)
+	} else {
+		let channel_options = channels.iter()
+			// Discord allows at most 25 options
+			.take(25)
+			.map(|c| CreateSelectMenuOption::new(&c.name, c.id))
+			.collect::<Vec<_>>();
+		let description = if channels.len() > 25 {
+			"Select the channel you want it to send messages to, or use the command again with a #channel mention if it isn't listed"
+		} else {
+			"Select the channel you want it to send messages to"
+		};
 
-	channels.iter().for_each(|channel| {
-		// Return if the channel is not a text channel
-		if channel.1.kind != ChannelType::Text {
-			return;
-		}
+		// Send a message with an embed with a select menu of all the channels
+		let mut message = msg.channel_id.send_message(&ctx.http, |m| {
+			m.embed(|e| {
+				e.title("Select a channel");
+				e.description(description);
+				e.color(MANGADEX_COLOR);
+				e.set_author(mangadex_author());
+				e
+			});
+			m.components(|c| c.add_action_row(select_row(LINK_CHANNEL_MENU_ID, "Select a channel", channel_options, 1)))
+		}).await?;
 
-		let mut option = CreateSelectMenuOption::default();
-		option.label(&channel.1.name);
-		option.value(channel.1.id);
-		
-		channel_options.push(option);
+		let interaction = match await_menu(ctx, msg, &message, LINK_CHANNEL_MENU_ID).await {
+			Some(v) => v,
+			None => return expire_link(ctx, &mut message).await,
+		};
+		let channel_id = match interaction.data.values.first().and_then(|v| v.parse::<u64>().ok()) {
+			Some(v) => v,
+			None => return expire_link(ctx, &mut message).await,
+		};
+		interaction.create_interaction_response(&ctx.http, |r| {
+			r.kind(InteractionResponseType::UpdateMessage)
+				.interaction_response_data(|d| {
+					d.embed(link_group_prompt);
+					d.components(|c| c)
+				})
+		}).await?;
+		(channel_id, message)
+	};
+
+	let reply = match msg.author
+		.await_reply(ctx)
+		.channel_id(msg.channel_id)
+		.timeout(Duration::from_secs(120))
+		.await {
+		Some(v) => v,
+		None => return expire_link(ctx, &mut message).await,
+	};
+	let groups = mangadex::ApiClient::default().search_groups(&reply.content).await.unwrap_or_else(|why| {
+		error!("Error searching MangaDex groups: {:?}", why);
+		Vec::new()
 	});
+	reply.delete(ctx).await.ok();
 
-	// Create CreateSelectorMenu to select the channel
-	let mut menu = CreateSelectMenu::default();
-	menu.custom_id("md_link_channel_select");
-	menu.placeholder("Select a channel");
-	menu.options(|o| {
-			o.set_options(channel_options);
-			o
+	if groups.is_empty() {
+		message.edit(&ctx.http, |m| m.embed(|e| {
+			e.description("No scanlation groups found!");
+			e.color(MANGADEX_COLOR);
+			e.set_author(mangadex_author());
+			e
+		})).await?;
+		return Ok(())
+	}
+
+	let group_options = groups.iter()
+		.map(|g| CreateSelectMenuOption::new(g.attributes.name.chars().take(100).collect::<String>(), &g.id))
+		.collect::<Vec<_>>();
+	message.edit(&ctx.http, |m| {
+		m.embed(|e| {
+			e.title("Select a group");
+			e.description("Select the scanlation group you meant");
+			e.color(MANGADEX_COLOR);
+			e.set_author(mangadex_author());
+			e
+		});
+		m.components(|c| c.add_action_row(select_row(LINK_GROUP_MENU_ID, "Select a group", group_options, 1)))
+	}).await?;
+
+	let interaction = match await_menu(ctx, msg, &message, LINK_GROUP_MENU_ID).await {
+		Some(v) => v,
+		None => return expire_link(ctx, &mut message).await,
+	};
+	let group = match groups.iter().find(|g| interaction.data.values.first() == Some(&g.id)) {
+		Some(v) => v,
+		None => return expire_link(ctx, &mut message).await,
+	};
+
+	let policy = nsfw_policy(ctx, msg).await;
+	// Without the group's series every one of them would be announced as new on the next check
+	let series = match mangadex::ApiClient::default().group_manga(&group.id).await {
+		Ok(v) => v,
+		Err(why) => {
+			error!("Error getting series of MangaDex group {}: {:?}", group.id, why);
+			interaction.create_interaction_response(&ctx.http, |r| {
+				r.kind(InteractionResponseType::UpdateMessage)
+					.interaction_response_data(|d| {
+						d.embed(|e| {
+							e.description("Couldn't get the series of the group from MangaDex, try again later");
+							e.color(MANGADEX_COLOR);
+							e.set_author(mangadex_author());
+							e
+						});
+						d.components(|c| c)
+					})
+			}).await?;
+			return Ok(())
 		}
-	);
-	menu.max_values(1);
+	};
+	let known = series.iter().map(|m| m.id.clone()).collect::<Vec<_>>();
+
+	let mut series_options = vec![CreateSelectMenuOption::new("Every series", LINK_ALL_SERIES)];
+	series_options.extend(series.iter()
+		.filter(|m| !(policy.hides() && mangadex::is_adult_rating(m.content_rating())))
+		.take(24)
+		.map(|m| {
+			let title = m.title().unwrap_or_else(|| m.id.clone());
+			CreateSelectMenuOption::new(title.chars().take(100).collect::<String>(), &m.id)
+		}));
+
+	// Groups without series yet can only be followed as a whole
+	let (track, interaction) = if series_options.len() > 1 {
+		let max_values = series_options.len() as u64;
+		interaction.create_interaction_response(&ctx.http, |r| {
+			r.kind(InteractionResponseType::UpdateMessage)
+				.interaction_response_data(|d| {
+					d.embed(|e| {
+						e.title("Select the series");
+						e.description(format!("Select the series of {} you want to follow", group.attributes.name));
+						e.color(MANGADEX_COLOR);
+						e.set_author(mangadex_author());
+						e
+					});
+					d.components(|c| c.add_action_row(select_row(LINK_SERIES_MENU_ID, "Select series", series_options, max_values)))
+				})
+		}).await?;
+
+		let interaction = match await_menu(ctx, msg, &message, LINK_SERIES_MENU_ID).await {
+			Some(v) => v,
+			None => return expire_link(ctx, &mut message).await,
+		};
+		let values = &interaction.data.values;
+		let track = if values.iter().any(|v| v == LINK_ALL_SERIES) { Vec::new() } else { values.clone() };
+		(track, interaction)
+	} else {
+		(Vec::new(), interaction)
+	};
 
-	// Instance of CreateActionRow
-	let mut action_row = CreateActionRow::default();
-	action_row.add_select_menu(menu);
+	let link = MDLink {
+		guild_id: guild_id.0,
+		channel_id,
+		group_id: group.id.clone(),
+		group_name: group.attributes.name.clone(),
+		options: MDLinkOptions { track },
+	};
+	link.save(&known)?;
+	// Series the channel stopped following might have had follow roles
+	let orphaned = forget_orphaned_follow_roles(&establish_connection());
+	delete_follow_roles(&ctx.http, orphaned).await;
 
+	let following = if link.options.track.is_empty() {
+		"every series".to_string()
+	} else {
+		format!("{} series", link.options.track.len())
+	};
+	interaction.create_interaction_response(&ctx.http, |r| {
+		r.kind(InteractionResponseType::UpdateMessage)
+			.interaction_response_data(|d| {
+				d.embed(|e| {
+					e.title(&link.group_name);
+					e.url(format!("https://mangadex.org/group/{}", link.group_id));
+					e.description(format!("New chapters of {} and new series will be posted in <#{}>", following, link.channel_id));
+					e.color(MANGADEX_COLOR);
+					e.set_author(mangadex_author());
+					e
+				});
+				d.components(|c| c)
+			})
+	}).await?;
+	Ok(())
+}
+
+/// Asks for the name of the group to link once the channel is picked.
+fn link_group_prompt(e: &mut CreateEmbed) -> &mut CreateEmbed {
+	e.title("Select a group");
+	e.description("Send the name of the scanlation group you want to follow");
+	e.color(MANGADEX_COLOR);
+	e.set_author(mangadex_author());
+	e
+}
+
+/// Waits for the author to pick from the given menu on the message.
+async fn await_menu(ctx: &Context, msg: &Message, message: &Message, custom_id: &'static str) -> Option<Arc<MessageComponentInteraction>> {
+	message
+		.await_component_interaction(ctx)
+		.author_id(msg.author.id)
+		.filter(move |i| i.data.custom_id == custom_id)
+		.timeout(Duration::from_secs(120))
+		.await
+}
 
-	// Send a message with an embed with a select menu of all the channels
-	let _ = msg.channel_id.send_message(&ctx.http, |m| {
+/// Leaves a note on the link message once nobody picked anything in time.
+async fn expire_link(ctx: &Context, message: &mut Message) -> CommandResult {
+	message.edit(&ctx.http, |m| {
 		m.embed(|e| {
-			e.title("Select a channel");
-			e.description("Select the channel you want it to send messages to");
-			//e.field("Channels", channel_names.join(", "), true);
+			e.description("Timed out, use the command again to link a group");
+			e.color(MANGADEX_COLOR);
 			e
 		});
-		m.components(|c| {
-			c.add_action_row(action_row);
-			c
-		})
-	}).await;
+		m.components(|c| c)
+	}).await?;
 	Ok(())
 }
 
+fn select_row(custom_id: &str, placeholder: &str, options: Vec<CreateSelectMenuOption>, max_values: u64) -> CreateActionRow {
+	let mut menu = CreateSelectMenu::default();
+	menu.custom_id(custom_id);
+	menu.placeholder(placeholder);
+	menu.options(|o| o.set_options(options));
+	menu.max_values(max_values);
+
+	let mut row = CreateActionRow::default();
+	row.add_select_menu(menu);
+	row
+}
+
 fn fix_description<S: Into<String>>(description: S) -> String {
     let bold = Regex::new(r"\[(|/)b\]").unwrap();
 	let mut desc = description.into();
@@ -376,8 +1549,27 @@ fn fix_description<S: Into<String>>(description: S) -> String {
     desc = spoilers.replace_all(&desc, "").to_string();
     desc = horizontal.replace_all(&desc, "").to_string();
     desc = desc.replace("&quot;", "\"");
-    if desc.len() > 1000 {
-        desc = format!("{}...", &desc[..1000]);
+    if desc.chars().count() > 1000 {
+        desc = format!("{}...", desc.chars().take(1000).collect::<String>());
     }
     desc
 }
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn long_descriptions_are_cut_on_characters() {
+		let description = "ゆるキャン△".repeat(200);
+		let fixed = fix_description(description.as_str());
+		assert_eq!(fixed.chars().count(), 1003);
+		assert!(fixed.ends_with("..."));
+		assert!(description.starts_with(fixed.trim_end_matches("...")));
+	}
+
+	#[test]
+	fn short_descriptions_are_kept() {
+		assert_eq!(fix_description("[b]Camping[/b] &quot;outdoors&quot;"), "**Camping** \"outdoors\"");
+	}
+}
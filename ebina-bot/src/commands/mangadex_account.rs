@@ -0,0 +1,327 @@
+use serenity::framework::standard::{macros::command, Args, CommandResult};
+use serenity::model::prelude::*;
+use serenity::http::Http;
+use serenity::prelude::*;
+
+use chrono::Utc;
+
+use std::collections::HashSet;
+use std::error::Error;
+
+use tracing::{error, info};
+
+use crate::commands::feed::{post_new_chapters, DropTarget};
+use crate::diesel::prelude::*;
+use crate::establish_connection;
+use crate::models::{MangaDexUser, NewMangaDexUser, NewUserFeed};
+use crate::schema::{mangadex_users, user_feeds};
+use crate::utils::crypto::{decrypt_token, encrypt_token, TokenError};
+use crate::utils::language::{preferred_languages, saved_languages, LanguageScope, FALLBACK_LANGUAGES};
+use crate::utils::mangadex::{AccountClient, FeedSource};
+use ebina_macro::tracking;
+
+/// How often follows are imported again, in seconds.
+const SYNC_INTERVAL: i64 = 6 * 60 * 60;
+
+/// Why follows couldn't be imported.
+enum SyncError {
+	/// MangaDex doesn't take the refresh token anymore, the member has to log in again
+	Expired,
+	Other(Box<dyn Error + Send + Sync>),
+}
+
+impl From<reqwest::Error> for SyncError {
+	fn from(why: reqwest::Error) -> Self {
+		SyncError::Other(why.into())
+	}
+}
+
+impl From<diesel::result::Error> for SyncError {
+	fn from(why: diesel::result::Error) -> Self {
+		SyncError::Other(why.into())
+	}
+}
+
+impl From<TokenError> for SyncError {
+	fn from(why: TokenError) -> Self {
+		SyncError::Other(why.into())
+	}
+}
+
+#[tracking("md_login")]
+#[command("login")]
+#[min_args(2)]
+#[usage = "<username> <password>"]
+#[description = "Logs in to MangaDex so `md import` can get the series you follow, only works in DMs. Your password isn't kept."]
+pub async fn mangadex_login(ctx: &Context, msg: &Message, mut args: Args) -> CommandResult {
+	if msg.guild_id.is_some() {
+		if let Err(why) = msg.delete(ctx).await {
+			error!("Error deleting MangaDex login message: {:?}", why);
+		}
+		msg.channel_id.say(ctx, format!("{}, log in in DMs, anyone here could have seen your password. Change it on MangaDex to be safe.", msg.author.mention())).await?;
+		return Ok(())
+	}
+
+	let username = args.single::<String>()?;
+	let password = args.rest().trim();
+
+	let tokens = match AccountClient::default().login(&username, password).await {
+		Ok(v) => v,
+		Err(why) if why.status().map_or(false, |s| s.is_client_error()) => {
+			msg.reply(ctx, "MangaDex didn't accept that username and password").await?;
+			return Ok(())
+		},
+		Err(why) => {
+			error!("Error logging in to MangaDex: {:?}", why);
+			msg.reply(ctx, "Couldn't reach MangaDex, try again later").await?;
+			return Ok(())
+		}
+	};
+
+	let sealed = match encrypt_token(&tokens.refresh) {
+		Ok(v) => v,
+		Err(why) => {
+			error!("Error encrypting MangaDex token: {}", why);
+			msg.reply(ctx, "Logging in to MangaDex isn't set up on this bot").await?;
+			return Ok(())
+		}
+	};
+
+	let connection = establish_connection();
+	diesel::insert_into(mangadex_users::table)
+		.values(NewMangaDexUser {
+			user_id: &(msg.author.id.0 as i64),
+			username: &username,
+			refresh_token: Some(&sealed),
+		})
+		.on_conflict(mangadex_users::user_id)
+		.do_update()
+		.set((mangadex_users::username.eq(&username), mangadex_users::refresh_token.eq(&sealed)))
+		.execute(&connection)?;
+
+	msg.reply(ctx, format!("Logged in as {}, use `md import` to get DMs when series you follow get new chapters", username)).await?;
+	Ok(())
+}
+
+#[tracking("md_logout")]
+#[command("logout")]
+#[description = "Forgets your MangaDex login and stops the DMs about the series you follow"]
+pub async fn mangadex_logout(ctx: &Context, msg: &Message) -> CommandResult {
+	let connection = establish_connection();
+	let user = msg.author.id.0 as i64;
+
+	diesel::delete(user_feeds::table.filter(user_feeds::user_id.eq(user))).execute(&connection)?;
+	let removed = diesel::delete(mangadex_users::table.filter(mangadex_users::user_id.eq(user))).execute(&connection)?;
+
+	if removed == 0 {
+		msg.reply(ctx, "You aren't logged in to MangaDex").await?;
+	} else {
+		msg.reply(ctx, "Logged out of MangaDex, you won't be DMed about new chapters anymore").await?;
+	}
+	Ok(())
+}
+
+#[tracking("md_import")]
+#[command("import")]
+#[description = "Imports the series you follow on MangaDex so you get DMed their new chapters, they're synced again every few hours"]
+pub async fn mangadex_import(ctx: &Context, msg: &Message) -> CommandResult {
+	let connection = establish_connection();
+
+	let account = mangadex_users::table
+		.filter(mangadex_users::user_id.eq(msg.author.id.0 as i64))
+		.first::<MangaDexUser>(&connection)
+		.optional()?;
+	let account = match account {
+		Some(v) => v,
+		None => {
+			msg.reply(ctx, "Log in first with `md login <username> <password>` in DMs").await?;
+			return Ok(())
+		}
+	};
+
+	match sync_follows(&account).await {
+		Ok((total, added, removed)) => {
+			msg.reply(ctx, format!("Following {} series, {} new and {} unfollowed. I'll DM you when they get new chapters", total, added, removed)).await?;
+		},
+		Err(SyncError::Expired) => {
+			msg.reply(ctx, "Your MangaDex login expired, log in again with `md login` in DMs").await?;
+		},
+		Err(SyncError::Other(why)) => {
+			error!("Error importing MangaDex follows of {}: {:?}", msg.author.id, why);
+			msg.reply(ctx, "Couldn't import your follows from MangaDex, try again later").await?;
+		},
+	}
+	Ok(())
+}
+
+/// Replaces the series a member gets DMed about with what they follow on MangaDex.
+///
+/// Returns how many series they follow, how many were added and how many removed.
+async fn sync_follows(account: &MangaDexUser) -> Result<(usize, usize, usize), SyncError> {
+	let connection = establish_connection();
+
+	// `md import` and the feed check can both get here, whoever refreshed last has the token that still works
+	let stored = mangadex_users::table
+		.find(account.id)
+		.select(mangadex_users::refresh_token)
+		.first::<Option<String>>(&connection)?
+		.ok_or(SyncError::Expired)?;
+	// Only the token that was read gets replaced or cleared, one stored in the meantime is newer
+	let current_token = mangadex_users::table
+		.find(account.id)
+		.filter(mangadex_users::refresh_token.eq(stored.clone()));
+
+	let refresh_token = match decrypt_token(&stored) {
+		Ok(v) => v,
+		Err(TokenError::Corrupt) => {
+			diesel::update(current_token)
+				.set(mangadex_users::refresh_token.eq(None::<String>))
+				.execute(&connection)?;
+			return Err(SyncError::Expired);
+		},
+		Err(why) => return Err(why.into()),
+	};
+
+	let client = AccountClient::default();
+	let tokens = match client.refresh(&refresh_token).await {
+		Ok(v) => v,
+		Err(why) if why.status().map_or(false, |s| s.is_client_error()) => {
+			diesel::update(current_token)
+				.set(mangadex_users::refresh_token.eq(None::<String>))
+				.execute(&connection)?;
+			return Err(SyncError::Expired);
+		},
+		Err(why) => return Err(why.into()),
+	};
+	// MangaDex hands out a new refresh token every time, the old one stops working
+	let replaced = diesel::update(current_token)
+		.set(mangadex_users::refresh_token.eq(encrypt_token(&tokens.refresh)?))
+		.execute(&connection)?;
+	if replaced == 0 {
+		info!("MangaDex token of {} was replaced while refreshing, keeping the newer one", account.user_id);
+	}
+
+	let follows = client.follows(&tokens.session).await?;
+	let followed = follows.iter().map(|m| m.id.clone()).collect::<Vec<_>>();
+
+	let existing = user_feeds::table
+		.filter(user_feeds::user_id.eq(account.user_id))
+		.select(user_feeds::manga_id)
+		.load::<String>(&connection)?
+		.into_iter()
+		.collect::<HashSet<_>>();
+
+	let removed = diesel::delete(user_feeds::table
+		.filter(user_feeds::user_id.eq(account.user_id))
+		.filter(user_feeds::manga_id.ne_all(&followed)))
+		.execute(&connection)?;
+
+	let preferred = preferred_languages(None, UserId(account.user_id as u64));
+	let mut added = 0;
+	for manga in follows.iter().filter(|m| !existing.contains(&m.id)) {
+		let title = manga.localized_title(&preferred).unwrap_or_else(|| manga.id.clone());
+		added += diesel::insert_into(user_feeds::table)
+			.values(NewUserFeed {
+				user_id: &account.user_id,
+				manga_id: &manga.id,
+				title: &title,
+			})
+			.on_conflict_do_nothing()
+			.execute(&connection)?;
+	}
+
+	let now = Utc::now().timestamp();
+	diesel::update(mangadex_users::table.find(account.id))
+		.set((
+			mangadex_users::synced_at.eq(now),
+			// Start from now instead of DMing the whole backlog
+			mangadex_users::last_chapter_at.eq(account.last_chapter_at.unwrap_or(now)),
+		))
+		.execute(&connection)?;
+
+	Ok((followed.len(), added, removed))
+}
+
+/// DMs members the new chapters of the series they follow, importing their follows again when it's time.
+pub async fn check_user_feeds(http: &Http) {
+	let connection = establish_connection();
+
+	let accounts = match mangadex_users::table.load::<MangaDexUser>(&connection) {
+		Ok(v) => v,
+		Err(why) => {
+			error!("Error loading MangaDex users: {:?}", why);
+			return;
+		}
+	};
+
+	let now = Utc::now().timestamp();
+	for mut account in accounts {
+		if account.refresh_token.is_some() && account.synced_at.map_or(true, |t| now - t >= SYNC_INTERVAL) {
+			match sync_follows(&account).await {
+				Ok((total, added, removed)) => {
+					info!("Synced MangaDex follows of {}: {} series, {} new, {} unfollowed", account.user_id, total, added, removed);
+					account.last_chapter_at = account.last_chapter_at.or(Some(now));
+				},
+				Err(SyncError::Expired) => {
+					let dm = UserId(account.user_id as u64).create_dm_channel(http).await;
+					if let Ok(channel) = dm {
+						let _ = channel.say(http, "Your MangaDex login expired, log in again with `md login` to keep your follows in sync. Chapters of the series already imported are still sent.").await;
+					}
+				},
+				Err(SyncError::Other(why)) => error!("Error syncing MangaDex follows of {}: {:?}", account.user_id, why),
+			}
+		}
+
+		check_user_feed(http, &account).await;
+	}
+}
+
+async fn check_user_feed(http: &Http, account: &MangaDexUser) {
+	let last = match account.last_chapter_at {
+		Some(v) => v,
+		None => return,
+	};
+
+	let connection = establish_connection();
+
+	let manga_ids = match user_feeds::table
+		.filter(user_feeds::user_id.eq(account.user_id))
+		.select(user_feeds::manga_id)
+		.load::<String>(&connection) {
+		Ok(v) => v,
+		Err(why) => {
+			error!("Error loading followed series of {}: {:?}", account.user_id, why);
+			return;
+		}
+	};
+	if manga_ids.is_empty() {
+		return;
+	}
+
+	let user = UserId(account.user_id as u64);
+	let saved = saved_languages(&connection, LanguageScope::User(user)).unwrap_or_else(|why| {
+		error!("Error loading languages of {}: {:?}", account.user_id, why);
+		Vec::new()
+	});
+	let languages = if saved.is_empty() {
+		FALLBACK_LANGUAGES.to_vec()
+	} else {
+		saved.iter().map(String::as_str).collect()
+	};
+
+	let chunks = manga_ids.chunks(100)
+		.map(|chunk| chunk.iter().map(String::as_str).collect::<Vec<_>>())
+		.collect::<Vec<_>>();
+	let sources = chunks.iter().map(|series| FeedSource::Series(series)).collect();
+	let name = format!("the MangaDex follows of {}", account.user_id);
+	let sent_until = match post_new_chapters(http, sources, &languages, last, DropTarget::User(user), &name).await {
+		Some(v) => v,
+		None => return,
+	};
+
+	if let Err(why) = diesel::update(mangadex_users::table.find(account.id))
+		.set(mangadex_users::last_chapter_at.eq(sent_until))
+		.execute(&connection) {
+		error!("Error updating MangaDex user {}: {:?}", account.id, why);
+	}
+}
@@ -0,0 +1,364 @@
+use std::env;
+
+use serenity::framework::standard::{macros::command, Args, CommandResult};
+use serenity::prelude::*;
+use serenity::model::prelude::*;
+use serenity::builder::CreateEmbedAuthor;
+
+use ebina_anilist::{search, get_viewer, save_media_list_entry, AniListError};
+use ebina_anilist::queries::queries::{Media, MediaList, MediaListStatus, MediaType, SaveMediaListEntryArguments};
+
+use tracing::error;
+
+use crate::commands::anilist::{send_anilist_error, ANI_LIST_COLOR, ANI_LIST_AUTHOR_NAME, ANI_LIST_AUTHOR_URL, ANI_LIST_AUTHOR_ICON_URL};
+use crate::diesel::prelude::*;
+use crate::establish_connection;
+use crate::models::{AniListUser, NewAniListUser};
+use crate::utils::crypto::{decrypt_token, encrypt_token};
+use crate::utils::nsfw::nsfw_policy;
+use crate::utils::options::Options;
+use ebina_macro::tracking;
+
+#[tracking("al_login")]
+#[command("login")]
+#[description = "Log in to Anilist so you can update your lists from Discord"]
+pub async fn anilist_login(ctx: &Context, msg: &Message) -> CommandResult {
+	let client_id = match env::var("ANILIST_CLIENT_ID") {
+		Ok(v) => v,
+		Err(_) => {
+			msg.reply(ctx, "Logging in to Anilist isn't set up on this bot").await?;
+			return Ok(())
+		}
+	};
+
+	let url = format!("https://anilist.co/api/v2/oauth/authorize?client_id={}&response_type=token", client_id);
+	let dm = msg.author.direct_message(ctx, |m| {
+		m.embed(|e| {
+			e.title("Log in to Anilist");
+			e.url(&url);
+			e.description(format!(
+				"1. Open [this link]({}) and approve Ebina\n2. Copy the token Anilist shows you\n3. Send `al token <token>` here\n\nThe token lets Ebina update your lists, you can revoke it in your Anilist settings under Apps or with `al logout`.",
+				url
+			));
+			e.color(ANI_LIST_COLOR);
+			e.author(|a| {
+				a.name(ANI_LIST_AUTHOR_NAME);
+				a.icon_url(ANI_LIST_AUTHOR_ICON_URL);
+				a.url(ANI_LIST_AUTHOR_URL);
+				a
+			});
+			e
+		});
+		m
+	}).await;
+
+	match dm {
+		Ok(_) if msg.guild_id.is_some() => {
+			msg.reply(ctx, "Check your DMs").await?;
+		},
+		Ok(_) => {},
+		Err(why) => {
+			error!("Error sending Anilist login DM: {:?}", why);
+			msg.reply(ctx, "I couldn't DM you, allow DMs from server members and try again").await?;
+		},
+	}
+
+	Ok(())
+}
+
+#[tracking("al_token")]
+#[command("token")]
+#[min_args(1)]
+#[usage = "<token>"]
+#[description = "Finishes logging in with the token from `al login`, only works in DMs"]
+pub async fn anilist_token(ctx: &Context, msg: &Message, args: Args) -> CommandResult {
+	use crate::schema::anilist_users::dsl::*;
+
+	if msg.guild_id.is_some() {
+		if let Err(why) = msg.delete(ctx).await {
+			error!("Error deleting Anilist token message: {:?}", why);
+		}
+		msg.channel_id.say(ctx, format!("{}, send your token in DMs, anyone here could have used it. Revoke it in your Anilist settings under Apps and use `al login` again.", msg.author.mention())).await?;
+		return Ok(())
+	}
+
+	let token = args.rest().trim();
+
+	// Make sure the token works and find out whose it is
+	let viewer = match get_viewer(token).await {
+		Ok(v) => v.viewer,
+		Err(why) => return send_anilist_error(ctx, msg, why).await,
+	};
+	let account = match viewer {
+		Some(v) => v,
+		None => return send_anilist_error(ctx, msg, AniListError::NoData).await,
+	};
+	let sealed = match encrypt_token(token) {
+		Ok(v) => v,
+		Err(why) => {
+			error!("Error encrypting Anilist token: {}", why);
+			msg.reply(ctx, "Logging in to Anilist isn't set up on this bot").await?;
+			return Ok(())
+		}
+	};
+
+	let connection = establish_connection();
+	let new_user = NewAniListUser {
+		user_id: &(msg.author.id.0 as i64),
+		anilist_name: &account.name,
+	};
+	let linked = diesel::insert_into(anilist_users)
+		.values(&new_user)
+		.on_conflict(user_id)
+		.do_update()
+		.set(anilist_name.eq(&account.name))
+		.get_result::<AniListUser>(&connection)?;
+	diesel::update(anilist_users.find(linked.id))
+		.set((anilist_id.eq(account.id), access_token.eq(sealed)))
+		.execute(&connection)?;
+
+	msg.reply(ctx, format!("Logged in as {}, you can now use `al progress`, `al rate` and `al status`", account.name)).await?;
+	Ok(())
+}
+
+#[tracking("al_logout")]
+#[command("logout")]
+#[description = "Forgets your Anilist login, your account stays linked for lookups"]
+pub async fn anilist_logout(ctx: &Context, msg: &Message) -> CommandResult {
+	use crate::schema::anilist_users::dsl::*;
+
+	let connection = establish_connection();
+	diesel::update(anilist_users.filter(user_id.eq(msg.author.id.0 as i64)))
+		.set(access_token.eq(None::<String>))
+		.execute(&connection)?;
+
+	msg.reply(ctx, "Logged out of Anilist, revoke Ebina in your Anilist settings under Apps to be sure the token can't be used").await?;
+	Ok(())
+}
+
+#[tracking("al_progress")]
+#[command("progress")]
+#[min_args(2)]
+#[usage = "<title> <episodes or chapters>"]
+#[example = "Spy x Family 12"]
+#[description = "Sets how many episodes or chapters you've seen of something on your Anilist"]
+pub async fn anilist_progress(ctx: &Context, msg: &Message, args: Args) -> CommandResult {
+	let (title, value) = split_last(args);
+	let progress = match value.parse::<i32>() {
+		Ok(v) if v >= 0 => v,
+		_ => {
+			msg.reply(ctx, "Give the progress as a number at the end, like `al progress Spy x Family 12`").await?;
+			return Ok(())
+		}
+	};
+
+	update_entry(ctx, msg, &title, |media| {
+		// Reaching the last episode or chapter finishes it
+		let total = match media.type_ {
+			Some(MediaType::Manga) => media.chapters,
+			_ => media.episodes,
+		};
+		SaveMediaListEntryArguments {
+			media_id: Some(media.id),
+			status: total.filter(|t| *t == progress).map(|_| MediaListStatus::Completed),
+			progress: Some(progress),
+			score_raw: None,
+		}
+	}).await
+}
+
+#[tracking("al_rate")]
+#[command("rate")]
+#[aliases("score")]
+#[min_args(2)]
+#[usage = "<title> <score out of 100>"]
+#[example = "Spy x Family 85"]
+#[description = "Scores something on your Anilist, it's shown in the score format you've picked there"]
+pub async fn anilist_rate(ctx: &Context, msg: &Message, args: Args) -> CommandResult {
+	let (title, value) = split_last(args);
+	let score = match value.parse::<i32>() {
+		Ok(v) if (0..=100).contains(&v) => v,
+		_ => {
+			msg.reply(ctx, "Give the score as a number from 0 to 100 at the end, like `al rate Spy x Family 85`").await?;
+			return Ok(())
+		}
+	};
+
+	update_entry(ctx, msg, &title, |media| SaveMediaListEntryArguments {
+		media_id: Some(media.id),
+		status: None,
+		progress: None,
+		score_raw: Some(score),
+	}).await
+}
+
+#[tracking("al_status")]
+#[command("status")]
+#[min_args(2)]
+#[usage = "<title> <watching|reading|planning|completed|paused|dropped|repeating>"]
+#[example = "Spy x Family completed"]
+#[description = "Sets the status of something on your Anilist, adding it if it isn't there yet"]
+pub async fn anilist_status(ctx: &Context, msg: &Message, args: Args) -> CommandResult {
+	let (title, value) = split_last(args);
+	let status = match parse_list_status(&value) {
+		Some(v) => v,
+		None => {
+			msg.reply(ctx, "Give the status at the end, one of watching, reading, planning, completed, paused, dropped or repeating").await?;
+			return Ok(())
+		}
+	};
+
+	update_entry(ctx, msg, &title, |_| SaveMediaListEntryArguments {
+		media_id: None,
+		status: Some(status),
+		progress: None,
+		score_raw: None,
+	}).await
+}
+
+/// Lets the user pick the media, then saves the list entry `entry` builds for it.
+async fn update_entry<F>(ctx: &Context, msg: &Message, title: &str, entry: F) -> CommandResult where
+F: FnOnce(&Media) -> SaveMediaListEntryArguments {
+	let token = match access_token(msg.author.id) {
+		Some(v) => v,
+		None => {
+			msg.reply(ctx, "Log in to Anilist with `al login` first").await?;
+			return Ok(())
+		}
+	};
+
+	let ani_list_author = CreateEmbedAuthor::default()
+		.icon_url(ANI_LIST_AUTHOR_ICON_URL)
+		.name(ANI_LIST_AUTHOR_NAME)
+		.url(ANI_LIST_AUTHOR_URL)
+		.to_owned();
+
+	let results = match search(title).await {
+		Ok(v) => v,
+		Err(why) => return send_anilist_error(ctx, msg, why).await,
+	};
+	let policy = nsfw_policy(ctx, msg).await;
+	let media_list = results.page.and_then(|p| p.media).unwrap_or_default()
+		.into_iter()
+		.flatten()
+		.filter(|m| !policy.hides() || !m.is_adult.unwrap_or(false))
+		.collect::<Vec<Media>>();
+
+	if media_list.is_empty() {
+		return send_anilist_error(ctx, msg, AniListError::NoData).await;
+	}
+
+	let options = media_list.iter().map(|m| {
+		let title = m.title.as_ref().and_then(|t| t.user_preferred.clone()).unwrap_or_default();
+		match m.format {
+			Some(format) => format!("{} ({})", title, format),
+			None => title,
+		}
+	}).collect::<Vec<String>>();
+
+	let index = Options::new(ctx, msg)
+		.title("Enter the number corresponding the entry you want to update!")
+		.options(options)
+		.colour(ANI_LIST_COLOR)
+		.author(ani_list_author.clone())
+		.edit()
+		.send()
+		.await;
+	if index.is_none() {
+		return Ok(())
+	}
+	let (index, message_id, channel_id) = index.unwrap();
+	let media = &media_list[index];
+
+	let mut arguments = entry(media);
+	arguments.media_id = Some(media.id);
+	let saved = match save_media_list_entry(&token, arguments).await {
+		Ok(v) => v.save_media_list_entry,
+		Err(why) => return send_anilist_error(ctx, msg, why).await,
+	};
+	let saved = match saved {
+		Some(v) => v,
+		None => return send_anilist_error(ctx, msg, AniListError::NoData).await,
+	};
+
+	let mut message = ctx.http.get_message(channel_id.0, message_id.0).await?;
+	message.edit(&ctx.http, |m| {
+		m.embed(|e| {
+			e.title(media.title.as_ref().and_then(|t| t.user_preferred.clone()).unwrap_or_default());
+			e.url(format!("https://anilist.co/{}/{}", media.type_.map(|t| t.to_string().to_lowercase()).unwrap_or_else(|| "anime".to_string()), media.id));
+			e.description("Updated your list entry");
+			for (name, value) in entry_fields(media, &saved) {
+				e.field(name, value, true);
+			}
+			e.set_author(ani_list_author);
+			e.color(ANI_LIST_COLOR);
+			e
+		});
+		m
+	}).await?;
+
+	Ok(())
+}
+
+/// The status, progress and score of a saved list entry.
+fn entry_fields(media: &Media, entry: &MediaList) -> Vec<(&'static str, String)> {
+	let mut fields = Vec::new();
+	if let Some(status) = entry.status {
+		fields.push(("Status", status.to_string()));
+	}
+	if let Some(progress) = entry.progress {
+		let total = match media.type_ {
+			Some(MediaType::Manga) => media.chapters,
+			_ => media.episodes,
+		};
+		match total {
+			Some(total) => fields.push(("Progress", format!("{}/{}", progress, total))),
+			None => fields.push(("Progress", progress.to_string())),
+		}
+	}
+	if let Some(score) = entry.score.filter(|s| *s > 0.0) {
+		fields.push(("Score", score.to_string()));
+	}
+	fields
+}
+
+/// Splits the arguments into the title and the value after it.
+fn split_last(args: Args) -> (String, String) {
+	let mut words = args.rest().split_whitespace().map(str::to_string).collect::<Vec<String>>();
+	let last = words.pop().unwrap_or_default();
+	(words.join(" "), last)
+}
+
+fn parse_list_status(status: &str) -> Option<MediaListStatus> {
+	match status.to_lowercase().as_str() {
+		"watching" | "reading" | "current" => Some(MediaListStatus::Current),
+		"planning" | "plan" => Some(MediaListStatus::Planning),
+		"completed" | "complete" | "done" => Some(MediaListStatus::Completed),
+		"paused" | "hold" | "on-hold" => Some(MediaListStatus::Paused),
+		"dropped" | "drop" => Some(MediaListStatus::Dropped),
+		"repeating" | "rewatching" | "rereading" => Some(MediaListStatus::Repeating),
+		_ => None,
+	}
+}
+
+/// The Anilist access token of a Discord user, if they've logged in.
+fn access_token(discord_user: UserId) -> Option<String> {
+	use crate::schema::anilist_users::dsl::*;
+
+	let connection = establish_connection();
+	let sealed = anilist_users
+		.filter(user_id.eq(discord_user.0 as i64))
+		.first::<AniListUser>(&connection)
+		.optional()
+		.ok()
+		.flatten()
+		.and_then(|u| u.access_token)?;
+	match decrypt_token(&sealed) {
+		Ok(v) => Some(v),
+		// Tokens that can't be opened anymore count as logged out
+		Err(why) => {
+			error!("Error decrypting Anilist token of {}: {}", discord_user, why);
+			None
+		}
+	}
+}
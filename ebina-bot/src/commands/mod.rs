@@ -1,10 +1,13 @@
 pub mod charades;
 pub mod general;
 pub mod mangadex;
+pub mod mangadex_account;
 pub mod moderation;
 pub mod osu;
 pub mod owner;
 pub mod vndb;
-//pub mod feed;
+pub mod feed;
 pub mod anilist;
+pub mod airing;
+pub mod media_list;
 pub mod macros;
@@ -1,4 +1,5 @@
 use crate::TagsContainer;
+use crate::utils::nsfw::nsfw_policy;
 use crate::utils::options::Options;
 use serenity::framework::standard::{macros::command, Args, CommandResult};
 use serenity::model::prelude::*;
@@ -90,7 +91,12 @@ pub async fn vn(ctx: &Context, msg: &Message, mut args: Args) -> CommandResult {
 
     client = vnns.1;
 
-    let vns = vnns.0;
+    // VNDB only flags the cover, so that's what decides if a VN counts as adult
+    let policy = nsfw_policy(ctx, msg).await;
+    let vns = vnns.0
+        .into_iter()
+        .filter(|vn| !policy.hides() || !vn.image_nsfw.unwrap_or(false))
+        .collect::<Vec<_>>();
 
     if vns.is_empty() {
         msg.reply(&ctx.http, "No results :(").await?;
@@ -115,6 +121,7 @@ pub async fn vn(ctx: &Context, msg: &Message, mut args: Args) -> CommandResult {
 	}
 
     let vn = &vns[index.unwrap().0];
+    let censored = policy.censors(vn.image_nsfw.unwrap_or(false));
 	let mess = &mut ctx.http.get_message(index.unwrap().2.0, index.unwrap().1.0).await?;
 
     let releases = match get_release(vn.id, client).await {
@@ -202,13 +209,17 @@ pub async fn vn(ctx: &Context, msg: &Message, mut args: Args) -> CommandResult {
     mess.edit(&ctx.http, |m| {
 		m.embed(|e| {
 			e.title(vn.title.as_ref().unwrap_or(&"".to_string()));
-			if vn.description.is_some() {
-				e.description(phonere.replace_all(
-					&re.replace_all(vn.description.as_ref().unwrap(), "[$name]($url)"),
-					"\\$1",
-				));
+			if censored {
+				e.description("The cover and description of adult visual novels are hidden outside of NSFW channels.");
+			} else {
+				if vn.description.is_some() {
+					e.description(phonere.replace_all(
+						&re.replace_all(vn.description.as_ref().unwrap(), "[$name]($url)"),
+						"\\$1",
+					));
+				}
+				e.thumbnail(vn.image.as_ref().unwrap_or(&"".to_string()));
 			}
-			e.thumbnail(vn.image.as_ref().unwrap_or(&"".to_string()));
 			e.url(format!("https://vndb.org/v{}", vn.id));
 			if vn.aliases.is_some() {
 				e.field(
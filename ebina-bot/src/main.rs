@@ -44,11 +44,13 @@ use tracing_subscriber::FmtSubscriber;
 use url::Url;
 
 use commands::{
-    anilist::*, charades::*, general::*, mangadex::*, moderation::*, osu::*, owner::*, vndb::*
+    airing::*, anilist::*, charades::*, feed::*, general::*, mangadex::*, mangadex_account::*, media_list::*, moderation::*, osu::*, owner::*, vndb::*
 };
 
 use ebina_types::*;
 
+use utils::follow::handle_follow_button;
+
 embed_migrations!();
 
 pub struct ShardManagerContainer;
@@ -75,6 +77,12 @@ impl TypeMapKey for TagsContainer {
     type Value = HashMap<u64, commands::vndb::VnTagJ>;
 }
 
+pub struct MangaDexTagsContainer;
+
+impl TypeMapKey for MangaDexTagsContainer {
+    type Value = Vec<utils::mangadex::Tag>;
+}
+
 #[help]
 async fn my_help(
     context: &Context,
@@ -91,6 +99,7 @@ async fn my_help(
 
 struct Handler {
 	is_web_running: AtomicBool,
+	is_search_registered: AtomicBool,
 }
 
 #[async_trait]
@@ -99,6 +108,10 @@ impl EventHandler for Handler {
         info!("Connected as {}", ready.user.name);
         let guilds = ctx.cache.guilds().len();
         info!("Guilds in cache: {}", guilds);
+        // Ready fires for every shard and after reconnects, the command only needs registering once
+        if !self.is_search_registered.swap(true, std::sync::atomic::Ordering::Relaxed) {
+            register_search_command(&ctx).await;
+        }
     }
 
     async fn resume(&self, _: Context, _: ResumedEvent) {
@@ -135,23 +148,29 @@ impl EventHandler for Handler {
 	}
 
 	async fn interaction_create(&self, ctx: Context, interaction: Interaction) {
-		if let Interaction::ApplicationCommand(command) = interaction {
-			let content = match command.data.name.as_str() {
-				"ping" => "Pong!",
-				"pong" => "Ping!",
-				_ => "Unknown command",
-			};
-
-			if let Err(why) = command
-				.create_interaction_response(&ctx, |response| {
-					response
-						.kind(InteractionResponseType::ChannelMessageWithSource)
-						.interaction_response_data(|message| message.content(content))
-				})
-				.await
-			{
-				error!("Error sending interaction response: {:?}", why);
-			}
+		match interaction {
+			Interaction::ApplicationCommand(command) => {
+				let content = match command.data.name.as_str() {
+					"ping" => "Pong!",
+					"pong" => "Ping!",
+					MD_SEARCH_SLASH => return search_interaction(&ctx, &command).await,
+					_ => "Unknown command",
+				};
+
+				if let Err(why) = command
+					.create_interaction_response(&ctx, |response| {
+						response
+							.kind(InteractionResponseType::ChannelMessageWithSource)
+							.interaction_response_data(|message| message.content(content))
+					})
+					.await
+				{
+					error!("Error sending interaction response: {:?}", why);
+				}
+			},
+			// Menus and paginators collect their own components, only follow panels outlive them
+			Interaction::MessageComponent(component) => handle_follow_button(&ctx, &component).await,
+			_ => {},
 		}
 	}
 }
@@ -201,7 +220,7 @@ async fn before(_ctx: &Context, _msg: &Message, _command_name: &str) -> bool {
 }
 
 #[group]
-#[commands(ping, quit, vn, invite, weather, wolf, sauce, prefix)]
+#[commands(ping, quit, vn, invite, weather, wolf, sauce, prefix, nsfw)]
 struct General;
 
 #[group]
@@ -221,21 +240,22 @@ struct Osu;
 #[description = "Commands related to moderation"]
 struct Moderation;
 
-/* #[group]
+#[group]
 #[prefix = "feed"]
-#[commands(set, unset, role)]
-struct Feed; */
+#[commands(feed_set, feed_unset, feed_languages, feed_list, feed_role, feed_unrole, feed_follow)]
+#[description = "Posts new MangaDex chapters in a channel"]
+struct Feed;
 
 #[group]
-#[commands(manga, link)]
-//#[sub_groups(feed)]
+#[commands(manga, search, link, read, chapters, covers, language, server_language, mangadex_login, mangadex_logout, mangadex_import)]
+#[sub_groups(feed)]
 #[default_command(manga)]
 #[prefix("md")]
 #[description = "Commands related to MangaDex"]
 struct Mangadex;
 
 #[group]
-#[commands(anilist_search, anilist_manga, anilist_anime, anilist_schedule)]
+#[commands(anilist_search, anilist_manga, anilist_anime, anilist_next, anilist_schedule, anilist_character, anilist_staff, anilist_studio, anilist_user, anilist_link, anilist_unlink, anilist_login, anilist_token, anilist_logout, anilist_progress, anilist_rate, anilist_status, anilist_season, anilist_trending, anilist_top, anilist_random, anilist_subscribe, anilist_unsubscribe, anilist_subscriptions, anilist_role, anilist_unrole, anilist_follow, anilist_digest)]
 #[default_command(anilist_search)]
 #[prefix("al")]
 #[description = "Commands related to Anilist and Anichart"]
@@ -291,7 +311,8 @@ async fn main() {
                     Box::pin(async move {
                         use crate::schema::discord_settings::dsl::*;
 
-                        let guild = msg.guild_id.unwrap().0;
+                        // DMs have no server settings, so they use the default prefixes
+                        let guild = msg.guild_id?.0;
 
                         let data = ctx.data.read().await;
 
@@ -332,6 +353,7 @@ async fn main() {
         .framework(framework)
         .event_handler(Handler {
 			is_web_running: AtomicBool::new(false),
+			is_search_registered: AtomicBool::new(false),
 		})
 		.application_id(application_id)
 		.type_map_insert::<CommandCounter>(HashMap::default())
@@ -380,15 +402,53 @@ async fn main() {
         shard_manager_term.lock().await.shutdown_all().await;
     });
 
-    /* let mut interval = tokio::time::interval(tokio::time::Duration::from_secs(600));
+    let airing_http = client.cache_and_http.http.clone();
+
+    tokio::spawn(async move {
+        let mut interval = tokio::time::interval(tokio::time::Duration::from_secs(300));
+        // Start a little in the past so episodes airing during a restart still get posted
+        let mut last_check = chrono::Utc::now() - chrono::Duration::minutes(10);
+        loop {
+            interval.tick().await;
+            let now = chrono::Utc::now();
+            // A failed check is retried with the same window, the last episode check keeps it from posting twice
+            match check_airing(&airing_http, last_check, now).await {
+                Ok(_) => last_check = now,
+                Err(why) => error!("Error checking airing episodes: {}", why),
+            }
+        }
+    });
+
+    let digest_http = client.cache_and_http.http.clone();
 
     tokio::spawn(async move {
+        let mut interval = tokio::time::interval(tokio::time::Duration::from_secs(60));
         loop {
             interval.tick().await;
-            //mangadex_update_xml(token.clone()).await;
-            check_feeds(token.clone()).await;
+            check_digests(&digest_http).await;
         }
-    }); */
+    });
+
+    let feed_http = client.cache_and_http.http.clone();
+
+    tokio::spawn(async move {
+        migrate_legacy_roles().await;
+        let mut interval = tokio::time::interval(tokio::time::Duration::from_secs(300));
+        loop {
+            interval.tick().await;
+            check_feeds(&feed_http).await;
+        }
+    });
+
+    let user_feed_http = client.cache_and_http.http.clone();
+
+    tokio::spawn(async move {
+        let mut interval = tokio::time::interval(tokio::time::Duration::from_secs(300));
+        loop {
+            interval.tick().await;
+            check_user_feeds(&user_feed_http).await;
+        }
+    });
 
     if let Err(why) = client.start_autosharded().await {
         error!("Client error: {:?}", why);
@@ -31,7 +31,14 @@ pub struct Feed {
     pub id: i32,
     pub server: i64,
     pub channel: i64,
-    pub manga: String,
+    /// MangaDex scanlation group UUID, or a legacy numeric id until the poll job maps it
+    pub group: Option<String>,
+    /// MangaDex manga UUID, on its own or to only follow one series of the group
+    pub manga: Option<String>,
+    /// Comma separated MangaDex language codes, empty for every language
+    pub languages: String,
+    /// Publish time of the newest chapter posted, in unix seconds
+    pub last_chapter_at: Option<i64>,
 }
 
 #[derive(Insertable)]
@@ -39,21 +46,111 @@ pub struct Feed {
 pub struct NewFeed<'a> {
     pub server_id: &'a i64,
     pub channel_id: &'a i64,
-    pub manga_id: &'a String,
+    pub group_id: Option<&'a str>,
+    pub manga_id: Option<&'a str>,
+    pub languages: &'a str,
 }
 
+/// A role pinged when a feed or airing subscription posts.
+#[derive(Queryable, Debug, Clone)]
+pub struct FeedRole {
+    pub id: i32,
+    pub guild_id: i64,
+    pub feed_id: Option<i32>,
+    pub airing_subscription_id: Option<i32>,
+    /// Only ping for this series of a group feed, every series when empty
+    pub manga_id: Option<String>,
+    pub role_id: i64,
+}
+
+#[derive(Insertable)]
+#[table_name = "feed_roles"]
+pub struct NewFeedRole<'a> {
+    pub guild_id: &'a i64,
+    pub feed_id: Option<i32>,
+    pub airing_subscription_id: Option<i32>,
+    pub manga_id: Option<&'a str>,
+    pub role_id: &'a i64,
+}
+
+/// A role the bot made for members to give themselves with a button.
+#[derive(Queryable, Debug, Clone)]
+pub struct FollowRole {
+    pub id: i32,
+    pub guild_id: i64,
+    pub role_id: i64,
+    pub title: String,
+}
+
+#[derive(Insertable)]
+#[table_name = "follow_roles"]
+pub struct NewFollowRole<'a> {
+    pub guild_id: &'a i64,
+    pub role_id: &'a i64,
+    pub title: &'a str,
+}
+
+/// Languages a guild or a user wants MangaDex text in, exactly one of the ids is set.
 #[derive(Queryable, Debug)]
-pub struct Role {
+pub struct LanguagePreference {
     pub id: i32,
-    pub server: i64,
-    pub data: String,
+    pub guild_id: Option<i64>,
+    pub user_id: Option<i64>,
+    /// Comma separated MangaDex language codes, most preferred first
+    pub languages: String,
 }
 
 #[derive(Insertable)]
-#[table_name = "roles"]
-pub struct NewRole<'a> {
-    pub server_id: &'a i64,
-    pub data: &'a String,
+#[table_name = "language_preferences"]
+pub struct NewLanguagePreference<'a> {
+    pub guild_id: Option<&'a i64>,
+    pub user_id: Option<&'a i64>,
+    pub languages: &'a str,
+}
+
+/// A member's MangaDex login, `refresh_token` is `None` once it stopped working.
+#[derive(Queryable, Debug, Clone)]
+pub struct MangaDexUser {
+    pub id: i32,
+    pub user_id: i64,
+    pub username: String,
+    pub refresh_token: Option<String>,
+    /// When the follows were last imported
+    pub synced_at: Option<i64>,
+    pub last_chapter_at: Option<i64>,
+}
+
+#[derive(Insertable)]
+#[table_name = "mangadex_users"]
+pub struct NewMangaDexUser<'a> {
+    pub user_id: &'a i64,
+    pub username: &'a str,
+    pub refresh_token: Option<&'a str>,
+}
+
+/// A series a member follows on MangaDex and gets DMed new chapters of.
+#[derive(Queryable, Debug)]
+pub struct UserFeed {
+    pub id: i32,
+    pub user_id: i64,
+    pub manga_id: String,
+    pub title: String,
+}
+
+#[derive(Insertable)]
+#[table_name = "user_feeds"]
+pub struct NewUserFeed<'a> {
+    pub user_id: &'a i64,
+    pub manga_id: &'a str,
+    pub title: &'a str,
+}
+
+/// A server's old RON map of legacy MangaDex manga ids to roles, moved to `feed_roles` on start.
+#[derive(Queryable, Debug)]
+pub struct Role {
+    pub id: i32,
+    pub server: i64,
+    pub data: String,
 }
 
 #[derive(Queryable, Debug)]
@@ -70,6 +167,98 @@ pub struct NewServerSettings<'a> {
     pub prefix: &'a String,
 }
 
+#[derive(Queryable, Debug)]
+pub struct AniListUser {
+    pub id: i32,
+    pub user_id: i64,
+    pub anilist_name: String,
+    pub anilist_id: Option<i32>,
+    pub access_token: Option<String>,
+}
+
+#[derive(Insertable)]
+#[table_name = "anilist_users"]
+pub struct NewAniListUser<'a> {
+    pub user_id: &'a i64,
+    pub anilist_name: &'a str,
+}
+
+#[derive(Queryable, Debug, Clone)]
+pub struct AiringSubscription {
+    pub id: i32,
+    pub guild_id: Option<i64>,
+    pub channel_id: Option<i64>,
+    pub user_id: Option<i64>,
+    pub media_id: i32,
+    pub media_title: String,
+    pub last_episode: i32,
+}
+
+#[derive(Insertable)]
+#[table_name = "airing_subscriptions"]
+pub struct NewAiringSubscription<'a> {
+    pub guild_id: Option<i64>,
+    pub channel_id: Option<i64>,
+    pub user_id: Option<i64>,
+    pub media_id: &'a i32,
+    pub media_title: &'a str,
+}
+
+#[derive(Queryable, Debug, Clone)]
+pub struct AiringDigest {
+    pub id: i32,
+    pub guild_id: i64,
+    pub channel_id: i64,
+    pub post_hour: i32,
+    pub post_minute: i32,
+    pub timezone: String,
+    pub formats: String,
+    pub exclude_adult: bool,
+    pub last_posted: Option<i64>,
+}
+
+#[derive(Insertable)]
+#[table_name = "airing_digests"]
+pub struct NewAiringDigest<'a> {
+    pub guild_id: &'a i64,
+    pub channel_id: &'a i64,
+}
+
+#[derive(Queryable, Debug)]
+pub struct NsfwSetting {
+    pub id: i32,
+    pub guild_id: i64,
+    pub policy: NsfwPolicy,
+}
+
+#[derive(Insertable)]
+#[table_name = "nsfw_settings"]
+pub struct NewNsfwSetting<'a> {
+    pub guild_id: &'a i64,
+    pub policy: &'a NsfwPolicy,
+}
+
+#[derive(Queryable, Debug)]
+pub struct GroupLink {
+    pub id: i32,
+    pub server_id: i64,
+    pub channel_id: i64,
+    pub group_id: String,
+    pub group_name: String,
+    /// Comma separated ids of the series the group had at the last check
+    pub known_series: String,
+}
+
+#[derive(Insertable)]
+#[table_name = "group_links"]
+pub struct NewGroupLink<'a> {
+    pub server_id: &'a i64,
+    pub channel_id: &'a i64,
+    pub group_id: &'a str,
+    pub group_name: &'a str,
+    pub known_series: &'a str,
+}
+
 #[derive(Debug, PartialEq, DbEnum, Clone)]
 pub enum Categories {
     Anime, // All variants must be fieldless
@@ -84,3 +273,14 @@ pub enum Difficulties {
     Medium,
     Hard,
 }
+
+/// How adult entries are shown outside of NSFW channels.
+#[derive(Debug, PartialEq, DbEnum, Clone, Copy)]
+pub enum NsfwPolicy {
+    /// Leave them out of results
+    Hide,
+    /// Show them without covers or descriptions
+    Censor,
+    /// Show them like everything else
+    Allow,
+}
@@ -1,3 +1,39 @@
+table! {
+    airing_digests (id) {
+        id -> Int4,
+        guild_id -> Int8,
+        channel_id -> Int8,
+        post_hour -> Int4,
+        post_minute -> Int4,
+        timezone -> Text,
+        formats -> Text,
+        exclude_adult -> Bool,
+        last_posted -> Nullable<Int8>,
+    }
+}
+
+table! {
+    airing_subscriptions (id) {
+        id -> Int4,
+        guild_id -> Nullable<Int8>,
+        channel_id -> Nullable<Int8>,
+        user_id -> Nullable<Int8>,
+        media_id -> Int4,
+        media_title -> Text,
+        last_episode -> Int4,
+    }
+}
+
+table! {
+    anilist_users (id) {
+        id -> Int4,
+        user_id -> Int8,
+        anilist_name -> Text,
+        anilist_id -> Nullable<Int4>,
+        access_token -> Nullable<Text>,
+    }
+}
+
 table! {
     use crate::models::{DifficultiesMapping, CategoriesMapping};
     use diesel::sql_types::*;
@@ -21,12 +57,76 @@ table! {
     }
 }
 
+table! {
+    feed_roles (id) {
+        id -> Int4,
+        guild_id -> Int8,
+        feed_id -> Nullable<Int4>,
+        airing_subscription_id -> Nullable<Int4>,
+        manga_id -> Nullable<Text>,
+        role_id -> Int8,
+    }
+}
+
 table! {
     feeds (id) {
         id -> Int4,
         server_id -> Int8,
         channel_id -> Int8,
-        manga_id -> Text,
+        group_id -> Nullable<Text>,
+        manga_id -> Nullable<Text>,
+        languages -> Text,
+        last_chapter_at -> Nullable<Int8>,
+    }
+}
+
+table! {
+    follow_roles (id) {
+        id -> Int4,
+        guild_id -> Int8,
+        role_id -> Int8,
+        title -> Text,
+    }
+}
+
+table! {
+    group_links (id) {
+        id -> Int4,
+        server_id -> Int8,
+        channel_id -> Int8,
+        group_id -> Text,
+        group_name -> Text,
+        known_series -> Text,
+    }
+}
+
+table! {
+    language_preferences (id) {
+        id -> Int4,
+        guild_id -> Nullable<Int8>,
+        user_id -> Nullable<Int8>,
+        languages -> Text,
+    }
+}
+
+table! {
+    mangadex_users (id) {
+        id -> Int4,
+        user_id -> Int8,
+        username -> Text,
+        refresh_token -> Nullable<Text>,
+        synced_at -> Nullable<Int8>,
+        last_chapter_at -> Nullable<Int8>,
+    }
+}
+
+table! {
+    use crate::models::NsfwPolicyMapping;
+    use diesel::sql_types::*;
+    nsfw_settings (id) {
+        id -> Int4,
+        guild_id -> Int8,
+        policy -> NsfwPolicyMapping,
     }
 }
 
@@ -39,4 +139,13 @@ table! {
     }
 }
 
-allow_tables_to_appear_in_same_query!(charades, discord_settings, feeds, roles,);
+table! {
+    user_feeds (id) {
+        id -> Int4,
+        user_id -> Int8,
+        manga_id -> Text,
+        title -> Text,
+    }
+}
+
+allow_tables_to_appear_in_same_query!(airing_digests, airing_subscriptions, anilist_users, charades, discord_settings, feed_roles, feeds, follow_roles, group_links, language_preferences, mangadex_users, nsfw_settings, roles, user_feeds,);
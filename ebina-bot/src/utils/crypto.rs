@@ -0,0 +1,87 @@
+use std::env;
+use std::error::Error;
+use std::fmt;
+
+use aes_gcm::aead::generic_array::GenericArray;
+use aes_gcm::aead::{Aead, NewAead};
+use aes_gcm::Aes256Gcm;
+use rand::RngCore;
+
+/// AES-GCM nonces are 96 bits, a new one is stored in front of every token.
+const NONCE_LENGTH: usize = 12;
+
+/// Why a token couldn't be sealed or opened.
+#[derive(Debug, Clone, PartialEq)]
+pub enum TokenError {
+	/// `TOKEN_KEY` isn't set or isn't 32 base64 encoded bytes
+	Key,
+	/// The stored token wasn't sealed with this key or was changed
+	Corrupt,
+}
+
+impl fmt::Display for TokenError {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		match self {
+			TokenError::Key => write!(f, "TOKEN_KEY must be 32 base64 encoded bytes"),
+			TokenError::Corrupt => write!(f, "the token can't be opened with this key"),
+		}
+	}
+}
+
+impl Error for TokenError {}
+
+fn cipher() -> Result<Aes256Gcm, TokenError> {
+	let key = env::var("TOKEN_KEY").ok()
+		.and_then(|k| base64::decode(k.trim()).ok())
+		.filter(|k| k.len() == 32)
+		.ok_or(TokenError::Key)?;
+	Ok(Aes256Gcm::new(GenericArray::from_slice(&key)))
+}
+
+/// Seals a token for the database, as base64 of the nonce followed by the ciphertext.
+///
+/// Tokens are sealed with AES-256-GCM under the key in `TOKEN_KEY` so a leaked database or
+/// backup doesn't give access to members' accounts. Losing the key only logs everyone out.
+pub fn encrypt_token(token: &str) -> Result<String, TokenError> {
+	let mut nonce = [0u8; NONCE_LENGTH];
+	rand::thread_rng().fill_bytes(&mut nonce);
+
+	let sealed = cipher()?
+		.encrypt(GenericArray::from_slice(&nonce), token.as_bytes())
+		.map_err(|_| TokenError::Corrupt)?;
+	Ok(base64::encode([&nonce[..], &sealed].concat()))
+}
+
+/// Opens a token sealed with [`encrypt_token`].
+pub fn decrypt_token(stored: &str) -> Result<String, TokenError> {
+	let data = base64::decode(stored).map_err(|_| TokenError::Corrupt)?;
+	if data.len() < NONCE_LENGTH {
+		return Err(TokenError::Corrupt);
+	}
+	let (nonce, sealed) = data.split_at(NONCE_LENGTH);
+
+	let token = cipher()?
+		.decrypt(GenericArray::from_slice(nonce), sealed)
+		.map_err(|_| TokenError::Corrupt)?;
+	String::from_utf8(token).map_err(|_| TokenError::Corrupt)
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn tokens_round_trip_and_reject_tampering() {
+		env::set_var("TOKEN_KEY", base64::encode([7u8; 32]));
+
+		let sealed = encrypt_token("secret token").unwrap();
+		assert_ne!(sealed, encrypt_token("secret token").unwrap(), "every token gets its own nonce");
+		assert_eq!(decrypt_token(&sealed).unwrap(), "secret token");
+
+		let mut data = base64::decode(&sealed).unwrap();
+		let last = data.len() - 1;
+		data[last] ^= 1;
+		assert_eq!(decrypt_token(&base64::encode(data)), Err(TokenError::Corrupt));
+		assert_eq!(decrypt_token("plain token"), Err(TokenError::Corrupt));
+	}
+}
@@ -0,0 +1,174 @@
+use std::fmt;
+
+/// Arguments in the style of `--status ongoing --tag "Slice of Life" title words`.
+///
+/// Flags can be given more than once, values with spaces need quotes and
+/// everything that isn't a flag or its value ends up in [`Flags::rest`].
+#[derive(Debug, Default, Clone)]
+pub struct Flags {
+	values: Vec<(String, String)>,
+	rest: Vec<String>,
+}
+
+/// Why the arguments couldn't be parsed.
+#[derive(Debug, Clone, PartialEq)]
+pub enum FlagError {
+	/// A flag that isn't in the known list
+	Unknown(String),
+	/// A flag at the end without a value
+	MissingValue(String),
+}
+
+impl fmt::Display for FlagError {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		match self {
+			FlagError::Unknown(flag) => write!(f, "`--{}` isn't an option here", flag),
+			FlagError::MissingValue(flag) => write!(f, "`--{}` needs a value", flag),
+		}
+	}
+}
+
+impl std::error::Error for FlagError {}
+
+impl Flags {
+	/// Parses `input`, only accepting the flags in `known`.
+	pub fn parse(input: &str, known: &[&str]) -> Result<Self, FlagError> {
+		let mut flags = Flags::default();
+		let mut tokens = tokenize(input).into_iter();
+
+		while let Some(token) = tokens.next() {
+			let flag = match token.strip_prefix("--") {
+				Some(v) if !v.is_empty() => v,
+				_ => {
+					flags.rest.push(token);
+					continue;
+				}
+			};
+
+			let (name, value) = match flag.split_once('=') {
+				Some((name, value)) => (name.to_lowercase(), value.to_string()),
+				None => {
+					let name = flag.to_lowercase();
+					match tokens.next() {
+						Some(value) => (name, value),
+						None => return Err(FlagError::MissingValue(name)),
+					}
+				}
+			};
+			if !known.contains(&name.as_str()) {
+				return Err(FlagError::Unknown(name));
+			}
+			flags.values.push((name, value));
+		}
+		Ok(flags)
+	}
+
+	/// Builds flags from name and value pairs, like the options of a slash command.
+	pub fn from_pairs<I>(pairs: I) -> Self where
+	I: IntoIterator<Item = (String, String)> {
+		let mut flags = Flags::default();
+		for (name, value) in pairs {
+			flags.values.push((name.to_lowercase(), value));
+		}
+		flags
+	}
+
+	/// The last value given for a flag.
+	pub fn get(&self, name: &str) -> Option<&str> {
+		self.values.iter().rev().find(|(n, _)| n == name).map(|(_, v)| v.as_str())
+	}
+
+	/// Every value given for a flag, comma separated values count as separate values.
+	pub fn all(&self, name: &str) -> Vec<&str> {
+		self.values.iter()
+			.filter(|(n, _)| n == name)
+			.flat_map(|(_, v)| v.split(','))
+			.map(str::trim)
+			.filter(|v| !v.is_empty())
+			.collect()
+	}
+
+	/// Everything that wasn't a flag, joined by spaces.
+	pub fn rest(&self) -> String {
+		self.rest.join(" ")
+	}
+
+	/// Sets what wasn't a flag, for flags that didn't come from text.
+	pub fn with_rest<S>(mut self, rest: S) -> Self where
+	S: Into<String> {
+		self.rest = vec![rest.into()];
+		self
+	}
+}
+
+/// Splits on whitespace, keeping text in double quotes together.
+fn tokenize(input: &str) -> Vec<String> {
+	let mut tokens = Vec::new();
+	let mut current = String::new();
+	let mut quoted = false;
+	let mut started = false;
+
+	for c in input.chars() {
+		match c {
+			'"' => {
+				quoted = !quoted;
+				started = true;
+			},
+			c if c.is_whitespace() && !quoted => {
+				if started {
+					tokens.push(std::mem::take(&mut current));
+					started = false;
+				}
+			},
+			c => {
+				current.push(c);
+				started = true;
+			}
+		}
+	}
+	if started {
+		tokens.push(current);
+	}
+	tokens
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	const KNOWN: [&str; 2] = ["tag", "status"];
+
+	#[test]
+	fn quoted_values_stay_together() {
+		let flags = Flags::parse(r#"--tag "Slice of Life" Spy x Family"#, &KNOWN).unwrap();
+		assert_eq!(flags.get("tag"), Some("Slice of Life"));
+		assert_eq!(flags.rest(), "Spy x Family");
+	}
+
+	#[test]
+	fn values_can_follow_an_equals_sign() {
+		let flags = Flags::parse("--status=ongoing --TAG=Action", &KNOWN).unwrap();
+		assert_eq!(flags.get("status"), Some("ongoing"));
+		assert_eq!(flags.get("tag"), Some("Action"));
+		assert_eq!(flags.rest(), "");
+	}
+
+	#[test]
+	fn repeated_flags_keep_every_value() {
+		let flags = Flags::parse("--tag Action --tag Comedy,Drama --status ongoing --status completed", &KNOWN).unwrap();
+		assert_eq!(flags.all("tag"), vec!["Action", "Comedy", "Drama"]);
+		assert_eq!(flags.get("status"), Some("completed"));
+	}
+
+	#[test]
+	fn unknown_flags_are_rejected() {
+		let error = Flags::parse("--genre Action", &KNOWN).unwrap_err();
+		assert_eq!(error, FlagError::Unknown("genre".to_string()));
+	}
+
+	#[test]
+	fn a_flag_at_the_end_needs_a_value() {
+		let error = Flags::parse("Spy x Family --tag", &KNOWN).unwrap_err();
+		assert_eq!(error, FlagError::MissingValue("tag".to_string()));
+	}
+}
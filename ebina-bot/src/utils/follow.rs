@@ -0,0 +1,175 @@
+use std::collections::HashSet;
+
+use serenity::builder::{CreateActionRow, CreateButton};
+use serenity::http::Http;
+use serenity::model::interactions::message_component::{ButtonStyle, MessageComponentInteraction};
+use serenity::model::prelude::*;
+use serenity::prelude::*;
+use serenity::utils::Colour;
+
+use tracing::{error, info};
+
+use crate::diesel::prelude::*;
+use crate::models::{FollowRole, NewFollowRole};
+
+/// Follow buttons carry the role they toggle after this prefix.
+const FOLLOW_BUTTON_PREFIX: &str = "follow_role:";
+
+/// The follow role already pinged for one of the given roles, so a title doesn't get two of them.
+pub fn existing_follow_role(connection: &PgConnection, pinged: &[i64]) -> QueryResult<Option<FollowRole>> {
+	use crate::schema::follow_roles::dsl::*;
+
+	follow_roles
+		.filter(role_id.eq_any(pinged))
+		.first::<FollowRole>(connection)
+		.optional()
+}
+
+/// Makes a mentionable role without permissions for members to follow `name` with.
+///
+/// The bot only keeps track of it once it's saved with [`save_follow_role`], which should
+/// happen in the same transaction as whatever pings it so pruning can't catch it in between.
+pub async fn create_follow_role(http: &Http, guild: GuildId, name: &str) -> serenity::Result<RoleId> {
+	let role_name = name.chars().take(100).collect::<String>();
+	let role = guild.create_role(http, |r| {
+		r.name(&role_name);
+		r.mentionable(true);
+		r.permissions(Permissions::empty());
+		r
+	}).await?;
+	Ok(role.id)
+}
+
+/// Remembers a role made with [`create_follow_role`] so its buttons work and it gets cleaned up.
+pub fn save_follow_role(connection: &PgConnection, guild: GuildId, role: RoleId, name: &str) -> QueryResult<()> {
+	diesel::insert_into(crate::schema::follow_roles::table)
+		.values(NewFollowRole {
+			guild_id: &(guild.0 as i64),
+			role_id: &(role.0 as i64),
+			title: name,
+		})
+		.execute(connection)?;
+	Ok(())
+}
+
+/// Posts the panel members press to follow or unfollow `title`.
+pub async fn send_follow_panel(http: &Http, channel: ChannelId, role: RoleId, title: &str, colour: Colour) -> serenity::Result<Message> {
+	channel.send_message(http, |m| {
+		m.embed(|e| {
+			e.title(format!("Follow {}", title));
+			e.description(format!("Press the button to get <@&{}> and be pinged when something new of {} is posted. Press it again to stop.", role.0, title));
+			e.color(colour);
+			e
+		});
+		m.components(|c| c.add_action_row(follow_row(role)))
+	}).await
+}
+
+fn follow_row(role: RoleId) -> CreateActionRow {
+	let mut button = CreateButton::default();
+	button.custom_id(format!("{}{}", FOLLOW_BUTTON_PREFIX, role.0));
+	button.label("Follow / Unfollow");
+	button.emoji(ReactionType::Unicode("🔔".to_string()));
+	button.style(ButtonStyle::Primary);
+
+	let mut row = CreateActionRow::default();
+	row.add_button(button);
+	row
+}
+
+/// Gives or takes the role of a follow button, panels outlive any collector so they're handled from the event handler.
+pub async fn handle_follow_button(ctx: &Context, component: &MessageComponentInteraction) {
+	let role = match component.data.custom_id.strip_prefix(FOLLOW_BUTTON_PREFIX).and_then(|r| r.parse::<u64>().ok()) {
+		Some(v) => RoleId(v),
+		None => return,
+	};
+
+	let content = match toggle_follow(ctx, component, role).await {
+		Ok(v) => v,
+		Err(why) => {
+			error!("Error toggling follow role {}: {:?}", role, why);
+			"Couldn't change your roles, ask an admin to check my permissions".to_string()
+		}
+	};
+
+	if let Err(why) = component.create_interaction_response(&ctx.http, |r| {
+		r.kind(InteractionResponseType::ChannelMessageWithSource)
+			.interaction_response_data(|d| d.content(content).ephemeral(true))
+	}).await {
+		error!("Error responding to follow button: {:?}", why);
+	}
+}
+
+async fn toggle_follow(ctx: &Context, component: &MessageComponentInteraction, role: RoleId) -> serenity::Result<String> {
+	use crate::schema::follow_roles::dsl::*;
+
+	let mut member = match (component.guild_id, &component.member) {
+		(Some(_), Some(member)) => member.clone(),
+		_ => return Ok("Following only works in servers".to_string()),
+	};
+
+	// Only roles the bot made for following can be handed out, whatever the button says
+	let connection = crate::establish_connection();
+	let follow = follow_roles
+		.filter(guild_id.eq(member.guild_id.0 as i64))
+		.filter(role_id.eq(role.0 as i64))
+		.first::<FollowRole>(&connection)
+		.optional()
+		.unwrap_or_else(|why| {
+			error!("Error loading follow role {}: {:?}", role, why);
+			None
+		});
+	let follow = match follow {
+		Some(v) => v,
+		None => return Ok("Nothing can be followed with this anymore".to_string()),
+	};
+
+	if member.roles.contains(&role) {
+		member.remove_role(&ctx.http, role).await?;
+		Ok(format!("You won't be pinged for {} anymore", follow.title))
+	} else {
+		member.add_role(&ctx.http, role).await?;
+		Ok(format!("You'll be pinged for {} now", follow.title))
+	}
+}
+
+/// Forgets the follow roles nothing pings anymore, after their feed or subscription was removed.
+///
+/// Returns them so they can be deleted with [`delete_follow_roles`] once the connection isn't needed anymore.
+pub fn forget_orphaned_follow_roles(connection: &PgConnection) -> Vec<FollowRole> {
+	let pinged = match crate::schema::feed_roles::table
+		.select(crate::schema::feed_roles::role_id)
+		.load::<i64>(connection) {
+		Ok(v) => v.into_iter().collect::<HashSet<_>>(),
+		Err(why) => {
+			error!("Error loading feed roles: {:?}", why);
+			return Vec::new();
+		}
+	};
+	let follows = match crate::schema::follow_roles::table.load::<FollowRole>(connection) {
+		Ok(v) => v,
+		Err(why) => {
+			error!("Error loading follow roles: {:?}", why);
+			return Vec::new();
+		}
+	};
+
+	let mut orphaned = Vec::new();
+	for follow in follows.into_iter().filter(|f| !pinged.contains(&f.role_id)) {
+		match diesel::delete(crate::schema::follow_roles::table.find(follow.id)).execute(connection) {
+			Ok(_) => orphaned.push(follow),
+			Err(why) => error!("Error removing follow role {}: {:?}", follow.id, why),
+		}
+	}
+	orphaned
+}
+
+/// Deletes follow roles from their guilds.
+pub async fn delete_follow_roles(http: &Http, follows: Vec<FollowRole>) {
+	for follow in follows {
+		// Someone might have deleted the role already, it's forgotten either way
+		if let Err(why) = GuildId(follow.guild_id as u64).delete_role(http, follow.role_id as u64).await {
+			info!("Couldn't delete follow role {}: {:?}", follow.role_id, why);
+		}
+	}
+}
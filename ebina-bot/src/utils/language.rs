@@ -0,0 +1,195 @@
+use serenity::model::prelude::*;
+
+use tracing::error;
+
+use crate::diesel::prelude::*;
+use crate::establish_connection;
+use crate::models::NewLanguagePreference;
+
+/// Tried after the guild's and the user's languages.
+pub const FALLBACK_LANGUAGES: [&str; 1] = ["en"];
+
+/// Who a language preference belongs to.
+#[derive(Debug, Clone, Copy)]
+pub enum LanguageScope {
+	Guild(GuildId),
+	User(UserId),
+}
+
+/// The languages to show MangaDex text in, the guild's first, then the user's, then the fallback.
+pub fn preferred_languages(guild: Option<GuildId>, user: UserId) -> Vec<String> {
+	let connection = establish_connection();
+
+	let mut scopes = Vec::new();
+	if let Some(guild) = guild {
+		scopes.push(LanguageScope::Guild(guild));
+	}
+	scopes.push(LanguageScope::User(user));
+
+	let mut preferred: Vec<String> = Vec::new();
+	for scope in scopes {
+		let saved = match saved_languages(&connection, scope) {
+			Ok(v) => v,
+			Err(why) => {
+				error!("Error loading language preference of {:?}: {:?}", scope, why);
+				Vec::new()
+			}
+		};
+		for code in saved {
+			if !preferred.contains(&code) {
+				preferred.push(code);
+			}
+		}
+	}
+	for code in FALLBACK_LANGUAGES {
+		if !preferred.iter().any(|p| p == code) {
+			preferred.push(code.to_string());
+		}
+	}
+	preferred
+}
+
+/// The languages a guild or user has set, empty when they haven't.
+pub fn saved_languages(connection: &PgConnection, scope: LanguageScope) -> QueryResult<Vec<String>> {
+	use crate::schema::language_preferences::dsl::*;
+
+	let saved = match scope {
+		LanguageScope::Guild(guild) => language_preferences
+			.filter(guild_id.eq(guild.0 as i64))
+			.select(languages)
+			.first::<String>(connection)
+			.optional()?,
+		LanguageScope::User(user) => language_preferences
+			.filter(user_id.eq(user.0 as i64))
+			.select(languages)
+			.first::<String>(connection)
+			.optional()?,
+	};
+	Ok(saved.map(|s| s.split(',').filter(|c| !c.is_empty()).map(str::to_string).collect()).unwrap_or_default())
+}
+
+/// Replaces the languages of a guild or user, an empty list removes the preference.
+pub fn save_languages(connection: &PgConnection, scope: LanguageScope, codes: &[String]) -> QueryResult<()> {
+	use crate::schema::language_preferences::dsl::*;
+
+	let joined = codes.join(",");
+	match scope {
+		LanguageScope::Guild(guild) => {
+			let guild = guild.0 as i64;
+			if codes.is_empty() {
+				diesel::delete(language_preferences.filter(guild_id.eq(guild))).execute(connection)?;
+				return Ok(());
+			}
+			diesel::insert_into(language_preferences)
+				.values(NewLanguagePreference {
+					guild_id: Some(&guild),
+					user_id: None,
+					languages: &joined,
+				})
+				.on_conflict(guild_id)
+				.do_update()
+				.set(languages.eq(&joined))
+				.execute(connection)?;
+		},
+		LanguageScope::User(user) => {
+			let user = user.0 as i64;
+			if codes.is_empty() {
+				diesel::delete(language_preferences.filter(user_id.eq(user))).execute(connection)?;
+				return Ok(());
+			}
+			diesel::insert_into(language_preferences)
+				.values(NewLanguagePreference {
+					guild_id: None,
+					user_id: Some(&user),
+					languages: &joined,
+				})
+				.on_conflict(user_id)
+				.do_update()
+				.set(languages.eq(&joined))
+				.execute(connection)?;
+		},
+	}
+	Ok(())
+}
+
+/// Picks the title of a MangaDex series out of its main and alt titles, see [`resolve`].
+///
+/// Every title shown goes through here, whichever client the series came from.
+pub fn resolve_title<'a>(titles: Vec<(String, &'a str)>, alt_titles: Vec<Vec<(String, &'a str)>>, preferred: &[String], original: Option<&str>) -> Option<&'a str> {
+	let texts = titles.into_iter().chain(alt_titles.into_iter().flatten()).collect::<Vec<_>>();
+	resolve(&texts, preferred, original)
+}
+
+/// Picks a text out of localized texts keyed by language code.
+///
+/// The preferred languages are tried in order, then the romanized and actual
+/// original language, then whatever comes first. Earlier entries win within a
+/// language, so main titles can be listed before alt titles.
+pub fn resolve<'a>(texts: &[(String, &'a str)], preferred: &[String], original: Option<&str>) -> Option<&'a str> {
+	let find = |code: &str| texts.iter()
+		.find(|(c, t)| c == code && !t.is_empty())
+		.map(|(_, t)| *t);
+
+	preferred.iter()
+		.find_map(|code| find(code))
+		.or_else(|| original.and_then(|o| find(&format!("{}-ro", o)).or_else(|| find(o))))
+		.or_else(|| texts.iter().map(|(_, t)| *t).find(|t| !t.is_empty()))
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	fn texts<'a>(pairs: &[(&str, &'a str)]) -> Vec<(String, &'a str)> {
+		pairs.iter().map(|(c, t)| (c.to_string(), *t)).collect()
+	}
+
+	fn preferred(codes: &[&str]) -> Vec<String> {
+		codes.iter().map(|c| c.to_string()).collect()
+	}
+
+	#[test]
+	fn preferred_languages_are_tried_in_order() {
+		let titles = texts(&[("en", "Spy x Family"), ("de", "Spy x Family (DE)"), ("fr", "Spy x Family (FR)")]);
+
+		// The guild's languages come before the user's, which come before the fallback
+		assert_eq!(resolve(&titles, &preferred(&["fr", "de", "en"]), Some("ja")), Some("Spy x Family (FR)"));
+		assert_eq!(resolve(&titles, &preferred(&["es", "de", "en"]), Some("ja")), Some("Spy x Family (DE)"));
+		assert_eq!(resolve(&titles, &preferred(&["es", "it", "en"]), Some("ja")), Some("Spy x Family"));
+	}
+
+	#[test]
+	fn romanized_original_comes_before_the_original() {
+		let titles = texts(&[("ja", "スパイファミリー"), ("ja-ro", "Supai Famirī")]);
+
+		assert_eq!(resolve(&titles, &preferred(&["en"]), Some("ja")), Some("Supai Famirī"));
+		assert_eq!(resolve(&titles[..1], &preferred(&["en"]), Some("ja")), Some("スパイファミリー"));
+	}
+
+	#[test]
+	fn first_text_is_the_last_resort() {
+		let titles = texts(&[("ko", ""), ("zh", "间谍过家家"), ("ja", "スパイファミリー")]);
+
+		assert_eq!(resolve(&titles, &preferred(&["en"]), Some("id")), Some("间谍过家家"));
+		assert_eq!(resolve(&titles, &preferred(&["en"]), None), Some("间谍过家家"));
+		assert_eq!(resolve(&[], &preferred(&["en"]), Some("ja")), None);
+	}
+
+	#[test]
+	fn empty_texts_are_skipped() {
+		let titles = texts(&[("en", ""), ("ja-ro", ""), ("ja", "スパイファミリー")]);
+
+		assert_eq!(resolve(&titles, &preferred(&["en"]), Some("ja")), Some("スパイファミリー"));
+	}
+
+	#[test]
+	fn main_titles_win_over_alt_titles() {
+		let main = texts(&[("en", "Spy x Family")]);
+		let alt = vec![texts(&[("en", "SPY×FAMILY")]), texts(&[("de", "Spy x Family (DE)")])];
+
+		assert_eq!(resolve_title(main.clone(), alt.clone(), &preferred(&["en"]), Some("ja")), Some("Spy x Family"));
+		// A preferred language only found in the alt titles still comes first
+		assert_eq!(resolve_title(main.clone(), alt, &preferred(&["de", "en"]), Some("ja")), Some("Spy x Family (DE)"));
+		assert_eq!(resolve_title(main, Vec::new(), &preferred(&["es"]), Some("ja")), Some("Spy x Family"));
+	}
+}
@@ -0,0 +1,866 @@
+use std::collections::HashMap;
+use std::env;
+
+use chrono::{DateTime, TimeZone, Utc};
+use serde::{Deserialize, Serialize};
+
+use tracing::error;
+
+use crate::utils::language;
+
+pub const API_URL: &str = "https://api.mangadex.org";
+pub const UPLOADS_URL: &str = "https://uploads.mangadex.org";
+
+/// Every content rating, the feed filters adult chapters itself depending on the channel.
+const ALL_CONTENT_RATINGS: [&str; 4] = ["safe", "suggestive", "erotica", "pornographic"];
+
+/// A list response from the MangaDex API.
+#[derive(Deserialize, Debug, Clone)]
+pub struct Collection<T> {
+	pub data: Vec<T>,
+	pub limit: u32,
+	pub offset: u32,
+	pub total: u32,
+}
+
+/// A single entity response from the MangaDex API.
+#[derive(Deserialize, Debug, Clone)]
+pub struct Entity<T> {
+	pub data: T,
+}
+
+#[derive(Deserialize, Debug, Clone)]
+pub struct Chapter {
+	pub id: String,
+	pub attributes: ChapterAttributes,
+	#[serde(default)]
+	pub relationships: Vec<Relationship>,
+}
+
+#[derive(Deserialize, Debug, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct ChapterAttributes {
+	pub volume: Option<String>,
+	pub chapter: Option<String>,
+	pub title: Option<String>,
+	pub translated_language: String,
+	pub external_url: Option<String>,
+	pub publish_at: String,
+	#[serde(default)]
+	pub pages: u32,
+}
+
+#[derive(Deserialize, Debug, Clone)]
+pub struct Relationship {
+	pub id: String,
+	#[serde(rename = "type")]
+	pub type_: String,
+	pub attributes: Option<serde_json::Value>,
+}
+
+#[derive(Deserialize, Debug, Clone)]
+pub struct Group {
+	pub id: String,
+	pub attributes: GroupAttributes,
+}
+
+#[derive(Deserialize, Debug, Clone)]
+pub struct GroupAttributes {
+	pub name: String,
+}
+
+#[derive(Deserialize, Debug, Clone)]
+pub struct Manga {
+	pub id: String,
+	pub attributes: serde_json::Value,
+	#[serde(default)]
+	pub relationships: Vec<Relationship>,
+}
+
+#[derive(Deserialize, Debug, Clone)]
+pub struct Tag {
+	pub id: String,
+	pub attributes: TagAttributes,
+}
+
+#[derive(Deserialize, Debug, Clone)]
+pub struct TagAttributes {
+	pub name: HashMap<String, String>,
+	/// `genre`, `theme`, `format` or `content`
+	pub group: String,
+}
+
+#[derive(Deserialize, Debug, Clone)]
+pub struct Cover {
+	pub id: String,
+	pub attributes: CoverAttributes,
+}
+
+#[derive(Deserialize, Debug, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct CoverAttributes {
+	pub volume: Option<String>,
+	pub file_name: String,
+	pub description: Option<String>,
+	pub locale: Option<String>,
+}
+
+#[derive(Deserialize, Debug, Clone)]
+pub struct Statistics {
+	pub rating: RatingStatistics,
+	#[serde(default)]
+	pub follows: u64,
+}
+
+#[derive(Deserialize, Debug, Clone)]
+pub struct RatingStatistics {
+	pub average: Option<f64>,
+	pub bayesian: Option<f64>,
+	/// Votes for every score from 1 to 10
+	#[serde(default)]
+	pub distribution: HashMap<String, u64>,
+}
+
+#[derive(Deserialize, Debug)]
+struct StatisticsResponse {
+	statistics: HashMap<String, Statistics>,
+}
+
+#[derive(Serialize)]
+struct LegacyMappingRequest<'a> {
+	#[serde(rename = "type")]
+	type_: &'a str,
+	ids: Vec<u64>,
+}
+
+#[derive(Deserialize, Debug)]
+struct LegacyMapping {
+	attributes: LegacyMappingAttributes,
+}
+
+#[derive(Deserialize, Debug)]
+#[serde(rename_all = "camelCase")]
+struct LegacyMappingAttributes {
+	legacy_id: u64,
+	new_id: String,
+}
+
+impl Chapter {
+	/// When the chapter was published, chapters MangaDex sent without a valid date count as published now.
+	pub fn published_at(&self) -> DateTime<Utc> {
+		DateTime::parse_from_rfc3339(&self.attributes.publish_at)
+			.map(|d| d.with_timezone(&Utc))
+			.unwrap_or_else(|_| Utc::now())
+	}
+
+	/// The chapter number as a float so chapters can be ordered, oneshots count as chapter 0.
+	pub fn number(&self) -> f64 {
+		self.attributes.chapter.as_ref().and_then(|c| c.parse::<f64>().ok()).unwrap_or(0.0)
+	}
+
+	pub fn manga(&self) -> Option<&Relationship> {
+		self.relationships.iter().find(|r| r.type_ == "manga")
+	}
+
+	/// Names of the scanlation groups that worked on the chapter.
+	pub fn group_names(&self) -> Vec<String> {
+		self.relationships.iter()
+			.filter(|r| r.type_ == "scanlation_group")
+			.filter_map(|r| r.attributes.as_ref()?.get("name")?.as_str().map(str::to_string))
+			.collect()
+	}
+
+	/// Link to the chapter, either on MangaDex or the official site it's hosted on.
+	pub fn url(&self) -> String {
+		match &self.attributes.external_url {
+			Some(v) => v.clone(),
+			None => format!("https://mangadex.org/chapter/{}", self.id),
+		}
+	}
+}
+
+impl Relationship {
+	/// The title of an expanded manga relationship, preferring English.
+	pub fn manga_title(&self) -> Option<String> {
+		self.attributes.as_ref().and_then(manga_title)
+	}
+
+	/// The content rating of an expanded manga relationship.
+	pub fn content_rating(&self) -> Option<&str> {
+		self.attributes.as_ref()?.get("contentRating")?.as_str()
+	}
+}
+
+impl RatingStatistics {
+	/// How many people rated, summed from the score distribution.
+	pub fn votes(&self) -> u64 {
+		self.distribution.values().sum()
+	}
+}
+
+impl Tag {
+	pub fn name(&self) -> &str {
+		self.attributes.name.get("en")
+			.or_else(|| self.attributes.name.values().next())
+			.map(String::as_str)
+			.unwrap_or_default()
+	}
+}
+
+impl Manga {
+	pub fn title(&self) -> Option<String> {
+		manga_title(&self.attributes)
+	}
+
+	/// The title in the first preferred language that has one, alt titles included.
+	pub fn localized_title(&self, preferred: &[String]) -> Option<String> {
+		localized_title(&self.attributes, preferred)
+	}
+
+	pub fn content_rating(&self) -> Option<&str> {
+		self.attributes.get("contentRating")?.as_str()
+	}
+
+	/// The file name of the main cover, needs the `cover_art` relationship included.
+	pub fn cover_file(&self) -> Option<String> {
+		self.relationships.iter()
+			.find(|r| r.type_ == "cover_art")?
+			.attributes.as_ref()?
+			.get("fileName")?
+			.as_str()
+			.map(str::to_string)
+	}
+}
+
+/// The title of manga attributes in the fallback languages, for posts that aren't for anyone in particular.
+fn manga_title(attributes: &serde_json::Value) -> Option<String> {
+	let fallback = language::FALLBACK_LANGUAGES.iter().map(|c| c.to_string()).collect::<Vec<_>>();
+	localized_title(attributes, &fallback)
+}
+
+fn localized_title(attributes: &serde_json::Value, preferred: &[String]) -> Option<String> {
+	let alt_titles = attributes.get("altTitles")
+		.and_then(|a| a.as_array())
+		.map(|a| a.iter().map(|titles| localized_texts(Some(titles))).collect())
+		.unwrap_or_default();
+	let original = attributes.get("originalLanguage").and_then(|o| o.as_str());
+	language::resolve_title(localized_texts(attributes.get("title")), alt_titles, preferred, original).map(str::to_string)
+}
+
+/// The texts of a `{"en": "...", "ja": "..."}` object keyed by language code.
+fn localized_texts(value: Option<&serde_json::Value>) -> Vec<(String, &str)> {
+	value.and_then(|v| v.as_object())
+		.map(|texts| texts.iter().filter_map(|(code, text)| Some((code.clone(), text.as_str()?))).collect())
+		.unwrap_or_default()
+}
+
+/// Whether titles of a content rating are only shown in NSFW channels, ratings are given by their API name.
+pub fn is_adult_rating(rating: Option<&str>) -> bool {
+	matches!(rating, Some("erotica") | Some("pornographic"))
+}
+
+/// Where API requests go, `MANGADEX_API_URL` can point the bot at a mirror or a local mock.
+pub fn api_url() -> String {
+	env::var("MANGADEX_API_URL").unwrap_or_else(|_| API_URL.to_string())
+}
+
+/// The full size image of a cover.
+pub fn cover_url(manga_id: &str, file_name: &str) -> String {
+	format!("{}/covers/{}/{}", UPLOADS_URL, manga_id, file_name)
+}
+
+/// The 256px thumbnail of a cover.
+pub fn cover_thumbnail(manga_id: &str, file_name: &str) -> String {
+	format!("{}/covers/{}/{}.256.jpg", UPLOADS_URL, manga_id, file_name)
+}
+
+/// Where the chapters of a feed come from.
+pub enum FeedSource<'a> {
+	/// Every chapter a group uploads, optionally only of one series.
+	Group(&'a str, Option<&'a str>),
+	/// Every chapter of a series, whoever uploads it.
+	Manga(&'a str),
+	/// Every chapter of up to 100 series at once.
+	Series(&'a [&'a str]),
+}
+
+/// A manga search, empty fields aren't filtered on.
+#[derive(Debug, Default, Clone)]
+pub struct MangaSearch {
+	pub title: String,
+	/// Tag ids every result has
+	pub included_tags: Vec<String>,
+	/// Tag ids no result has
+	pub excluded_tags: Vec<String>,
+	/// `shounen`, `shoujo`, `josei`, `seinen` or `none`
+	pub demographics: Vec<String>,
+	/// `ongoing`, `completed`, `hiatus` or `cancelled`
+	pub statuses: Vec<String>,
+	pub content_ratings: Vec<String>,
+	pub original_languages: Vec<String>,
+	/// The order parameter, like `order[followedCount]`, and its direction. Relevance when none
+	pub order: Option<(&'static str, &'static str)>,
+}
+
+/// Makes anonymous requests, to MangaDex unless another url is given.
+#[derive(Debug, Clone)]
+pub struct ApiClient {
+	client: reqwest::Client,
+	url: String,
+}
+
+impl ApiClient {
+	pub fn new<S>(url: S) -> Self where
+	S: Into<String> {
+		ApiClient {
+			client: reqwest::Client::new(),
+			url: url.into(),
+		}
+	}
+
+	/// Chapters published after `since`, oldest first, in the given languages or every language when empty.
+	///
+	/// Busy feeds can have more than the 100 chapters MangaDex hands out at a time, those take a few requests.
+	pub async fn chapter_feed(&self, source: FeedSource<'_>, languages: &[&str], since: DateTime<Utc>) -> reqwest::Result<Vec<Chapter>> {
+		let mut base_query = vec![
+			("order[publishAt]", "asc".to_string()),
+			("publishAtSince", since.format("%Y-%m-%dT%H:%M:%S").to_string()),
+			("includeFutureUpdates", "0".to_string()),
+			("includes[]", "manga".to_string()),
+			("includes[]", "scanlation_group".to_string()),
+		];
+		base_query.extend(ALL_CONTENT_RATINGS.iter().map(|r| ("contentRating[]", r.to_string())));
+		base_query.extend(languages.iter().map(|l| ("translatedLanguage[]", l.to_string())));
+
+		let url = match source {
+			FeedSource::Group(group, manga) => {
+				base_query.push(("groups[]", group.to_string()));
+				if let Some(manga) = manga {
+					base_query.push(("manga", manga.to_string()));
+				}
+				format!("{}/chapter", self.url)
+			},
+			FeedSource::Manga(manga) => format!("{}/manga/{}/feed", self.url, manga),
+			FeedSource::Series(manga) => {
+				base_query.extend(manga.iter().map(|m| ("manga[]", m.to_string())));
+				format!("{}/chapter", self.url)
+			},
+		};
+
+		let mut chapters = Vec::new();
+
+		loop {
+			let mut query = vec![
+				("limit", "100".to_string()),
+				("offset", chapters.len().to_string()),
+			];
+			query.extend(base_query.iter().cloned());
+
+			let page = self.client
+				.get(&url)
+				.query(&query)
+				.send()
+				.await?
+				.error_for_status()?
+				.json::<Collection<Chapter>>()
+				.await?;
+			let fetched = page.data.len();
+			chapters.extend(page.data);
+			// MangaDex doesn't page past 10000 entries
+			if fetched == 0 || chapters.len() >= page.total as usize || chapters.len() >= 10_000 {
+				break;
+			}
+		}
+		Ok(chapters)
+	}
+
+	pub async fn get_group(&self, id: &str) -> reqwest::Result<Group> {
+		let group = self.client
+			.get(format!("{}/group/{}", self.url, id))
+			.send()
+			.await?
+			.error_for_status()?
+			.json::<Entity<Group>>()
+			.await?;
+		Ok(group.data)
+	}
+
+	pub async fn get_manga(&self, id: &str) -> reqwest::Result<Manga> {
+		let manga = self.client
+			.get(format!("{}/manga/{}", self.url, id))
+			.query(&[("includes[]", "cover_art")])
+			.send()
+			.await?
+			.error_for_status()?
+			.json::<Entity<Manga>>()
+			.await?;
+		Ok(manga.data)
+	}
+
+	/// Every chapter of a manga in reading order, in the given languages or every language when empty.
+	///
+	/// MangaDex hands out at most 500 chapters at a time, long series take a few requests.
+	pub async fn manga_chapters(&self, manga: &str, languages: &[&str]) -> reqwest::Result<Vec<Chapter>> {
+		let mut chapters = Vec::new();
+
+		loop {
+			let mut query = vec![
+				("limit", "500".to_string()),
+				("offset", chapters.len().to_string()),
+				("order[volume]", "asc".to_string()),
+				("order[chapter]", "asc".to_string()),
+				("includes[]", "scanlation_group".to_string()),
+			];
+			query.extend(ALL_CONTENT_RATINGS.iter().map(|r| ("contentRating[]", r.to_string())));
+			query.extend(languages.iter().map(|l| ("translatedLanguage[]", l.to_string())));
+
+			let page = self.client
+				.get(format!("{}/manga/{}/feed", self.url, manga))
+				.query(&query)
+				.send()
+				.await?
+				.error_for_status()?
+				.json::<Collection<Chapter>>()
+				.await?;
+			let fetched = page.data.len();
+			chapters.extend(page.data);
+			// MangaDex doesn't page past 10000 entries
+			if fetched == 0 || chapters.len() >= page.total as usize || chapters.len() >= 10_000 {
+				break;
+			}
+		}
+		Ok(chapters)
+	}
+
+	/// The chapter with the highest number in any language.
+	pub async fn latest_chapter(&self, manga: &str) -> reqwest::Result<Option<Chapter>> {
+		let mut query = vec![
+			("limit", "1".to_string()),
+			("order[chapter]", "desc".to_string()),
+		];
+		query.extend(ALL_CONTENT_RATINGS.iter().map(|r| ("contentRating[]", r.to_string())));
+
+		let chapters = self.client
+			.get(format!("{}/manga/{}/feed", self.url, manga))
+			.query(&query)
+			.send()
+			.await?
+			.error_for_status()?
+			.json::<Collection<Chapter>>()
+			.await?;
+		Ok(chapters.data.into_iter().next())
+	}
+
+	/// Scanlation groups matching a name, best matches first.
+	pub async fn search_groups(&self, name: &str) -> reqwest::Result<Vec<Group>> {
+		let groups = self.client
+			.get(format!("{}/group", self.url))
+			.query(&[("name", name), ("limit", "25"), ("order[relevance]", "desc")])
+			.send()
+			.await?
+			.error_for_status()?
+			.json::<Collection<Group>>()
+			.await?;
+		Ok(groups.data)
+	}
+
+	/// Up to 100 series a group has uploaded to, newest series first.
+	pub async fn group_manga(&self, group: &str) -> reqwest::Result<Vec<Manga>> {
+		let mut query = vec![
+			("group", group.to_string()),
+			("limit", "100".to_string()),
+			("order[createdAt]", "desc".to_string()),
+			("includes[]", "cover_art".to_string()),
+		];
+		query.extend(ALL_CONTENT_RATINGS.iter().map(|r| ("contentRating[]", r.to_string())));
+
+		let manga = self.client
+			.get(format!("{}/manga", self.url))
+			.query(&query)
+			.send()
+			.await?
+			.error_for_status()?
+			.json::<Collection<Manga>>()
+			.await?;
+		Ok(manga.data)
+	}
+
+	/// Ratings and follows of a manga, `None` when MangaDex has none for it.
+	pub async fn manga_statistics(&self, manga: &str) -> reqwest::Result<Option<Statistics>> {
+		let mut response = self.client
+			.get(format!("{}/statistics/manga/{}", self.url, manga))
+			.send()
+			.await?
+			.error_for_status()?
+			.json::<StatisticsResponse>()
+			.await?;
+		Ok(response.statistics.remove(manga))
+	}
+
+	/// Every cover of a manga, ordered by volume.
+	pub async fn manga_covers(&self, manga: &str) -> reqwest::Result<Vec<Cover>> {
+		let mut covers = Vec::new();
+
+		loop {
+			let page = self.client
+				.get(format!("{}/cover", self.url))
+				.query(&[
+					("manga[]", manga.to_string()),
+					("limit", "100".to_string()),
+					("offset", covers.len().to_string()),
+					("order[volume]", "asc".to_string()),
+				])
+				.send()
+				.await?
+				.error_for_status()?
+				.json::<Collection<Cover>>()
+				.await?;
+
+			let fetched = page.data.len();
+			covers.extend(page.data);
+			if fetched == 0 || covers.len() >= page.total as usize {
+				break;
+			}
+		}
+		Ok(covers)
+	}
+
+	/// Every tag MangaDex has, they rarely change so callers should hold on to them.
+	pub async fn get_tags(&self) -> reqwest::Result<Vec<Tag>> {
+		let tags = self.client
+			.get(format!("{}/manga/tag", self.url))
+			.send()
+			.await?
+			.error_for_status()?
+			.json::<Collection<Tag>>()
+			.await?;
+		Ok(tags.data)
+	}
+
+	/// Manga matching a search, at most `limit` of them.
+	pub async fn search_manga(&self, search: &MangaSearch, limit: u32) -> reqwest::Result<Vec<Manga>> {
+		let mut query = vec![
+			("limit", limit.to_string()),
+			("includes[]", "cover_art".to_string()),
+		];
+		if !search.title.is_empty() {
+			query.push(("title", search.title.clone()));
+		}
+		query.extend(search.included_tags.iter().map(|t| ("includedTags[]", t.clone())));
+		query.extend(search.excluded_tags.iter().map(|t| ("excludedTags[]", t.clone())));
+		query.extend(search.demographics.iter().map(|d| ("publicationDemographic[]", d.clone())));
+		query.extend(search.statuses.iter().map(|s| ("status[]", s.clone())));
+		query.extend(search.original_languages.iter().map(|l| ("originalLanguage[]", l.clone())));
+		if search.content_ratings.is_empty() {
+			query.extend(ALL_CONTENT_RATINGS.iter().map(|r| ("contentRating[]", r.to_string())));
+		} else {
+			query.extend(search.content_ratings.iter().map(|r| ("contentRating[]", r.clone())));
+		}
+		// Relevance only means something when there's a title to be relevant to
+		let order = match search.order {
+			Some(v) => Some(v),
+			None if !search.title.is_empty() => Some(("order[relevance]", "desc")),
+			None => None,
+		};
+		if let Some((field, direction)) = order {
+			query.push((field, direction.to_string()));
+		}
+
+		let manga = self.client
+			.get(format!("{}/manga", self.url))
+			.query(&query)
+			.send()
+			.await?
+			.error_for_status()?
+			.json::<Collection<Manga>>()
+			.await?;
+		Ok(manga.data)
+	}
+
+	/// Maps a group id from the old MangaDex site to its v5 UUID.
+	pub async fn legacy_group_id(&self, id: u64) -> reqwest::Result<Option<String>> {
+		Ok(self.legacy_ids("group", &[id]).await?.remove(&id))
+	}
+
+	/// Maps ids of a type (`manga`, `group`, `chapter`...) from the old MangaDex site to v5 UUIDs, ids MangaDex doesn't know are left out.
+	pub async fn legacy_ids(&self, type_: &str, ids: &[u64]) -> reqwest::Result<HashMap<u64, String>> {
+		if ids.is_empty() {
+			return Ok(HashMap::new());
+		}
+
+		let mapping = self.client
+			.post(format!("{}/legacy/mapping", self.url))
+			.json(&LegacyMappingRequest { type_, ids: ids.to_vec() })
+			.send()
+			.await?
+			.error_for_status()?
+			.json::<Collection<LegacyMapping>>()
+			.await?;
+		Ok(mapping.data.into_iter().map(|m| (m.attributes.legacy_id, m.attributes.new_id)).collect())
+	}
+
+	/// Cover thumbnails of the given manga, keyed by manga id. Missing covers are left out.
+	///
+	/// MangaDex looks up at most 100 manga at a time, longer lists take a request per 100.
+	pub async fn cover_thumbnails(&self, manga_ids: &[&str]) -> HashMap<String, String> {
+		let mut covers = HashMap::new();
+
+		for chunk in manga_ids.chunks(100) {
+			let mut query = vec![
+				("limit", chunk.len().to_string()),
+				("includes[]", "cover_art".to_string()),
+			];
+			query.extend(ALL_CONTENT_RATINGS.iter().map(|r| ("contentRating[]", r.to_string())));
+			query.extend(chunk.iter().map(|id| ("ids[]", id.to_string())));
+
+			let response = async {
+				self.client
+					.get(format!("{}/manga", self.url))
+					.query(&query)
+					.send()
+					.await?
+					.error_for_status()?
+					.json::<Collection<Manga>>()
+					.await
+			}.await;
+
+			// Posts just go without a thumbnail when covers can't be loaded
+			match response {
+				Ok(manga) => covers.extend(manga.data.iter()
+					.filter_map(|m| Some((m.id.clone(), cover_thumbnail(&m.id, &m.cover_file()?))))),
+				Err(why) => error!("Error getting MangaDex covers: {:?}", why),
+			}
+		}
+		covers
+	}
+}
+
+impl Default for ApiClient {
+	fn default() -> Self {
+		ApiClient::new(api_url())
+	}
+}
+
+/// The session and refresh tokens of a logged in account.
+///
+/// Sessions last 15 minutes, refresh tokens about a month and are replaced every refresh.
+#[derive(Deserialize, Debug, Clone)]
+pub struct AuthTokens {
+	pub session: String,
+	pub refresh: String,
+}
+
+#[derive(Deserialize, Debug)]
+struct AuthResponse {
+	token: AuthTokens,
+}
+
+#[derive(Serialize)]
+struct LoginRequest<'a> {
+	username: &'a str,
+	password: &'a str,
+}
+
+#[derive(Serialize)]
+struct RefreshRequest<'a> {
+	token: &'a str,
+}
+
+/// Makes requests as a MangaDex user, to MangaDex unless another url is given.
+#[derive(Debug, Clone)]
+pub struct AccountClient {
+	client: reqwest::Client,
+	url: String,
+}
+
+impl AccountClient {
+	pub fn new<S>(url: S) -> Self where
+	S: Into<String> {
+		AccountClient {
+			client: reqwest::Client::new(),
+			url: url.into(),
+		}
+	}
+
+	/// Logs in with a username and password, the password isn't needed again after this.
+	pub async fn login(&self, username: &str, password: &str) -> reqwest::Result<AuthTokens> {
+		let response = self.client
+			.post(format!("{}/auth/login", self.url))
+			.json(&LoginRequest { username, password })
+			.send()
+			.await?
+			.error_for_status()?
+			.json::<AuthResponse>()
+			.await?;
+		Ok(response.token)
+	}
+
+	/// Trades a refresh token for a new session and refresh token.
+	pub async fn refresh(&self, refresh_token: &str) -> reqwest::Result<AuthTokens> {
+		let response = self.client
+			.post(format!("{}/auth/refresh", self.url))
+			.json(&RefreshRequest { token: refresh_token })
+			.send()
+			.await?
+			.error_for_status()?
+			.json::<AuthResponse>()
+			.await?;
+		Ok(response.token)
+	}
+
+	/// Every manga the account follows.
+	pub async fn follows(&self, session: &str) -> reqwest::Result<Vec<Manga>> {
+		let mut manga = Vec::new();
+
+		loop {
+			let page = self.client
+				.get(format!("{}/user/follows/manga", self.url))
+				.query(&[("limit", "100".to_string()), ("offset", manga.len().to_string())])
+				.bearer_auth(session)
+				.send()
+				.await?
+				.error_for_status()?
+				.json::<Collection<Manga>>()
+				.await?;
+
+			let fetched = page.data.len();
+			manga.extend(page.data);
+			if fetched == 0 || manga.len() >= page.total as usize {
+				break;
+			}
+		}
+		Ok(manga)
+	}
+}
+
+impl Default for AccountClient {
+	fn default() -> Self {
+		AccountClient::new(api_url())
+	}
+}
+
+/// Turns a unix timestamp back into a date for the feed queries.
+pub fn from_timestamp(timestamp: i64) -> DateTime<Utc> {
+	Utc.timestamp(timestamp, 0)
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	use std::io::{Read, Write};
+	use std::net::TcpListener;
+	use std::sync::{Arc, Mutex};
+	use std::thread;
+
+	/// A local stand-in for the MangaDex API answering `METHOD /path?query` with canned JSON, anything else is a 404.
+	struct MockMangaDex {
+		url: String,
+		requests: Arc<Mutex<Vec<String>>>,
+	}
+
+	impl MockMangaDex {
+		fn start(routes: Vec<(&'static str, &'static str)>) -> Self {
+			let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+			let url = format!("http://{}", listener.local_addr().unwrap());
+			let requests = Arc::new(Mutex::new(Vec::new()));
+			let seen = requests.clone();
+
+			thread::spawn(move || {
+				for stream in listener.incoming() {
+					let mut stream = match stream {
+						Ok(v) => v,
+						Err(_) => continue,
+					};
+					let request = read_request(&mut stream);
+					let line = request.lines().next().unwrap_or_default().to_string();
+					let route = routes.iter().find(|(r, _)| line.starts_with(&format!("{} ", r)));
+					let (status, body) = match route {
+						Some((_, body)) => ("200 OK", *body),
+						None => ("404 Not Found", r#"{"result":"error"}"#),
+					};
+					seen.lock().unwrap().push(request);
+					let _ = write!(stream, "HTTP/1.1 {}\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}", status, body.len(), body);
+				}
+			});
+
+			MockMangaDex { url, requests }
+		}
+
+		fn requests(&self) -> Vec<String> {
+			self.requests.lock().unwrap().clone()
+		}
+	}
+
+	/// Reads the head and, going by its length, the body of a request.
+	fn read_request(stream: &mut std::net::TcpStream) -> String {
+		let mut data = Vec::new();
+		let mut buffer = [0; 1024];
+		loop {
+			let read = stream.read(&mut buffer).unwrap_or(0);
+			if read == 0 {
+				break;
+			}
+			data.extend_from_slice(&buffer[..read]);
+
+			let text = String::from_utf8_lossy(&data);
+			if let Some(end) = text.find("\r\n\r\n") {
+				let length = text[..end].lines()
+					.find_map(|l| l.to_lowercase().strip_prefix("content-length:").map(|v| v.trim().parse::<usize>().unwrap_or(0)))
+					.unwrap_or(0);
+				if data.len() >= end + 4 + length {
+					break;
+				}
+			}
+		}
+		String::from_utf8_lossy(&data).into_owned()
+	}
+
+	const TOKENS: &str = r#"{"result":"ok","token":{"session":"session-token","refresh":"refresh-token"}}"#;
+
+	#[tokio::test]
+	async fn login_returns_tokens() {
+		let mock = MockMangaDex::start(vec![("POST /auth/login", TOKENS)]);
+
+		let tokens = AccountClient::new(&mock.url).login("ebina", "hunter2").await.unwrap();
+
+		assert_eq!(tokens.session, "session-token");
+		assert_eq!(tokens.refresh, "refresh-token");
+		assert!(mock.requests()[0].contains(r#"{"username":"ebina","password":"hunter2"}"#));
+	}
+
+	#[tokio::test]
+	async fn refresh_fails_on_client_errors() {
+		let mock = MockMangaDex::start(vec![]);
+
+		let why = AccountClient::new(&mock.url).refresh("expired").await.unwrap_err();
+
+		assert_eq!(why.status().map(|s| s.as_u16()), Some(404));
+	}
+
+	#[tokio::test]
+	async fn follows_are_paged() {
+		let mock = MockMangaDex::start(vec![
+			("GET /user/follows/manga?limit=100&offset=0", r#"{"result":"ok","data":[{"id":"a","attributes":{},"relationships":[]},{"id":"b","attributes":{},"relationships":[]}],"limit":100,"offset":0,"total":3}"#),
+			("GET /user/follows/manga?limit=100&offset=2", r#"{"result":"ok","data":[{"id":"c","attributes":{},"relationships":[]}],"limit":100,"offset":2,"total":3}"#),
+		]);
+
+		let follows = AccountClient::new(&mock.url).follows("session-token").await.unwrap();
+
+		assert_eq!(follows.iter().map(|m| m.id.as_str()).collect::<Vec<_>>(), vec!["a", "b", "c"]);
+		assert!(mock.requests().iter().all(|r| r.to_lowercase().contains("authorization: bearer session-token")));
+	}
+
+	#[tokio::test]
+	async fn chapter_feeds_are_paged() {
+		let mock = MockMangaDex::start(vec![
+			("GET /manga/m/feed?limit=100&offset=0", r#"{"result":"ok","data":[{"id":"a","attributes":{"translatedLanguage":"en","publishAt":"2022-06-01T00:00:00+00:00"}},{"id":"b","attributes":{"translatedLanguage":"en","publishAt":"2022-06-01T00:00:00+00:00"}}],"limit":100,"offset":0,"total":3}"#),
+			("GET /manga/m/feed?limit=100&offset=2", r#"{"result":"ok","data":[{"id":"c","attributes":{"translatedLanguage":"en","publishAt":"2022-06-02T00:00:00+00:00"}}],"limit":100,"offset":2,"total":3}"#),
+		]);
+
+		let chapters = ApiClient::new(&mock.url).chapter_feed(FeedSource::Manga("m"), &["en"], from_timestamp(0)).await.unwrap();
+
+		assert_eq!(chapters.iter().map(|c| c.id.as_str()).collect::<Vec<_>>(), vec!["a", "b", "c"]);
+		assert_eq!(mock.requests().len(), 2);
+	}
+}
@@ -3,7 +3,20 @@ use std::env;
 use serenity::model::channel::Message;
 use tracing::{error, info};
 
+pub mod crypto;
+pub mod flags;
+pub mod follow;
+pub mod language;
+/// Requests for polling feeds and MangaDex accounts, beside the `mangadex_api` client the commands use.
+///
+/// Feed polls ask for chapters with their manga and groups expanded and keep those as raw JSON,
+/// so one request carries everything a post needs, and the clients are given their base url, `MANGADEX_API_URL`
+/// by default, so polling and logging in can run against a local mock. Lookups of a single title go through
+/// `mangadex_api` instead, titles and ratings from either are handled in one place here and in `language`.
+pub mod mangadex;
+pub mod nsfw;
 pub mod options;
+pub mod paginator;
 
 
 /*/// # Description
@@ -0,0 +1,59 @@
+use serenity::model::prelude::*;
+use serenity::prelude::*;
+
+use crate::diesel::prelude::*;
+use crate::establish_connection;
+use crate::models::NsfwPolicy;
+
+use tracing::error;
+
+impl NsfwPolicy {
+	/// Whether adult entries should be left out of results entirely.
+	pub fn hides(&self) -> bool {
+		*self == NsfwPolicy::Hide
+	}
+
+	/// Whether the cover and description of an entry should be left out.
+	pub fn censors(&self, adult: bool) -> bool {
+		adult && *self != NsfwPolicy::Allow
+	}
+}
+
+/// The policy for the channel a message was sent in, NSFW channels and DMs always allow adult entries.
+pub async fn nsfw_policy(ctx: &Context, msg: &Message) -> NsfwPolicy {
+	channel_nsfw_policy(ctx, msg.guild_id, msg.channel_id).await
+}
+
+/// The policy for a channel, for interactions that don't come with a message.
+pub async fn channel_nsfw_policy(ctx: &Context, guild: Option<GuildId>, channel_id: ChannelId) -> NsfwPolicy {
+	let guild = match guild {
+		Some(v) => v,
+		None => return NsfwPolicy::Allow,
+	};
+
+	if let Ok(Channel::Guild(channel)) = channel_id.to_channel(ctx).await {
+		if channel.is_nsfw() {
+			return NsfwPolicy::Allow;
+		}
+	}
+
+	guild_nsfw_policy(guild)
+}
+
+/// The policy a guild has set, hiding adult entries unless told otherwise.
+pub fn guild_nsfw_policy(guild: GuildId) -> NsfwPolicy {
+	use crate::schema::nsfw_settings::dsl::*;
+
+	let connection = establish_connection();
+	match nsfw_settings
+		.filter(guild_id.eq(guild.0 as i64))
+		.select(policy)
+		.first::<NsfwPolicy>(&connection)
+		.optional() {
+		Ok(v) => v.unwrap_or(NsfwPolicy::Hide),
+		Err(why) => {
+			error!("Error loading NSFW setting for {}: {:?}", guild, why);
+			NsfwPolicy::Hide
+		}
+	}
+}
@@ -0,0 +1,134 @@
+use std::time::Duration;
+
+use serenity::{
+	builder::{CreateActionRow, CreateButton, CreateEmbed},
+	client::Context,
+	futures::stream::StreamExt,
+	model::{channel::Message, interactions::{InteractionResponseType, message_component::ButtonStyle}},
+};
+
+const FIRST_ID: &str = "paginator_first";
+const PREVIOUS_ID: &str = "paginator_previous";
+const NEXT_ID: &str = "paginator_next";
+const LAST_ID: &str = "paginator_last";
+
+pub struct Paginator<'a> {
+	ctx: &'a Context,
+	msg: &'a Message,
+	pages: Vec<CreateEmbed>,
+	timeout: Duration,
+}
+
+impl<'a> Paginator<'a> {
+	pub fn new(ctx: &'a Context, msg: &'a Message) -> Self {
+		Paginator {
+			ctx,
+			msg,
+			pages: Vec::new(),
+			timeout: Duration::from_secs(120),
+		}
+	}
+
+	/// Adds a single page
+	/// # Examples
+	/// ```rust
+	/// use ebina_bot::utils::paginator::Paginator;
+	///
+	/// Paginator::new(ctx, msg)
+	///     .page(first_embed)
+	///     .page(second_embed)
+	///     .send()
+	///     .await?;
+	/// ```
+	pub fn page(mut self, page: CreateEmbed) -> Self {
+		self.pages.push(page);
+		self
+	}
+
+	/// Sets all the pages in one go
+	pub fn pages(mut self, pages: Vec<CreateEmbed>) -> Self {
+		self.pages = pages;
+		self
+	}
+
+	/// How long to wait for button presses before removing the buttons
+	pub fn timeout(mut self, timeout: Duration) -> Self {
+		self.timeout = timeout;
+		self
+	}
+
+	/// Sends the first page and lets the author flip through the rest with buttons
+	pub async fn send(mut self) -> serenity::Result<()> {
+		if self.pages.is_empty() {
+			return Ok(());
+		}
+
+		let total = self.pages.len();
+		if total > 1 {
+			for (i, page) in self.pages.iter_mut().enumerate() {
+				page.footer(|f| f.text(format!("Page {}/{}", i + 1, total)));
+			}
+		}
+
+		let mut message = self.msg.channel_id.send_message(&self.ctx.http, |m| {
+			m.set_embed(self.pages[0].clone());
+			if total > 1 {
+				m.components(|c| c.add_action_row(buttons(0, total)));
+			}
+			m
+		}).await?;
+
+		if total == 1 {
+			return Ok(());
+		}
+
+		let mut index = 0usize;
+		let mut interactions = message
+			.await_component_interactions(self.ctx)
+			.author_id(self.msg.author.id)
+			.timeout(self.timeout)
+			.build();
+
+		while let Some(interaction) = interactions.next().await {
+			index = match interaction.data.custom_id.as_str() {
+				FIRST_ID => 0,
+				PREVIOUS_ID => index.saturating_sub(1),
+				NEXT_ID => (index + 1).min(total - 1),
+				LAST_ID => total - 1,
+				_ => continue,
+			};
+
+			let page = self.pages[index].clone();
+			interaction.create_interaction_response(&self.ctx.http, |r| {
+				r.kind(InteractionResponseType::UpdateMessage)
+					.interaction_response_data(|d| {
+						d.set_embed(page);
+						d.components(|c| c.add_action_row(buttons(index, total)))
+					})
+			}).await?;
+		}
+
+		// Remove the buttons once nobody can use them anymore
+		message.edit(&self.ctx.http, |m| m.components(|c| c)).await?;
+		Ok(())
+	}
+}
+
+/// The row of navigation buttons for the given page.
+fn buttons(index: usize, total: usize) -> CreateActionRow {
+	let mut row = CreateActionRow::default();
+	row.add_button(button(FIRST_ID, "⏮", index == 0));
+	row.add_button(button(PREVIOUS_ID, "◀", index == 0));
+	row.add_button(button(NEXT_ID, "▶", index + 1 == total));
+	row.add_button(button(LAST_ID, "⏭", index + 1 == total));
+	row
+}
+
+fn button(custom_id: &str, label: &str, disabled: bool) -> CreateButton {
+	let mut button = CreateButton::default();
+	button.custom_id(custom_id);
+	button.label(label);
+	button.style(ButtonStyle::Secondary);
+	button.disabled(disabled);
+	button
+}